@@ -12,6 +12,7 @@ import type LLMTool from 'api/llms/llmTool.ts';
 import { createError, errorMessage } from 'api/utils/error.ts';
 import { ErrorType, type LLMErrorOptions } from 'api/errors/error.ts';
 import { logger } from 'shared/logger.ts';
+import { isKeychainRef, resolveApiKey } from 'shared/keychain.ts';
 import { ModelRegistryService } from 'api/llms/modelRegistryService.ts';
 import type {
 	LLMCallbacks,
@@ -40,6 +41,8 @@ type AnthropicBlockParamOrArray =
 	>;
 class AnthropicLLM extends LLM {
 	private anthropic!: Anthropic;
+	// Settles once a keychain-stored API key has been read and the client rebuilt with it
+	private anthropicReady: Promise<void> = Promise.resolve();
 
 	constructor(callbacks: LLMCallbacks) {
 		super(callbacks);
@@ -49,11 +52,20 @@ class AnthropicLLM extends LLM {
 	}
 
 	private initializeAnthropicClient() {
+		// The desktop app keeps the key in the OS keychain, leaving a `keychain:` reference in
+		// config.yaml. When it starts bb-api it also passes the key via the environment, which
+		// is used until the keychain has been read (and when it can't be).
+		const configApiKey = this.projectConfig.api?.llmProviders?.anthropic?.apiKey;
 		const clientOptions: ClientOptions = {
-			apiKey: this.projectConfig.api?.llmProviders?.anthropic?.apiKey,
+			apiKey: configApiKey && !isKeychainRef(configApiKey) ? configApiKey : Deno.env.get('ANTHROPIC_API_KEY'),
 		};
-
 		this.anthropic = new Anthropic(clientOptions);
+
+		if (isKeychainRef(configApiKey)) {
+			this.anthropicReady = resolveApiKey(configApiKey, 'ANTHROPIC_API_KEY').then((apiKey) => {
+				if (apiKey) this.anthropic = new Anthropic({ ...clientOptions, apiKey });
+			});
+		}
 	}
 
 	// Helper function to check for file metadata blocks
@@ -610,6 +622,7 @@ class AnthropicLLM extends LLM {
 			let anthropicMessageStream;
 			let anthropicResponse;
 			let anthropicMessage: Anthropic.Messages.Message | undefined;
+			await this.anthropicReady;
 			try {
 				const streamResponse = await this.anthropic.beta.messages
 					.stream(
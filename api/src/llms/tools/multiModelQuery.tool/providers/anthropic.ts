@@ -2,25 +2,28 @@ import type { ModelProvider } from '../types.ts';
 import Anthropic from 'anthropic';
 import type { ClientOptions } from 'anthropic';
 import { logger } from 'shared/logger.ts';
+import { resolveApiKey } from 'shared/keychain.ts';
 
 export class AnthropicProvider implements ModelProvider {
-	private client: Anthropic;
+	private client: Promise<Anthropic>;
 
 	constructor(configApiKey?: string) {
-		const apiKey = configApiKey || Deno.env.get('ANTHROPIC_API_KEY');
-
-		if (!apiKey) {
-			throw new Error('Anthropic API key is not set in the config or environment variables.');
-		}
-
-		const clientOptions: ClientOptions = { apiKey };
-		//logger.info(`LLMToolMultiModelQuery[AnthropicProvider]: creating client with: `, clientOptions);
-		this.client = new Anthropic(clientOptions);
+		// A `keychain:` reference is read from the OS keychain, falling back to the environment
+		this.client = resolveApiKey(configApiKey, 'ANTHROPIC_API_KEY').then((apiKey) => {
+			if (!apiKey) {
+				throw new Error('Anthropic API key is not set in the config, keychain or environment variables.');
+			}
+			const clientOptions: ClientOptions = { apiKey };
+			//logger.info(`LLMToolMultiModelQuery[AnthropicProvider]: creating client with: `, clientOptions);
+			return new Anthropic(clientOptions);
+		});
+		// Errors surface from query(); don't report them as unhandled in the meantime
+		this.client.catch(() => {});
 	}
 
 	async query(model: string, prompt: string): Promise<string> {
 		try {
-			const response = await this.client.messages.create({
+			const response = await (await this.client).messages.create({
 				model: model,
 				messages: [{ role: 'user', content: prompt }],
 				max_tokens: 4000,
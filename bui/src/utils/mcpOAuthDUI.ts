@@ -10,6 +10,7 @@ interface OAuthResult {
 	code: string;
 	state: string;
 	error?: string;
+	windowLabel?: string;
 }
 
 interface OAuthFlowParams {
@@ -18,6 +19,8 @@ interface OAuthFlowParams {
 	window_title?: string;
 	window_width?: number;
 	window_height?: number;
+	server_id?: string;
+	timeout_secs?: number;
 }
 
 /**
@@ -78,6 +81,7 @@ export class McpOAuthDUI extends McpOAuth {
 					window_title: `Sign in to MCP Server: ${this.serverId}`,
 					window_width: 500,
 					window_height: 650,
+					server_id: this.serverId,
 				} as OAuthFlowParams,
 			});
 
@@ -96,6 +100,10 @@ export class McpOAuthDUI extends McpOAuth {
 		console.log('McpOAuthDUI: Setting up OAuth event listener');
 
 		this.eventUnlisten = await listen<OAuthResult>('oauth-result', (event) => {
+			// Ignore results for other in-flight OAuth windows, e.g. a concurrent flow for the same server
+			if (event.payload.windowLabel && this.oauthWindowLabel && event.payload.windowLabel !== this.oauthWindowLabel) {
+				return;
+			}
 			if (event.payload.success && event.payload.serverId === this.serverId) {
 				console.log('McpOAuthDUI: Processing successful OAuth result for server:', this.serverId);
 				this.exchangeCodeForTokens(event.payload.code, event.payload.state)
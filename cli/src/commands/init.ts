@@ -2,6 +2,7 @@ import { Command } from 'cliffy/command';
 import { Confirm, Input, prompt } from 'cliffy/prompt';
 import { colors } from 'cliffy/ansi/colors';
 import { logger } from 'shared/logger.ts';
+import { resolveApiKey } from 'shared/keychain.ts';
 import { basename } from '@std/path';
 import { getProjectId } from 'shared/dataDir.ts';
 import { getDataSourceRegistry } from 'api/dataSources/dataSourceRegistry.ts';
@@ -56,7 +57,8 @@ async function runWizard(
 		'';
 	const defaultAssistantName = existingProjectConfig.myAssistantsName;
 	const existingApiKey = existingProjectConfig.anthropicApiKey;
-	const isApiKeyRequired = !globalConfig.api.llmProviders?.anthropic?.apiKey;
+	// A `keychain:` reference written by the desktop app only counts if the keychain entry exists
+	const isApiKeyRequired = !(await resolveApiKey(globalConfig.api.llmProviders?.anthropic?.apiKey, 'ANTHROPIC_API_KEY'));
 
 	const answers = await prompt([
 		{
@@ -274,8 +276,8 @@ export const init = new Command()
 			const finalProjectConfig = projectId ? await configManager.getProjectConfig(projectId) : undefined;
 
 			if (
-				!finalGlobalConfig.api?.llmProviders?.anthropic?.apiKey &&
-				!finalProjectConfig?.api?.llmProviders?.anthropic?.apiKey
+				!(await resolveApiKey(finalGlobalConfig.api?.llmProviders?.anthropic?.apiKey, 'ANTHROPIC_API_KEY')) &&
+				!(await resolveApiKey(finalProjectConfig?.api?.llmProviders?.anthropic?.apiKey, 'ANTHROPIC_API_KEY'))
 			) {
 				throw new Error(
 					'Anthropic API key is required. Please set it in either user or project configuration.',
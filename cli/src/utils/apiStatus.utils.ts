@@ -60,8 +60,13 @@ export async function getPid(projectId?: string): Promise<number | null> {
 	try {
 		const pidFile = await getPidFilePath(projectId);
 		if (await exists(pidFile)) {
-			const pidString = await Deno.readTextFile(pidFile);
-			return parseInt(pidString.trim(), 10);
+			const pidString = (await Deno.readTextFile(pidFile)).trim();
+			// The DUI writes a JSON payload ({ pid, started_at, port, version })
+			if (pidString.startsWith('{')) {
+				const { pid } = JSON.parse(pidString);
+				return typeof pid === 'number' ? pid : null;
+			}
+			return parseInt(pidString, 10);
 		}
 	} catch (error) {
 		logger.debug(`Error reading PID file: ${error}`);
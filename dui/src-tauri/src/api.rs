@@ -1,5 +1,12 @@
-use crate::commands::api_status::{check_api_status, reconcile_api_pid_state, save_api_pid};
-use crate::config::read_global_config;
+use crate::commands::api_status::{
+    check_api_status, get_pid_info, reconcile_api_pid_state, save_api_pid,
+};
+use crate::commands::pid_file::PidFileInfo;
+use crate::commands::server_status::ensure_port_available;
+use crate::commands::version::binary_version_at;
+use crate::config::{get_install_directory, read_global_config};
+use crate::error::BBError;
+use crate::startup_poll::wait_until_ready;
 use dirs;
 use log::{debug, error, info, warn};
 use serde::Serialize;
@@ -14,12 +21,12 @@ use std::os::windows::ffi::OsStrExt;
 use windows_sys::Win32::Foundation::{CloseHandle, FALSE};
 #[cfg(target_os = "windows")]
 use windows_sys::Win32::System::Threading::{
-    CreateProcessW, OpenProcess, TerminateProcess, CREATE_NO_WINDOW, NORMAL_PRIORITY_CLASS,
-    PROCESS_INFORMATION, STARTUPINFOW,
+    CreateProcessW, OpenProcess, TerminateProcess, CREATE_NO_WINDOW, CREATE_UNICODE_ENVIRONMENT,
+    NORMAL_PRIORITY_CLASS, PROCESS_INFORMATION, STARTUPINFOW,
 };
 
 #[cfg(not(target_os = "windows"))]
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 pub(crate) fn get_default_log_dir() -> Option<PathBuf> {
     #[cfg(target_os = "macos")]
@@ -66,15 +73,51 @@ pub fn get_api_log_path(config: &crate::config::ApiConfig) -> Option<PathBuf> {
 }
 
 pub(crate) fn get_bb_api_path() -> Result<PathBuf, String> {
-    debug!("Starting binary search");
-    let mut checked_paths = Vec::new();
-    let api_name = if cfg!(target_os = "windows") {
+    find_bb_api_path().map_err(|checked_paths| {
+        let error_msg = format!(
+            "Could not find {} in any of these locations:\n{}",
+            api_name(),
+            checked_paths
+                .iter()
+                .map(|p| format!("- {}", p.display()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        error!("Binary search failed: {}", error_msg);
+        error_msg
+    })
+}
+
+fn api_name() -> &'static str {
+    if cfg!(target_os = "windows") {
         "bb-api.exe"
     } else {
         "bb-api"
-    };
+    }
+}
+
+/// Search the install locations for bb-api, returning the paths checked when it isn't found
+pub(crate) fn find_bb_api_path() -> Result<PathBuf, Vec<PathBuf>> {
+    debug!("Starting binary search");
+    let mut checked_paths = Vec::new();
+    let api_name = api_name();
     info!("Looking for {} executable", api_name);
 
+    // Configured install directory first; if the binary isn't there use the default locations
+    if let Some(custom_install) = get_install_directory() {
+        let custom_binary = custom_install.join(api_name);
+        checked_paths.push(custom_binary.clone());
+        debug!(
+            "Checking configured install location: {}",
+            custom_binary.display()
+        );
+        if custom_binary.exists() {
+            info!("Found API executable in configured install location");
+            return Ok(custom_binary);
+        }
+        debug!("Binary not found in configured install location");
+    }
+
     // Try user-specific location first
     if let Some(home) = dirs::home_dir() {
         let user_install = if cfg!(target_os = "windows") {
@@ -136,36 +179,65 @@ pub(crate) fn get_bb_api_path() -> Result<PathBuf, String> {
         return Ok(system_binary);
     }
 
-    let error_msg = format!(
-        "Could not find {} in any of these locations:\n{}",
-        api_name,
-        checked_paths
-            .iter()
-            .map(|p| format!("- {}", p.display()))
-            .collect::<Vec<_>>()
-            .join("\n")
-    );
-    error!("Binary search failed: {}", error_msg);
-    Err(error_msg)
+    Err(checked_paths)
+}
+
+const RESTART_WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const RESTART_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Add the version of the bb-api binary at `path` to the PID file written for `info`, unless
+/// another process has been recorded since
+fn record_api_version(info: PidFileInfo, path: PathBuf) {
+    tauri::async_runtime::spawn(async move {
+        let version = match binary_version_at(&path).await {
+            Ok(Some(version)) => version,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to read API version from {:?}: {}", path, e);
+                return;
+            }
+        };
+        match get_pid_info().await {
+            Ok(Some(current)) if current.pid == info.pid => {
+                let info = PidFileInfo {
+                    version: Some(version),
+                    ..current
+                };
+                if let Err(e) = save_api_pid(info).await {
+                    warn!("Failed to save API version to PID file: {}", e);
+                }
+            }
+            _ => debug!(
+                "API PID {} no longer recorded; not saving its version",
+                info.pid
+            ),
+        }
+    });
 }
 
 #[derive(Debug, Serialize)]
 pub struct ApiStartResult {
     pub success: bool,
     pub pid: Option<i32>,
-    pub error: Option<String>,
+    pub error: Option<BBError>,
     pub requires_settings: bool,
+    /// How long the service took to respond after being started, or how long we waited
+    pub startup_ms: Option<u64>,
 }
 
-fn verify_api_requirements() -> Result<(), String> {
+fn verify_api_requirements() -> Result<(), BBError> {
     // Only check if bb-api binary exists
     get_bb_api_path()
         .map(|_| ())
-        .map_err(|e| format!("BB API binary not found: {}", e))
+        .map_err(|e| BBError::BinaryNotFound(format!("BB API binary not found: {}", e)))
 }
 
 #[cfg(target_os = "windows")]
-fn create_process_windows(executable_path: PathBuf, args: Vec<String>) -> Result<u32, String> {
+fn create_process_windows(
+    executable_path: PathBuf,
+    args: Vec<String>,
+    envs: &[(&str, &str)],
+) -> Result<u32, String> {
     use std::ptr::null_mut;
 
     // Convert the command line to UTF-16 for Windows API
@@ -185,6 +257,25 @@ fn create_process_windows(executable_path: PathBuf, args: Vec<String>) -> Result
 
     let mut process_info: PROCESS_INFORMATION = unsafe { std::mem::zeroed() };
 
+    // The parent's environment plus `envs`, as a block of NUL-terminated KEY=VALUE strings
+    // ending with an extra NUL
+    let mut environment: Vec<u16> = Vec::new();
+    let overridden = |key: &OsStr| {
+        envs.iter()
+            .any(|(name, _)| key.to_string_lossy().eq_ignore_ascii_case(name))
+    };
+    for (key, value) in std::env::vars_os().filter(|(key, _)| !overridden(key)) {
+        environment.extend(key.encode_wide());
+        environment.push('=' as u16);
+        environment.extend(value.encode_wide());
+        environment.push(0);
+    }
+    for (key, value) in envs {
+        environment.extend(OsStr::new(&format!("{}={}", key, value)).encode_wide());
+        environment.push(0);
+    }
+    environment.push(0);
+
     // Create the process with specific flags to hide the window
     let success = unsafe {
         CreateProcessW(
@@ -193,8 +284,8 @@ fn create_process_windows(executable_path: PathBuf, args: Vec<String>) -> Result
             null_mut(), // Process security attributes
             null_mut(), // Thread security attributes
             FALSE,      // Don't inherit handles
-            CREATE_NO_WINDOW | NORMAL_PRIORITY_CLASS,
-            null_mut(), // Use parent's environment
+            CREATE_NO_WINDOW | NORMAL_PRIORITY_CLASS | CREATE_UNICODE_ENVIRONMENT,
+            environment.as_ptr() as *const std::ffi::c_void,
             null_mut(), // Use parent's current directory
             &startup_info,
             &mut process_info,
@@ -216,7 +307,7 @@ fn create_process_windows(executable_path: PathBuf, args: Vec<String>) -> Result
 }
 
 #[tauri::command]
-pub async fn start_api() -> Result<ApiStartResult, String> {
+pub async fn start_api() -> Result<ApiStartResult, BBError> {
     // Verify only that the binary exists
     if let Err(e) = verify_api_requirements() {
         return Ok(ApiStartResult {
@@ -224,31 +315,52 @@ pub async fn start_api() -> Result<ApiStartResult, String> {
             pid: None,
             error: Some(e),
             requires_settings: false,
+            startup_ms: None,
         });
     }
 
     // First reconcile any existing state
-    reconcile_api_pid_state().await?;
+    reconcile_api_pid_state()
+        .await
+        .map_err(BBError::ProcessError)?;
 
     // Check if API is already running
-    let status = check_api_status().await?;
+    let status = check_api_status().await.map_err(BBError::ProcessError)?;
     if status.api_responds {
         return Ok(ApiStartResult {
             success: true,
             pid: status.pid,
             error: None,
             requires_settings: false,
+            startup_ms: None,
         });
     }
 
     // Get API configuration
-    let global_config =
-        read_global_config().map_err(|e| format!("Failed to read config: {}", e))?;
+    let global_config = read_global_config()
+        .map_err(|e| BBError::ConfigError(format!("Failed to read config: {}", e)))?;
     let config = &global_config.api;
+    crate::supervisor::note_service_started("api");
+
+    // Fail fast with a clear message instead of timing out on a port we can't bind
+    if let Err(e) = ensure_port_available(&config.hostname, config.port, "bb-api")
+        .await
+        .map_err(BBError::PortInUse)
+    {
+        error!("{}", e);
+        return Ok(ApiStartResult {
+            success: false,
+            pid: None,
+            error: Some(e),
+            requires_settings: false,
+            startup_ms: None,
+        });
+    }
 
     // Get the full path to the bb-api executable
-    let bb_api_path =
-        get_bb_api_path().map_err(|e| format!("Failed to locate bb-api executable: {}", e))?;
+    let bb_api_path = get_bb_api_path().map_err(|e| {
+        BBError::BinaryNotFound(format!("Failed to locate bb-api executable: {}", e))
+    })?;
 
     info!("Found bb-api executable at: {}", bb_api_path.display());
 
@@ -262,8 +374,8 @@ pub async fn start_api() -> Result<ApiStartResult, String> {
     args.push(config.tls.use_tls.to_string());
 
     // Get log file path using the consolidated logic
-    let log_path =
-        get_api_log_path(config).ok_or_else(|| "Failed to determine log path".to_string())?;
+    let log_path = get_api_log_path(config)
+        .ok_or_else(|| BBError::ConfigError("Failed to determine log path".to_string()))?;
 
     // Ensure log directory exists
     if let Some(parent) = log_path.parent() {
@@ -272,12 +384,20 @@ pub async fn start_api() -> Result<ApiStartResult, String> {
             return Ok(ApiStartResult {
                 success: false,
                 pid: None,
-                error: Some(format!("Failed to create log directory: {}", e)),
+                error: Some(BBError::from_io("Failed to create log directory", &e)),
                 requires_settings: false,
+                startup_ms: None,
             });
         }
     }
 
+    // Roll over an oversized log before the new process appends to it
+    crate::logging::rotate_log_file(
+        &log_path,
+        global_config.dui.service_log_max_bytes,
+        global_config.dui.service_log_max_files,
+    );
+
     // Add log file argument
     args.extend_from_slice(&[
         "--log-file".to_string(),
@@ -290,18 +410,51 @@ pub async fn start_api() -> Result<ApiStartResult, String> {
         args
     );
 
+    // config.yaml only holds a keychain reference, so hand the resolved key to this bb-api
+    // through its own environment. Setting it on the app's process would race other threads.
+    let child_env: Vec<(&str, &str)> = global_config
+        .api
+        .llm_providers
+        .anthropic
+        .as_ref()
+        .and_then(|provider| provider.api_key.as_deref())
+        .filter(|key| !key.is_empty() && crate::keychain::keychain_account(key).is_none())
+        .map(|api_key| (crate::keychain::ANTHROPIC_API_KEY_ENV, api_key))
+        .into_iter()
+        .collect();
+
     // Start the process using platform-specific method
     let process_result = {
         #[cfg(target_os = "windows")]
         {
-            create_process_windows(bb_api_path, args).map(|pid| pid as i32)
+            create_process_windows(bb_api_path.clone(), args, &child_env)
+                .map(|pid| pid as i32)
+                .map_err(BBError::ProcessError)
         }
 
         #[cfg(not(target_os = "windows"))]
         {
-            match Command::new(bb_api_path).args(&args).spawn() {
+            // Send stdout/stderr to a file so startup failures can be shown in the DUI. The
+            // API outlives the DUI, so it must not write into pipes the DUI owns.
+            crate::logging::clear_service_output("api");
+            let output_path = crate::logging::service_output_path(&log_path, "api");
+            let (stdout, stderr) = match crate::logging::capture_service_output("api", &output_path)
+            {
+                Ok((stdout, stderr)) => (Stdio::from(stdout), Stdio::from(stderr)),
+                Err(e) => {
+                    warn!("Failed to open API output file {:?}: {}", output_path, e);
+                    (Stdio::null(), Stdio::null())
+                }
+            };
+            match Command::new(&bb_api_path)
+                .args(&args)
+                .envs(child_env.iter().copied())
+                .stdout(stdout)
+                .stderr(stderr)
+                .spawn()
+            {
                 Ok(child) => Ok(child.id() as i32),
-                Err(e) => Err(format!("Failed to start API process: {}", e)),
+                Err(e) => Err(BBError::from_spawn_io("Failed to start API process", &e)),
             }
         }
     };
@@ -310,66 +463,69 @@ pub async fn start_api() -> Result<ApiStartResult, String> {
         Ok(pid) => {
             info!("API process started with PID: {}", pid);
 
-            // Save the PID immediately
-            if let Err(e) = save_api_pid(pid).await {
+            // Save the PID immediately; the version is added once the binary just started
+            // has reported it
+            let pid_info = PidFileInfo::started_now(pid, config.port, None);
+            if let Err(e) = save_api_pid(pid_info.clone()).await {
                 warn!("Failed to save PID file: {}", e);
             }
-
-            // Give the API a moment to start
-            let max_attempts = 10;
-            for attempt in 1..=max_attempts {
-                std::thread::sleep(std::time::Duration::from_millis(500));
-
-                // Verify the API is responding
-                match check_api_status().await {
-                    Ok(status) if status.api_responds => {
-                        info!("API is responding after {} attempts", attempt);
-                        return Ok(ApiStartResult {
-                            success: true,
-                            pid: Some(pid),
-                            error: None,
-                            requires_settings: false,
-                        });
-                    }
-                    Ok(_) if attempt == max_attempts => {
-                        let error_msg =
-                            "API process started but not responding after multiple attempts";
-                        error!("{}", error_msg);
-                        return Ok(ApiStartResult {
-                            success: false,
-                            pid: Some(pid),
-                            error: Some(error_msg.to_string()),
-                            requires_settings: false,
-                        });
-                    }
-                    Ok(_) => {
-                        debug!(
-                            "API not responding yet, attempt {}/{}",
-                            attempt, max_attempts
-                        );
-                        continue;
+            record_api_version(pid_info, bb_api_path);
+
+            // Give the API a moment to start, backing off so slow machines aren't reported
+            // as failures while polling stays quick for fast starts
+            let timeout = std::time::Duration::from_secs(global_config.dui.service_startup_timeout);
+            let (responds, elapsed) = wait_until_ready(
+                std::time::Duration::from_millis(global_config.dui.api_startup_poll_interval_ms),
+                timeout,
+                |attempt| async move {
+                    // Verify the API is responding
+                    match check_api_status().await {
+                        Ok(status) if status.api_responds => true,
+                        Ok(_) => {
+                            debug!("API not responding yet, attempt {}", attempt);
+                            false
+                        }
+                        Err(e) => {
+                            error!("Error checking API status: {}", e);
+                            false
+                        }
                     }
-                    Err(e) => {
-                        error!("Error checking API status: {}", e);
-                    }
-                }
+                },
+            )
+            .await;
+
+            if responds {
+                info!("API is responding after {}ms", elapsed.as_millis());
+                return Ok(ApiStartResult {
+                    success: true,
+                    pid: Some(pid),
+                    error: None,
+                    requires_settings: false,
+                    startup_ms: Some(elapsed.as_millis() as u64),
+                });
             }
 
+            let error_msg = format!(
+                "API process started but not responding after {}s (dui.serviceStartupTimeout)",
+                elapsed.as_secs()
+            );
+            error!("{}", error_msg);
             Ok(ApiStartResult {
                 success: false,
                 pid: Some(pid),
-                error: Some("API process started but failed to respond".to_string()),
+                error: Some(BBError::ProcessError(error_msg)),
                 requires_settings: false,
+                startup_ms: Some(elapsed.as_millis() as u64),
             })
         }
         Err(e) => {
-            let error_msg = format!("Failed to start API process: {}", e);
-            error!("{}", error_msg);
+            error!("{}", e);
             Ok(ApiStartResult {
                 success: false,
                 pid: None,
-                error: Some(error_msg),
+                error: Some(e),
                 requires_settings: false,
+                startup_ms: None,
             })
         }
     }
@@ -377,12 +533,14 @@ pub async fn start_api() -> Result<ApiStartResult, String> {
 
 #[tauri::command]
 pub async fn stop_api() -> Result<bool, String> {
-    use crate::commands::api_status::{find_all_api_processes, robust_terminate_process};
+    use crate::commands::api_status::robust_terminate_process;
+    use crate::commands::process::find_all_processes;
 
     info!("Stopping API - looking for all bb-api processes");
+    crate::supervisor::note_service_stopped("api");
 
     // Find ALL bb-api processes (not just ones with PID files)
-    let all_pids = find_all_api_processes().await?;
+    let all_pids = find_all_processes("bb-api").await?;
 
     if all_pids.is_empty() {
         info!("No API processes found");
@@ -395,13 +553,10 @@ pub async fn stop_api() -> Result<bool, String> {
 
     info!("Found {} API process(es): {:?}", all_pids.len(), all_pids);
 
-    let mut all_stopped = true;
-
-    // Terminate each process
+    // Terminate each process; whether they are really gone is decided by the re-scan below
     for pid in all_pids {
         if !robust_terminate_process(pid, "bb-api").await {
             error!("Failed to stop API process with PID: {}", pid);
-            all_stopped = false;
         }
     }
 
@@ -411,15 +566,77 @@ pub async fn stop_api() -> Result<bool, String> {
     }
 
     // Wait and verify all processes are gone
-    std::thread::sleep(std::time::Duration::from_millis(1000));
-    let remaining_pids = find_all_api_processes().await?;
+    tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
+    let remaining_pids = find_all_processes("bb-api").await?;
 
-    if !remaining_pids.is_empty() {
-        warn!("Some API processes still running: {:?}", remaining_pids);
-        all_stopped = false;
-    } else {
+    let all_stopped = remaining_pids.is_empty();
+    if all_stopped {
         info!("All API processes stopped successfully");
+    } else {
+        warn!("Some API processes still running: {:?}", remaining_pids);
     }
 
     Ok(all_stopped)
 }
+
+/// Stop the API, wait until its processes are gone and its port is free, then start it again.
+/// Refuses to start if the old instance is still around, so a failed stop can't leave two running.
+#[tauri::command]
+pub async fn restart_api() -> Result<ApiStartResult, BBError> {
+    use crate::commands::process::find_all_processes;
+    use crate::commands::server_status::is_port_free;
+
+    info!("Restarting API");
+    let global_config = read_global_config()
+        .map_err(|e| BBError::ConfigError(format!("Failed to read config: {}", e)))?;
+    let port = global_config.api.port;
+
+    if !stop_api().await.map_err(BBError::ProcessError)? {
+        warn!("stop_api reported failure; checking whether the API is actually gone");
+    }
+
+    // Poll rather than sleep a fixed time: continue as soon as the old instance has released everything
+    let deadline = std::time::Instant::now() + RESTART_WAIT_TIMEOUT;
+    let mut remaining = find_all_processes("bb-api")
+        .await
+        .map_err(BBError::ProcessError)?;
+    let mut port_free = is_port_free(port);
+    while (!remaining.is_empty() || !port_free) && std::time::Instant::now() < deadline {
+        tokio::time::sleep(RESTART_POLL_INTERVAL).await;
+        remaining = find_all_processes("bb-api")
+            .await
+            .map_err(BBError::ProcessError)?;
+        port_free = is_port_free(port);
+    }
+
+    if !remaining.is_empty() || !port_free {
+        let error_msg = if !remaining.is_empty() {
+            BBError::ProcessError(format!(
+                "API did not stop (still running: {:?}); not starting a second instance",
+                remaining
+            ))
+        } else {
+            BBError::PortInUse(format!(
+                "Port {} is still in use after stopping the API; not starting a second instance",
+                port
+            ))
+        };
+        error!("{}", error_msg);
+        return Ok(ApiStartResult {
+            success: false,
+            pid: remaining.first().copied(),
+            error: Some(error_msg),
+            requires_settings: false,
+            startup_ms: None,
+        });
+    }
+
+    start_api().await
+}
+
+/// Recent stdout/stderr lines from the last API process started by the DUI, oldest first.
+/// Output is not captured on Windows, where the API is started detached.
+#[tauri::command]
+pub async fn get_api_startup_output() -> Result<Vec<String>, String> {
+    Ok(crate::logging::get_service_output("api"))
+}
@@ -1,11 +1,18 @@
-use crate::config::read_global_config;
+use crate::config::{get_install_directory, read_global_config};
+use crate::error::BBError;
 use dirs;
 use log::{debug, error, info, warn};
 use serde::Serialize;
 use std::fs;
 use std::path::PathBuf;
 //use crate::commands::api_status::{check_api_status, reconcile_api_pid_state, save_api_pid};
-use crate::commands::bui_status::{check_bui_status, reconcile_bui_pid_state, save_bui_pid};
+use crate::commands::bui_status::{
+    check_bui_status, get_pid_info, reconcile_bui_pid_state, save_bui_pid,
+};
+use crate::commands::pid_file::PidFileInfo;
+use crate::commands::server_status::ensure_port_available;
+use crate::commands::version::binary_version_at;
+use crate::startup_poll::wait_until_ready;
 
 #[cfg(target_os = "windows")]
 use std::ffi::OsStr;
@@ -67,15 +74,51 @@ pub fn get_bui_log_path(config: &crate::config::BuiConfig) -> Option<PathBuf> {
 }
 
 pub(crate) fn get_bb_bui_path() -> Result<PathBuf, String> {
-    debug!("Starting binary search");
-    let mut checked_paths = Vec::new();
-    let bui_name = if cfg!(target_os = "windows") {
+    find_bb_bui_path().map_err(|checked_paths| {
+        let error_msg = format!(
+            "Could not find {} in any of these locations:\n{}",
+            bui_name(),
+            checked_paths
+                .iter()
+                .map(|p| format!("- {}", p.display()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        error!("Binary search failed: {}", error_msg);
+        error_msg
+    })
+}
+
+fn bui_name() -> &'static str {
+    if cfg!(target_os = "windows") {
         "bb-bui.exe"
     } else {
         "bb-bui"
-    };
+    }
+}
+
+/// Search the install locations for bb-bui, returning the paths checked when it isn't found
+pub(crate) fn find_bb_bui_path() -> Result<PathBuf, Vec<PathBuf>> {
+    debug!("Starting binary search");
+    let mut checked_paths = Vec::new();
+    let bui_name = bui_name();
     info!("Looking for {} executable", bui_name);
 
+    // Configured install directory first; if the binary isn't there use the default locations
+    if let Some(custom_install) = get_install_directory() {
+        let custom_binary = custom_install.join(bui_name);
+        checked_paths.push(custom_binary.clone());
+        debug!(
+            "Checking configured install location: {}",
+            custom_binary.display()
+        );
+        if custom_binary.exists() {
+            info!("Found BUI executable in configured install location");
+            return Ok(custom_binary);
+        }
+        debug!("Binary not found in configured install location");
+    }
+
     // Try user-specific location first
     if let Some(home) = dirs::home_dir() {
         let user_install = if cfg!(target_os = "windows") {
@@ -137,30 +180,56 @@ pub(crate) fn get_bb_bui_path() -> Result<PathBuf, String> {
         return Ok(system_binary);
     }
 
-    let error_msg = format!(
-        "Could not find {} in any of these locations:\n{}",
-        bui_name,
-        checked_paths
-            .iter()
-            .map(|p| format!("- {}", p.display()))
-            .collect::<Vec<_>>()
-            .join("\n")
-    );
-    error!("Binary search failed: {}", error_msg);
-    Err(error_msg)
+    Err(checked_paths)
+}
+
+const RESTART_WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const RESTART_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Add the version of the bb-bui binary at `path` to the PID file written for `info`, unless
+/// another process has been recorded since
+fn record_bui_version(info: PidFileInfo, path: PathBuf) {
+    tauri::async_runtime::spawn(async move {
+        let version = match binary_version_at(&path).await {
+            Ok(Some(version)) => version,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to read BUI version from {:?}: {}", path, e);
+                return;
+            }
+        };
+        match get_pid_info().await {
+            Ok(Some(current)) if current.pid == info.pid => {
+                let info = PidFileInfo {
+                    version: Some(version),
+                    ..current
+                };
+                if let Err(e) = save_bui_pid(info).await {
+                    warn!("Failed to save BUI version to PID file: {}", e);
+                }
+            }
+            _ => debug!(
+                "BUI PID {} no longer recorded; not saving its version",
+                info.pid
+            ),
+        }
+    });
 }
 
 #[derive(Debug, Serialize)]
 pub struct BuiStartResult {
     pub success: bool,
     pub pid: Option<i32>,
-    pub error: Option<String>,
+    pub error: Option<BBError>,
     pub requires_settings: bool,
+    /// How long the service took to respond after being started, or how long we waited
+    pub startup_ms: Option<u64>,
 }
 
-fn verify_bui_requirements() -> Result<(), String> {
+fn verify_bui_requirements() -> Result<(), BBError> {
     // Check if bb-bui binary exists
-    get_bb_bui_path().map_err(|e| format!("BB BUI binary not found: {}", e))?;
+    get_bb_bui_path()
+        .map_err(|e| BBError::BinaryNotFound(format!("BB BUI binary not found: {}", e)))?;
 
     // // Check if config exists and has required values
     // let global_config = read_global_config().map_err(|e| format!("Failed to read config: {}", e))?;
@@ -232,7 +301,7 @@ fn create_process_windows(executable_path: PathBuf, args: Vec<String>) -> Result
 }
 
 #[tauri::command]
-pub async fn start_bui() -> Result<BuiStartResult, String> {
+pub async fn start_bui() -> Result<BuiStartResult, BBError> {
     // // First check if API is running, as BUI requires it
     // let api_status = check_api_status().await?;
     // if !api_status.api_responds {
@@ -251,31 +320,52 @@ pub async fn start_bui() -> Result<BuiStartResult, String> {
             pid: None,
             error: Some(e),
             requires_settings: true,
+            startup_ms: None,
         });
     }
 
     // First reconcile any existing state
-    reconcile_bui_pid_state().await?;
+    reconcile_bui_pid_state()
+        .await
+        .map_err(BBError::ProcessError)?;
 
     // Check if BUI is already running
-    let status = check_bui_status().await?;
+    let status = check_bui_status().await.map_err(BBError::ProcessError)?;
     if status.bui_responds {
         return Ok(BuiStartResult {
             success: true,
             pid: status.pid,
             error: None,
             requires_settings: false,
+            startup_ms: None,
         });
     }
 
     // Get BUI configuration
-    let global_config =
-        read_global_config().map_err(|e| format!("Failed to read config: {}", e))?;
+    let global_config = read_global_config()
+        .map_err(|e| BBError::ConfigError(format!("Failed to read config: {}", e)))?;
     let config = &global_config.bui;
+    crate::supervisor::note_service_started("bui");
+
+    // Fail fast with a clear message instead of timing out on a port we can't bind
+    if let Err(e) = ensure_port_available(&config.hostname, config.port, "bb-bui")
+        .await
+        .map_err(BBError::PortInUse)
+    {
+        error!("{}", e);
+        return Ok(BuiStartResult {
+            success: false,
+            pid: None,
+            error: Some(e),
+            requires_settings: false,
+            startup_ms: None,
+        });
+    }
 
     // Get the full path to the bb-bui executable
-    let bb_bui_path =
-        get_bb_bui_path().map_err(|e| format!("Failed to locate bb-bui executable: {}", e))?;
+    let bb_bui_path = get_bb_bui_path().map_err(|e| {
+        BBError::BinaryNotFound(format!("Failed to locate bb-bui executable: {}", e))
+    })?;
 
     info!("Found bb-bui executable at: {}", bb_bui_path.display());
 
@@ -290,8 +380,8 @@ pub async fn start_bui() -> Result<BuiStartResult, String> {
 
     // Get log file path
     //let log_path = get_bui_log_path(&config)
-    let log_path =
-        get_bui_log_path(config).ok_or_else(|| "Failed to determine log path".to_string())?;
+    let log_path = get_bui_log_path(config)
+        .ok_or_else(|| BBError::ConfigError("Failed to determine log path".to_string()))?;
 
     // Ensure log directory exists
     if let Some(parent) = log_path.parent() {
@@ -300,12 +390,20 @@ pub async fn start_bui() -> Result<BuiStartResult, String> {
             return Ok(BuiStartResult {
                 success: false,
                 pid: None,
-                error: Some(format!("Failed to create log directory: {}", e)),
+                error: Some(BBError::from_io("Failed to create log directory", &e)),
                 requires_settings: false,
+                startup_ms: None,
             });
         }
     }
 
+    // Roll over an oversized log before the new process appends to it
+    crate::logging::rotate_log_file(
+        &log_path,
+        global_config.dui.service_log_max_bytes,
+        global_config.dui.service_log_max_files,
+    );
+
     // Add log file argument
     args.extend_from_slice(&[
         "--log-file".to_string(),
@@ -322,14 +420,16 @@ pub async fn start_bui() -> Result<BuiStartResult, String> {
     let process_result = {
         #[cfg(target_os = "windows")]
         {
-            create_process_windows(bb_bui_path, args).map(|pid| pid as i32)
+            create_process_windows(bb_bui_path.clone(), args)
+                .map(|pid| pid as i32)
+                .map_err(BBError::ProcessError)
         }
 
         #[cfg(not(target_os = "windows"))]
         {
-            match Command::new(bb_bui_path).args(&args).spawn() {
+            match Command::new(&bb_bui_path).args(&args).spawn() {
                 Ok(child) => Ok(child.id() as i32),
-                Err(e) => Err(format!("Failed to start BUI process: {}", e)),
+                Err(e) => Err(BBError::from_spawn_io("Failed to start BUI process", &e)),
             }
         }
     };
@@ -338,66 +438,67 @@ pub async fn start_bui() -> Result<BuiStartResult, String> {
         Ok(pid) => {
             info!("BUI process started with PID: {}", pid);
 
-            // Save the PID immediately
-            if let Err(e) = save_bui_pid(pid).await {
+            // Save the PID immediately; the version is added once the binary just started
+            // has reported it
+            let pid_info = PidFileInfo::started_now(pid, config.port, None);
+            if let Err(e) = save_bui_pid(pid_info.clone()).await {
                 warn!("Failed to save PID file: {}", e);
             }
-
-            // Give the BUI a moment to start
-            let max_attempts = 10;
-            for attempt in 1..=max_attempts {
-                std::thread::sleep(std::time::Duration::from_millis(500));
-
+            record_bui_version(pid_info, bb_bui_path);
+
+            // Give the BUI a moment to start, backing off so slow machines aren't reported
+            // as failures while polling stays quick for fast starts
+            let timeout = std::time::Duration::from_secs(global_config.dui.service_startup_timeout);
+            let poll_delay =
+                std::time::Duration::from_millis(global_config.dui.api_startup_poll_interval_ms);
+            let (responds, elapsed) = wait_until_ready(poll_delay, timeout, |attempt| async move {
                 // Verify the BUI is responding
                 match check_bui_status().await {
-                    Ok(status) if status.bui_responds => {
-                        info!("BUI is responding after {} attempts", attempt);
-                        return Ok(BuiStartResult {
-                            success: true,
-                            pid: Some(pid),
-                            error: None,
-                            requires_settings: false,
-                        });
-                    }
-                    Ok(_) if attempt == max_attempts => {
-                        let error_msg =
-                            "BUI process started but not responding after multiple attempts";
-                        error!("{}", error_msg);
-                        return Ok(BuiStartResult {
-                            success: false,
-                            pid: Some(pid),
-                            error: Some(error_msg.to_string()),
-                            requires_settings: false,
-                        });
-                    }
+                    Ok(status) if status.bui_responds => true,
                     Ok(_) => {
-                        debug!(
-                            "BUI not responding yet, attempt {}/{}",
-                            attempt, max_attempts
-                        );
-                        continue;
+                        debug!("BUI not responding yet, attempt {}", attempt);
+                        false
                     }
                     Err(e) => {
                         error!("Error checking BUI status: {}", e);
+                        false
                     }
                 }
+            })
+            .await;
+
+            if responds {
+                info!("BUI is responding after {}ms", elapsed.as_millis());
+                return Ok(BuiStartResult {
+                    success: true,
+                    pid: Some(pid),
+                    error: None,
+                    requires_settings: false,
+                    startup_ms: Some(elapsed.as_millis() as u64),
+                });
             }
 
+            let error_msg = format!(
+                "BUI process started but not responding after {}s (dui.serviceStartupTimeout)",
+                elapsed.as_secs()
+            );
+            error!("{}", error_msg);
             Ok(BuiStartResult {
                 success: false,
                 pid: Some(pid),
-                error: Some("BUI process started but failed to respond".to_string()),
+                error: Some(BBError::ProcessError(error_msg)),
                 requires_settings: false,
+                startup_ms: Some(elapsed.as_millis() as u64),
             })
         }
         Err(e) => {
-            let error_msg = format!("Failed to start BUI process: {}", e);
-            error!("{}", error_msg);
+            error!("{}", e);
             Ok(BuiStartResult {
                 success: false,
                 pid: None,
-                error: Some(error_msg),
+                error: Some(e),
                 requires_settings: false,
+                startup_ms: None,
             })
         }
     }
@@ -405,12 +506,14 @@ pub async fn start_bui() -> Result<BuiStartResult, String> {
 
 #[tauri::command]
 pub async fn stop_bui() -> Result<bool, String> {
-    use crate::commands::bui_status::{find_all_bui_processes, robust_terminate_process};
+    use crate::commands::bui_status::robust_terminate_process;
+    use crate::commands::process::find_all_processes;
 
     info!("Stopping BUI - looking for all bb-bui processes");
+    crate::supervisor::note_service_stopped("bui");
 
     // Find ALL bb-bui processes (not just ones with PID files)
-    let all_pids = find_all_bui_processes().await?;
+    let all_pids = find_all_processes("bb-bui").await?;
 
     if all_pids.is_empty() {
         info!("No BUI processes found");
@@ -423,13 +526,10 @@ pub async fn stop_bui() -> Result<bool, String> {
 
     info!("Found {} BUI process(es): {:?}", all_pids.len(), all_pids);
 
-    let mut all_stopped = true;
-
-    // Terminate each process
+    // Terminate each process; whether they are really gone is decided by the re-scan below
     for pid in all_pids {
         if !robust_terminate_process(pid, "bb-bui").await {
             error!("Failed to stop BUI process with PID: {}", pid);
-            all_stopped = false;
         }
     }
 
@@ -439,15 +539,70 @@ pub async fn stop_bui() -> Result<bool, String> {
     }
 
     // Wait and verify all processes are gone
-    std::thread::sleep(std::time::Duration::from_millis(1000));
-    let remaining_pids = find_all_bui_processes().await?;
+    tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
+    let remaining_pids = find_all_processes("bb-bui").await?;
 
-    if !remaining_pids.is_empty() {
-        warn!("Some BUI processes still running: {:?}", remaining_pids);
-        all_stopped = false;
-    } else {
+    let all_stopped = remaining_pids.is_empty();
+    if all_stopped {
         info!("All BUI processes stopped successfully");
+    } else {
+        warn!("Some BUI processes still running: {:?}", remaining_pids);
     }
 
     Ok(all_stopped)
 }
+
+/// Stop the BUI, wait until its processes are gone and its port is free, then start it again.
+/// Refuses to start if the old instance is still around, so a failed stop can't leave two running.
+#[tauri::command]
+pub async fn restart_bui() -> Result<BuiStartResult, BBError> {
+    use crate::commands::process::find_all_processes;
+    use crate::commands::server_status::is_port_free;
+
+    info!("Restarting BUI");
+    let global_config = read_global_config()
+        .map_err(|e| BBError::ConfigError(format!("Failed to read config: {}", e)))?;
+    let port = global_config.bui.port;
+
+    if !stop_bui().await.map_err(BBError::ProcessError)? {
+        warn!("stop_bui reported failure; checking whether the BUI is actually gone");
+    }
+
+    // Poll rather than sleep a fixed time: continue as soon as the old instance has released everything
+    let deadline = std::time::Instant::now() + RESTART_WAIT_TIMEOUT;
+    let mut remaining = find_all_processes("bb-bui")
+        .await
+        .map_err(BBError::ProcessError)?;
+    let mut port_free = is_port_free(port);
+    while (!remaining.is_empty() || !port_free) && std::time::Instant::now() < deadline {
+        tokio::time::sleep(RESTART_POLL_INTERVAL).await;
+        remaining = find_all_processes("bb-bui")
+            .await
+            .map_err(BBError::ProcessError)?;
+        port_free = is_port_free(port);
+    }
+
+    if !remaining.is_empty() || !port_free {
+        let error_msg = if !remaining.is_empty() {
+            BBError::ProcessError(format!(
+                "BUI did not stop (still running: {:?}); not starting a second instance",
+                remaining
+            ))
+        } else {
+            BBError::PortInUse(format!(
+                "Port {} is still in use after stopping the BUI; not starting a second instance",
+                port
+            ))
+        };
+        error!("{}", error_msg);
+        return Ok(BuiStartResult {
+            success: false,
+            pid: remaining.first().copied(),
+            error: Some(error_msg),
+            requires_settings: false,
+            startup_ms: None,
+        });
+    }
+
+    start_bui().await
+}
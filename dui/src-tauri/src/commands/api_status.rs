@@ -1,17 +1,13 @@
-use log::{error, info};
-use reqwest;
+use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 use tauri::command;
 
+use crate::commands::pid_file::{is_service_process, read_pid_file, write_pid_file, PidFileInfo};
+use crate::commands::probe::{probe_service, service_status_url, SERVICE_PROBE_TIMEOUT};
 use crate::config::read_global_config;
 
-#[cfg(not(target_os = "windows"))]
-use std::process::Command as StdCommand;
-#[cfg(target_os = "windows")]
-use std::process::Command as StdCommand;
-
 const PID_FILE_NAME: &str = "api.pid";
 const APP_NAME: &str = "dev.beyondbetter.app";
 
@@ -64,21 +60,16 @@ fn get_pid_file_path() -> Result<PathBuf, String> {
     Ok(get_app_runtime_dir()?.join(PID_FILE_NAME))
 }
 
-pub async fn save_api_pid(pid: i32) -> Result<(), String> {
-    let pid_file = get_pid_file_path()?;
-    fs::write(&pid_file, pid.to_string()).map_err(|e| format!("Failed to write PID file: {}", e))
+pub async fn save_api_pid(info: PidFileInfo) -> Result<(), String> {
+    write_pid_file(&get_pid_file_path()?, &info)
+}
+
+pub async fn get_pid_info() -> Result<Option<PidFileInfo>, String> {
+    read_pid_file(&get_pid_file_path()?)
 }
 
 pub async fn get_pid() -> Result<Option<i32>, String> {
-    let pid_file = get_pid_file_path()?;
-    match fs::read_to_string(&pid_file) {
-        Ok(content) => match content.trim().parse::<i32>() {
-            Ok(pid) => Ok(Some(pid)),
-            Err(_) => Ok(None),
-        },
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
-        Err(e) => Err(format!("Failed to read PID file: {}", e)),
-    }
+    Ok(get_pid_info().await?.map(|info| info.pid))
 }
 
 pub async fn remove_pid() -> Result<(), String> {
@@ -117,63 +108,6 @@ fn check_process_exists(pid: i32) -> bool {
     }
 }
 
-// Add new function for process discovery
-pub async fn find_all_api_processes() -> Result<Vec<i32>, String> {
-    let process_name = if cfg!(target_os = "windows") {
-        "bb-api.exe"
-    } else {
-        "bb-api"
-    };
-
-    let output = if cfg!(target_os = "windows") {
-        StdCommand::new("tasklist")
-            .args(&["/fo", "csv", "/nh"])
-            .output()
-    } else if cfg!(target_os = "macos") {
-        StdCommand::new("pgrep")
-            .args(&["-f", process_name])
-            .output()
-    } else {
-        // Linux
-        StdCommand::new("pgrep").args(&[process_name]).output()
-    };
-
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let mut pids = Vec::new();
-
-            if cfg!(target_os = "windows") {
-                // Parse CSV format from tasklist
-                for line in stdout.lines() {
-                    if line.contains(process_name) {
-                        let parts: Vec<&str> = line.split(',').collect();
-                        if parts.len() >= 2 {
-                            if let Ok(pid) = parts[1].trim_matches('"').parse::<i32>() {
-                                pids.push(pid);
-                            }
-                        }
-                    }
-                }
-            } else {
-                // Parse pgrep output
-                for line in stdout.lines() {
-                    if let Ok(pid) = line.trim().parse::<i32>() {
-                        pids.push(pid);
-                    }
-                }
-            }
-
-            info!("Found {} API processes: {:?}", pids.len(), pids);
-            Ok(pids)
-        }
-        Err(e) => {
-            error!("Failed to list processes: {}", e);
-            Err(format!("Failed to list processes: {}", e))
-        }
-    }
-}
-
 // Add robust termination function
 pub async fn robust_terminate_process(pid: i32, process_name: &str) -> bool {
     info!(
@@ -196,7 +130,7 @@ pub async fn robust_terminate_process(pid: i32, process_name: &str) -> bool {
 
     if graceful_result {
         // Wait a bit for graceful shutdown
-        std::thread::sleep(std::time::Duration::from_millis(2000));
+        tokio::time::sleep(std::time::Duration::from_millis(2000)).await;
 
         // Check if process is gone
         if !check_process_exists(pid) {
@@ -232,7 +166,7 @@ pub async fn robust_terminate_process(pid: i32, process_name: &str) -> bool {
     };
 
     // Wait a bit and verify
-    std::thread::sleep(std::time::Duration::from_millis(1000));
+    tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
     let success = !check_process_exists(pid);
 
     if success {
@@ -245,51 +179,20 @@ pub async fn robust_terminate_process(pid: i32, process_name: &str) -> bool {
 }
 
 async fn check_api_responds(hostname: &str, port: u16, use_tls: bool) -> Result<bool, String> {
-    // Try the configured protocol first
-    let primary_scheme = if use_tls { "https" } else { "http" };
-    let primary_url = format!("{}://{}:{}/api/v1/status", primary_scheme, hostname, port);
-
-    info!("Checking API status at: {}", primary_url);
-
-    match reqwest::get(&primary_url).await {
-        Ok(response) => {
-            let status = response.status();
-            info!(
-                "API responded with status: {} on {}",
-                status, primary_scheme
-            );
-            if status.is_success() {
-                return Ok(true);
-            }
-        }
-        Err(e) => {
-            info!("Failed to connect to API on {}: {}", primary_scheme, e);
-        }
-    }
-
-    // Fallback to the other protocol
-    let fallback_scheme = if use_tls { "http" } else { "https" };
-    let fallback_url = format!("{}://{}:{}/api/v1/status", fallback_scheme, hostname, port);
-
-    info!("Trying fallback API status check at: {}", fallback_url);
-
-    match reqwest::get(&fallback_url).await {
-        Ok(response) => {
-            let status = response.status();
-            info!(
-                "API responded with status: {} on {} (fallback)",
-                status, fallback_scheme
-            );
-            Ok(status.is_success())
-        }
-        Err(e) => {
-            info!(
-                "Failed to connect to API on {} (fallback): {}",
-                fallback_scheme, e
-            );
-            Ok(false)
+    // Try the configured protocol first, then fall back to the other one
+    let schemes = if use_tls {
+        ["https", "http"]
+    } else {
+        ["http", "https"]
+    };
+    for scheme in schemes {
+        let url = service_status_url(scheme, hostname, port);
+        info!("Checking API status at: {}", url);
+        if probe_service(&url, SERVICE_PROBE_TIMEOUT).await {
+            return Ok(true);
         }
     }
+    Ok(false)
 }
 
 #[command]
@@ -311,8 +214,15 @@ pub async fn check_api_status() -> Result<ApiStatusCheck, String> {
             println!("Found PID file with PID: {}", pid);
             status.pid = Some(pid);
 
-            // Level 2: Check if process exists
+            // Level 2: Check if process exists, and that it is still ours
             status.pid_exists = check_process_exists(pid);
+            if status.pid_exists && !is_service_process(pid, "bb-api") {
+                warn!(
+                    "PID {} belongs to another program, treating API as stopped",
+                    pid
+                );
+                status.pid_exists = false;
+            }
             println!("Process exists: {}", status.pid_exists);
 
             // Level 3: Check if API endpoint responds
@@ -356,7 +266,7 @@ pub async fn reconcile_api_pid_state() -> Result<(), String> {
     let pid = get_pid().await?;
 
     if !status.pid_exists && pid.is_some() {
-        // PID file exists but process doesn't - clean up
+        // PID file exists but process doesn't (or the PID was reused) - clean up
         remove_pid().await?;
     } else if status.pid_exists && !status.api_responds {
         // Process exists but API doesn't respond - potential zombie
@@ -365,7 +275,7 @@ pub async fn reconcile_api_pid_state() -> Result<(), String> {
         // API responds but no PID file - recover state if possible
         if let Some(pid) = status.pid {
             println!("Recovering PID file with process ID: {}", pid);
-            save_api_pid(pid).await?;
+            save_api_pid(PidFileInfo::recovered(pid)).await?;
         }
     }
 
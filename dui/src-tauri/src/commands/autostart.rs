@@ -0,0 +1,216 @@
+use log::{debug, info};
+use serde::Serialize;
+#[cfg(not(target_os = "windows"))]
+use std::fs;
+use std::path::PathBuf;
+use tauri::command;
+
+#[cfg(target_os = "windows")]
+use std::process::Command;
+
+#[cfg(target_os = "macos")]
+use crate::config::APP_NAME;
+
+// Passed to the app when it is started by the OS at login
+const AUTOSTART_ARG: &str = "--autostart";
+#[cfg(target_os = "windows")]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+#[cfg(target_os = "windows")]
+const RUN_VALUE_NAME: &str = "Beyond Better";
+
+#[derive(Debug, Serialize)]
+pub struct LaunchAtLoginStatus {
+    /// Whether the app is registered to start at login
+    pub enabled: bool,
+    /// Whether the current process was started by that registration
+    pub launched_at_login: bool,
+}
+
+fn current_exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Failed to determine executable path: {}", e))
+}
+
+#[cfg(target_os = "macos")]
+fn launch_agent_path() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
+    Ok(home_dir
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("{}.plist", APP_NAME)))
+}
+
+#[cfg(target_os = "linux")]
+fn autostart_desktop_path() -> Result<PathBuf, String> {
+    let config_dir =
+        dirs::config_dir().ok_or_else(|| "Failed to get config directory".to_string())?;
+    Ok(config_dir.join("autostart").join("beyond-better.desktop"))
+}
+
+fn is_registered() -> Result<bool, String> {
+    #[cfg(target_os = "macos")]
+    {
+        Ok(launch_agent_path()?.exists())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let output = Command::new("reg")
+            .args(["query", RUN_KEY, "/v", RUN_VALUE_NAME])
+            .output()
+            .map_err(|e| format!("Failed to query registry: {}", e))?;
+        Ok(output.status.success())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        Ok(autostart_desktop_path()?.exists())
+    }
+}
+
+fn register() -> Result<(), String> {
+    let exe = current_exe()?;
+
+    #[cfg(target_os = "macos")]
+    {
+        let path = launch_agent_path()?;
+        let plist = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+            APP_NAME,
+            exe.display(),
+            AUTOSTART_ARG
+        );
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create LaunchAgents directory: {}", e))?;
+        }
+        fs::write(&path, plist).map_err(|e| format!("Failed to write launch agent: {}", e))?;
+        debug!("Wrote launch agent: {:?}", path);
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let command_line = format!("\"{}\" {}", exe.display(), AUTOSTART_ARG);
+        let output = Command::new("reg")
+            .args([
+                "add",
+                RUN_KEY,
+                "/v",
+                RUN_VALUE_NAME,
+                "/t",
+                "REG_SZ",
+                "/d",
+                &command_line,
+                "/f",
+            ])
+            .output()
+            .map_err(|e| format!("Failed to update registry: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to add Run key: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let path = autostart_desktop_path()?;
+        let desktop_entry = format!(
+            "[Desktop Entry]\nType=Application\nName=Beyond Better\nExec=\"{}\" {}\nX-GNOME-Autostart-enabled=true\n",
+            exe.display(),
+            AUTOSTART_ARG
+        );
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create autostart directory: {}", e))?;
+        }
+        fs::write(&path, desktop_entry)
+            .map_err(|e| format!("Failed to write autostart entry: {}", e))?;
+        debug!("Wrote autostart entry: {:?}", path);
+        Ok(())
+    }
+}
+
+fn unregister() -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let path = launch_agent_path()?;
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("Failed to remove launch agent: {}", e))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if !is_registered()? {
+            return Ok(());
+        }
+        let output = Command::new("reg")
+            .args(["delete", RUN_KEY, "/v", RUN_VALUE_NAME, "/f"])
+            .output()
+            .map_err(|e| format!("Failed to update registry: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to remove Run key: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let path = autostart_desktop_path()?;
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove autostart entry: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+fn launch_at_login_status() -> Result<LaunchAtLoginStatus, String> {
+    Ok(LaunchAtLoginStatus {
+        enabled: is_registered()?,
+        launched_at_login: std::env::args().any(|arg| arg == AUTOSTART_ARG),
+    })
+}
+
+#[command]
+pub async fn get_launch_at_login() -> Result<LaunchAtLoginStatus, String> {
+    launch_at_login_status()
+}
+
+/// Register or unregister the app with the OS login items: a LaunchAgent on macOS,
+/// the per-user Run key on Windows and an XDG autostart entry on Linux
+#[command]
+pub async fn set_launch_at_login(enabled: bool) -> Result<LaunchAtLoginStatus, String> {
+    if enabled {
+        register()?;
+    } else {
+        unregister()?;
+    }
+    info!(
+        "Launch at login {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    launch_at_login_status()
+}
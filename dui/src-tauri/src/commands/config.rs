@@ -1,11 +1,28 @@
-use log::{error, info};
+use log::{debug, error, info, warn};
+use serde::Serialize;
 use serde_yaml;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
 use crate::config::{
-    get_default_log_path, get_global_config_dir, read_global_config, GlobalConfig,
-    LlmProviderConfig,
+    get_default_log_path, get_global_config_dir, read_global_config, read_global_config_file,
+    write_global_config, write_global_config_yaml, ConfigFieldError, ConfigValidationError,
+    GlobalConfig, LlmProviderConfig,
 };
+use crate::config_watcher::flatten;
+use crate::keychain::{keychain_ref, ANTHROPIC_API_KEY_ACCOUNT};
+use crate::proxy::HttpProxy;
+
+// Number of automatic and manual config backups kept under <config_dir>/backups
+const MAX_CONFIG_BACKUPS: usize = 5;
+// Upper bound on lines returned by get_recent_logs
+const MAX_RECENT_LOG_LINES: usize = 5000;
+// Bytes read per step when scanning a log file backwards
+const LOG_TAIL_CHUNK_SIZE: u64 = 8192;
 
 #[tauri::command]
 pub async fn get_log_path(filename: &str) -> Result<Option<String>, String> {
@@ -39,8 +56,8 @@ pub async fn get_bui_log_path() -> Result<String, String> {
 #[tauri::command]
 pub async fn get_dui_log_path() -> Result<String, String> {
     // Get the log directory
-    let log_dir = crate::api::get_default_log_dir()
-        .ok_or_else(|| "Failed to determine log directory".to_string())?;
+    let log_dir =
+        crate::get_app_log_dir().ok_or_else(|| "Failed to determine log directory".to_string())?;
 
     // DUI logs are stored in "Beyond Better.log"
     let path = log_dir.join("Beyond Better.log");
@@ -49,19 +66,243 @@ pub async fn get_dui_log_path() -> Result<String, String> {
     Ok(path.to_string_lossy().to_string())
 }
 
+/// The JSON lines access log the proxy is writing, which follows relocated logs
 #[tauri::command]
-pub async fn get_proxy_log_path() -> Result<String, String> {
-    // Get the log directory
-    let log_dir = crate::api::get_default_log_dir()
-        .ok_or_else(|| "Failed to determine log directory".to_string())?;
-
-    // Proxy logs are stored in "proxy-access.log"
-    let path = log_dir.join("proxy-access.log");
+pub async fn get_proxy_log_path(
+    proxy: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<String, String> {
+    let path = proxy.read().await.access_log_path().await;
 
     // Convert to string, handling any non-UTF8 characters
     Ok(path.to_string_lossy().to_string())
 }
 
+/// Last `lines` lines of `path`, read backwards from the end so large logs aren't loaded whole
+fn tail_lines(path: &Path, lines: usize) -> std::io::Result<Vec<String>> {
+    let mut file = fs::File::open(path)?;
+    let mut pos = file.metadata()?.len();
+    let mut buf: Vec<u8> = Vec::new();
+    let mut newlines = 0;
+
+    // One more newline than requested means the earliest wanted line is complete
+    while pos > 0 && newlines <= lines {
+        let read = LOG_TAIL_CHUNK_SIZE.min(pos);
+        pos -= read;
+        file.seek(SeekFrom::Start(pos))?;
+        let mut chunk = vec![0; read as usize];
+        file.read_exact(&mut chunk)?;
+        newlines += chunk.iter().filter(|&&b| b == b'\n').count();
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let all: Vec<&str> = text.lines().collect();
+    Ok(all[all.len().saturating_sub(lines)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// Most recent lines of a service log (`api`, `bui`, `dui` or `proxy`, which is the JSON
+/// lines access log), oldest first.
+/// `lines` is capped at MAX_RECENT_LOG_LINES; a log that doesn't exist yet (or was just
+/// rotated away) yields no lines rather than an error.
+#[tauri::command]
+pub async fn get_recent_logs(
+    service: String,
+    lines: usize,
+    proxy: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<Vec<String>, String> {
+    let path = match service.as_str() {
+        "api" => get_api_log_path().await?,
+        "bui" => get_bui_log_path().await?,
+        "dui" => get_dui_log_path().await?,
+        "proxy" => proxy
+            .read()
+            .await
+            .access_log_path()
+            .await
+            .to_string_lossy()
+            .to_string(),
+        _ => return Err(format!("Unknown service: {}", service)),
+    };
+    let lines = lines.min(MAX_RECENT_LOG_LINES);
+
+    match tail_lines(Path::new(&path), lines) {
+        Ok(recent) => Ok(recent),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            debug!("Log file for {} not found: {}", service, path);
+            Ok(Vec::new())
+        }
+        Err(e) => Err(format!("Failed to read log file {}: {}", path, e)),
+    }
+}
+
+/// Probe the directory with a throwaway file; permission bits alone are unreliable on Windows
+fn check_dir_writable(dir: &Path) -> Result<(), String> {
+    let probe = dir.join(".bb-write-test");
+    fs::write(&probe, b"")
+        .map_err(|e| format!("Log directory is not writable: {}: {}", dir.display(), e))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Move a file, falling back to copy + delete when the rename crosses filesystems
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+/// Move every log file (including rotated ones) from `old_dir` to `new_dir`
+fn move_log_files(old_dir: &Path, new_dir: &Path) -> Result<Vec<String>, String> {
+    let mut moved = Vec::new();
+    let entries = match fs::read_dir(old_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(moved),
+        Err(e) => return Err(format!("Failed to read log directory: {}", e)),
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_log = path.is_file()
+            && path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext == "log" || ext == "jsonl");
+        if !is_log {
+            continue;
+        }
+
+        let target = new_dir.join(entry.file_name());
+        match move_file(&path, &target) {
+            Ok(()) => {
+                info!("Moved log file {:?} -> {:?}", path, target);
+                moved.push(target.to_string_lossy().to_string());
+            }
+            Err(e) => warn!("Failed to move log file {:?}: {}", path, e),
+        }
+    }
+
+    Ok(moved)
+}
+
+/// Change the app's log level until the next restart, e.g. to capture a bug at debug level
+#[tauri::command]
+pub async fn set_log_level(level: String) -> Result<(), String> {
+    let level = crate::logging::set_app_log_level(&level)?;
+    info!("App log level set to {}", level);
+    Ok(())
+}
+
+/// Apply changes made by hand to log4rs.yaml in the log directory without restarting
+#[tauri::command]
+pub async fn reload_logging_config() -> Result<(), String> {
+    let config_path = crate::logging::reload_log_config_file().map_err(|e| {
+        warn!("Failed to reload logging config: {}", e);
+        e
+    })?;
+    info!("Reloaded logging config from {:?}", config_path);
+    Ok(())
+}
+
+/// Relocate DUI-managed logs to `path`.
+///
+/// Closes the DUI's log files and the proxy access log, moves existing log files, records the
+/// new directory in the global config (along with API/BUI log files that lived in the old
+/// directory), moves log4rs.yaml with its appender paths updated and reopens the logs at the
+/// new location.
+/// Returns the new paths of the files that were moved.
+#[tauri::command]
+pub async fn set_log_directory(
+    path: String,
+    proxy: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<Vec<String>, String> {
+    let new_dir = PathBuf::from(&path);
+    if !new_dir.is_absolute() {
+        return Err(format!("Log directory must be an absolute path: {}", path));
+    }
+    fs::create_dir_all(&new_dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+    check_dir_writable(&new_dir)?;
+
+    let old_dir =
+        crate::get_app_log_dir().ok_or_else(|| "Failed to determine log directory".to_string())?;
+    let same_dir = match (fs::canonicalize(&old_dir), fs::canonicalize(&new_dir)) {
+        (Ok(old), Ok(new)) => old == new,
+        _ => old_dir == new_dir,
+    };
+    if same_dir {
+        info!("Log directory unchanged: {:?}", new_dir);
+        return Ok(Vec::new());
+    }
+
+    let mut config = read_global_config_file().map_err(|e| {
+        error!("Failed to read config for log directory update: {}", e);
+        format!("Failed to read config: {}", e)
+    })?;
+    // Resolve before moving so relative and default paths still point at the old files
+    let api_log = crate::api::get_api_log_path(&config.api);
+    let bui_log = crate::bui::get_bui_log_path(&config.bui);
+
+    info!("Moving logs from {:?} to {:?}", old_dir, new_dir);
+    // Files still open for writing can't be moved on Windows, and elsewhere would keep
+    // receiving writes at their new location
+    let proxy = proxy.read().await;
+    crate::logging::suspend_app_logging()
+        .map_err(|e| format!("Failed to close log files: {}", e))?;
+    if let Err(e) = proxy.close_access_log().await {
+        warn!("Failed to close proxy access log: {}", e);
+    }
+    let moved = move_log_files(&old_dir, &new_dir);
+    if let Err(e) = &moved {
+        // Reopen the logs where they are
+        if let Err(reload_error) = crate::logging::reload_app_logging(&old_dir) {
+            warn!("Failed to reopen logs in {:?}: {}", old_dir, reload_error);
+        }
+        if let Err(reopen_error) = proxy.set_log_dir(old_dir.clone()).await {
+            warn!("Failed to reopen proxy access log: {}", reopen_error);
+        }
+        error!("Failed to move logs to {:?}: {}", new_dir, e);
+    }
+    let moved = moved?;
+
+    let relocate = |log_path: Option<PathBuf>| {
+        log_path
+            .filter(|p| p.starts_with(&old_dir))
+            .and_then(|p| p.file_name().map(|name| new_dir.join(name)))
+            .map(|p| p.to_string_lossy().to_string())
+    };
+    if let Some(api_log_file) = relocate(api_log) {
+        config.api.log_file = Some(api_log_file);
+    }
+    if let Some(bui_log_file) = relocate(bui_log) {
+        config.bui.log_file = Some(bui_log_file);
+    }
+    config.dui.log_directory = Some(new_dir.to_string_lossy().to_string());
+
+    let saved =
+        write_global_config(&config).map_err(|e| format!("Failed to write config file: {}", e));
+
+    // Reopen both logs before reporting any failure, so neither stays closed
+    let reloaded = crate::logging::reload_app_logging(&new_dir)
+        .map_err(|e| format!("Failed to reinitialize logging: {}", e));
+    let reopened = proxy
+        .set_log_dir(new_dir.clone())
+        .await
+        .map_err(|e| format!("Failed to reopen proxy access log: {}", e));
+    saved.and(reloaded).and(reopened)?;
+
+    info!(
+        "Log directory set to {:?} ({} files moved)",
+        new_dir,
+        moved.len()
+    );
+    Ok(moved)
+}
+
 #[tauri::command]
 pub async fn open_log_file(path: String) -> Result<(), String> {
     use std::path::Path;
@@ -71,26 +312,9 @@ pub async fn open_log_file(path: String) -> Result<(), String> {
         return Err(format!("Log file does not exist: {}", path));
     }
 
-    // Use platform-specific command to open the file
-    let (cmd, args) = if cfg!(target_os = "windows") {
-        (
-            "cmd",
-            vec![
-                "/c".to_string(),
-                "start".to_string(),
-                "".to_string(),
-                path.clone(),
-            ],
-        )
-    } else if cfg!(target_os = "macos") {
-        ("open", vec![path.clone()])
-    } else {
-        // Linux/Unix
-        ("xdg-open", vec![path.clone()])
-    };
-
-    match std::process::Command::new(cmd).args(args).spawn() {
-        Ok(_) => {
+    // Open with the default app; the opener passes the path to the OS without a shell
+    match tauri_plugin_opener::open_path(&path, None::<&str>) {
+        Ok(()) => {
             log::info!("Opened log file: {}", path);
             Ok(())
         }
@@ -154,21 +378,93 @@ pub async fn get_global_config() -> Result<GlobalConfig, String> {
         info!("Setting default log path: {:?}", config.bui.log_file);
     }
 
+    // Show the real key's prefix rather than the keychain placeholder
+    crate::keychain::resolve_secrets(&mut config);
+
     // Create a redacted copy for the frontend
     let mut redacted = config.clone();
+    mask_api_key(&mut redacted);
+
+    Ok(redacted)
+}
 
-    // Mask the Anthropic API key if it exists and is not empty
-    if let Some(ref provider) = redacted.api.llm_providers.anthropic {
+/// Configuration as the app and services actually use it: config.yaml with migrations,
+/// serde defaults, the keychain API key and BB_* environment overrides applied. Unlike
+/// `get_global_config`, no display defaults (e.g. log paths) are filled in. The API key is
+/// masked.
+#[tauri::command]
+pub async fn get_effective_config() -> Result<GlobalConfig, String> {
+    let mut config = read_global_config().map_err(|e| {
+        error!("Failed to read effective config: {}", e);
+        e.to_string()
+    })?;
+    mask_api_key(&mut config);
+    Ok(config)
+}
+
+/// Mask the Anthropic API key if it exists and is not empty
+fn mask_api_key(config: &mut GlobalConfig) {
+    if let Some(ref provider) = config.api.llm_providers.anthropic {
         if let Some(ref key) = provider.api_key {
             if !key.is_empty() {
-                redacted.api.llm_providers.anthropic = Some(LlmProviderConfig {
+                config.api.llm_providers.anthropic = Some(LlmProviderConfig {
                     api_key: Some(format!("{}...", &key[..18.min(key.len())])),
                 });
             }
         }
     }
+}
 
-    Ok(redacted)
+#[derive(Debug, Serialize)]
+pub struct ConfigDiffEntry {
+    pub default: serde_json::Value,
+    pub current: serde_json::Value,
+}
+
+/// Settings that differ from the defaults, keyed by dotted camelCase path
+/// (e.g. `api.logLevel`), for pasting into bug reports. The API key is masked.
+#[tauri::command]
+pub async fn get_config_diff() -> Result<BTreeMap<String, ConfigDiffEntry>, String> {
+    let mut config = read_global_config_file().map_err(|e| {
+        error!("Failed to read config for diff: {}", e);
+        e.to_string()
+    })?;
+    mask_api_key(&mut config);
+
+    let (mut defaults, mut current) = (BTreeMap::new(), BTreeMap::new());
+    let to_value = |config: &GlobalConfig| {
+        serde_json::to_value(config).map_err(|e| format!("Failed to serialize config: {}", e))
+    };
+    flatten("", &to_value(&GlobalConfig::default())?, &mut defaults);
+    flatten("", &to_value(&config)?, &mut current);
+
+    // Keys skipped when unset (e.g. optional paths) only exist on one side
+    let keys: BTreeSet<String> = defaults.keys().chain(current.keys()).cloned().collect();
+    Ok(keys
+        .into_iter()
+        .filter_map(|key| {
+            let default = defaults.get(&key).cloned().unwrap_or_default();
+            let current = current.get(&key).cloned().unwrap_or_default();
+            (default != current).then_some((key, ConfigDiffEntry { default, current }))
+        })
+        .collect())
+}
+
+/// Every problem found in config.yaml, by field, so the settings UI can point at what to
+/// fix. Reading the config only logs these; an empty list means the config is valid.
+#[tauri::command]
+pub async fn validate_global_config() -> Result<Vec<ConfigFieldError>, String> {
+    match read_global_config_file() {
+        Ok(config) => Ok(config.validate()),
+        // A config.yaml that doesn't parse is reported with the position of the problem
+        Err(e) => match e.downcast::<ConfigValidationError>() {
+            Ok(e) => Ok(e.errors),
+            Err(e) => {
+                error!("Failed to read config for validation: {}", e);
+                Err(e.to_string())
+            }
+        },
+    }
 }
 
 #[tauri::command]
@@ -185,16 +481,36 @@ pub async fn set_global_config_value(key: String, value: String) -> Result<(), S
     );
 
     // Read current config
-    let mut config = read_global_config().map_err(|e| {
+    let mut config = read_global_config_file().map_err(|e| {
         error!("Failed to read config for update: {}", e);
         e.to_string()
     })?;
 
     // Update the value using the dot notation key
-    update_config_value(&mut config, &key, &value).map_err(|e| {
+    let new_value = match update_config_value(&mut config, &key, &value).map_err(|e| {
         error!("Failed to update config value: {}", e);
-        e.to_string()
-    })?;
+        e
+    })? {
+        Some(new_value) => new_value,
+        None => return Ok(()),
+    };
+
+    // The API key goes to the keychain; config.yaml only records where to find it
+    let path = config_key_path(&key);
+    let new_value = match new_value.as_str() {
+        Some(secret)
+            if path.join(".") == "api.llmProviders.anthropic.apiKey" && !secret.is_empty() =>
+        {
+            match crate::keychain::store_secret(ANTHROPIC_API_KEY_ACCOUNT, secret) {
+                Ok(()) => serde_json::Value::String(keychain_ref(ANTHROPIC_API_KEY_ACCOUNT)),
+                Err(e) => {
+                    warn!("Storing API key in config.yaml: {}", e);
+                    new_value
+                }
+            }
+        }
+        _ => new_value,
+    };
 
     // Write updated config
     let config_dir = get_global_config_dir().map_err(|e| e.to_string())?;
@@ -215,24 +531,129 @@ pub async fn set_global_config_value(key: String, value: String) -> Result<(), S
     };
 
     // Update only the specific value using the dot notation path
-    update_yaml_value(&mut yaml_value, &key, &value)?;
+    update_yaml_value(&mut yaml_value, &path, &new_value)?;
 
     // Convert to YAML string
     let yaml_str = serde_yaml::to_string(&yaml_value)
         .map_err(|e| format!("Failed to serialize config: {}", e))?;
 
+    // Keep a copy of the current file so a bad edit can be undone
+    if config_path.exists() {
+        if let Err(e) = create_config_backup() {
+            warn!("Failed to back up config before update: {}", e);
+        }
+    }
+
     // Write to file
-    fs::write(&config_path, &yaml_str)
+    write_global_config_yaml(&yaml_str)
         .map_err(|e| format!("Failed to write config file: {}", e))?;
 
     Ok(())
 }
 
-fn update_yaml_value(root: &mut serde_yaml::Value, key: &str, value: &str) -> Result<(), String> {
-    // Split the key path and convert to camelCase
-    let mut path_parts: Vec<String> = Vec::new();
-    for part in key.split('.') {
-        if part.contains('_') {
+fn config_backups_dir() -> Result<PathBuf, String> {
+    Ok(get_global_config_dir()
+        .map_err(|e| e.to_string())?
+        .join("backups"))
+}
+
+/// Backup file names, newest first. Names embed a sortable timestamp.
+fn list_config_backups(backups_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(backups_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| name.starts_with("config-") && name.ends_with(".yaml"))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort_unstable_by(|a, b| b.cmp(a));
+    names
+}
+
+/// Copy config.yaml into the backups directory and prune all but the newest backups
+fn create_config_backup() -> Result<String, String> {
+    let config_path = get_global_config_dir()
+        .map_err(|e| e.to_string())?
+        .join("config.yaml");
+    let backups_dir = config_backups_dir()?;
+    fs::create_dir_all(&backups_dir)
+        .map_err(|e| format!("Failed to create backups directory: {}", e))?;
+
+    let backup_name = format!(
+        "config-{}.yaml",
+        chrono::Local::now().format("%Y%m%d-%H%M%S-%3f")
+    );
+    fs::copy(&config_path, backups_dir.join(&backup_name))
+        .map_err(|e| format!("Failed to back up config: {}", e))?;
+    debug!("Backed up config to {}", backup_name);
+
+    for old_backup in list_config_backups(&backups_dir)
+        .into_iter()
+        .skip(MAX_CONFIG_BACKUPS)
+    {
+        if let Err(e) = fs::remove_file(backups_dir.join(&old_backup)) {
+            warn!("Failed to remove old config backup {}: {}", old_backup, e);
+        }
+    }
+
+    Ok(backup_name)
+}
+
+/// Back up config.yaml, returning the name of the new backup
+#[tauri::command]
+pub async fn backup_global_config() -> Result<String, String> {
+    let backup_name = create_config_backup()?;
+    info!("Created config backup {}", backup_name);
+    Ok(backup_name)
+}
+
+/// Available config backups, newest first
+#[tauri::command]
+pub async fn get_global_config_backups() -> Result<Vec<String>, String> {
+    Ok(list_config_backups(&config_backups_dir()?))
+}
+
+/// Replace config.yaml with a backup after checking that the backup parses and validates.
+/// The current config is backed up first so the restore itself can be undone.
+#[tauri::command]
+pub async fn restore_global_config(backup_name: String) -> Result<(), String> {
+    if backup_name.contains(['/', '\\']) || backup_name.starts_with('.') {
+        return Err(format!("Invalid backup name: {}", backup_name));
+    }
+    let backup_path = config_backups_dir()?.join(&backup_name);
+    let contents = fs::read_to_string(&backup_path)
+        .map_err(|e| format!("Failed to read backup {}: {}", backup_name, e))?;
+
+    let backup_config = serde_yaml::from_str::<GlobalConfig>(&contents)
+        .map_err(|e| format!("Backup {} is not a valid config: {}", backup_name, e))?;
+    let errors = backup_config.validate();
+    if !errors.is_empty() {
+        let problems: Vec<String> = errors
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.problem))
+            .collect();
+        return Err(format!(
+            "Backup {} is not a valid config: {}",
+            backup_name,
+            problems.join("; ")
+        ));
+    }
+
+    if let Err(e) = create_config_backup() {
+        warn!("Failed to back up config before restore: {}", e);
+    }
+    write_global_config_yaml(&contents)
+        .map_err(|e| format!("Failed to write config file: {}", e))?;
+    info!("Restored config from backup {}", backup_name);
+    Ok(())
+}
+
+/// Split a dot-notation key into camelCase path segments (`api.log_file` -> `api`, `logFile`)
+fn config_key_path(key: &str) -> Vec<String> {
+    key.split('.')
+        .map(|part| {
             let mut camel = String::new();
             let mut capitalize = false;
             for c in part.chars() {
@@ -245,117 +666,150 @@ fn update_yaml_value(root: &mut serde_yaml::Value, key: &str, value: &str) -> Re
                     camel.push(c);
                 }
             }
-            path_parts.push(camel);
-        } else {
-            path_parts.push(part.to_string());
-        }
+            camel
+        })
+        .collect()
+}
+
+fn json_at<'a>(root: &'a serde_json::Value, path: &[String]) -> Option<&'a serde_json::Value> {
+    path.iter().try_fold(root, |node, part| node.get(part))
+}
+
+/// Interpret `value` for a field whose current JSON value is `current`. Fields that are unset
+/// (and so missing from the serialized config) get a best guess that serde later confirms.
+fn coerce_config_value(
+    key: &str,
+    current: Option<&serde_json::Value>,
+    value: &str,
+) -> Result<serde_json::Value, String> {
+    use serde_json::Value;
+    match current {
+        Some(Value::Bool(_)) => value
+            .trim()
+            .parse::<bool>()
+            .map(Value::Bool)
+            .map_err(|_| format!("Invalid boolean for {}: {}", key, value)),
+        Some(Value::Number(_)) => serde_json::from_str::<serde_json::Number>(value.trim())
+            .map(Value::Number)
+            .map_err(|_| format!("Invalid number for {}: {}", key, value)),
+        Some(Value::String(_)) => Ok(Value::String(value.to_string())),
+        Some(Value::Array(_)) | Some(Value::Object(_)) => serde_json::from_str(value)
+            .map_err(|e| format!("Invalid JSON value for {}: {}", key, e)),
+        Some(Value::Null) | None => Ok(serde_json::from_str::<Value>(value.trim())
+            .ok()
+            .filter(|v| v.is_boolean() || v.is_number())
+            .unwrap_or_else(|| Value::String(value.to_string()))),
     }
+}
+
+/// Write `value` at `path` in the raw YAML tree, creating intermediate mappings as needed.
+/// Editing the raw file rather than re-serializing GlobalConfig keeps keys the app doesn't know.
+fn update_yaml_value(
+    root: &mut serde_yaml::Value,
+    path: &[String],
+    value: &serde_json::Value,
+) -> Result<(), String> {
+    let value =
+        serde_yaml::to_value(value).map_err(|e| format!("Failed to convert value: {}", e))?;
+    let (last, parents) = path
+        .split_last()
+        .ok_or_else(|| "Empty config key".to_string())?;
 
-    // Navigate the YAML tree, creating nodes as needed
     let mut current = root;
-    for (i, part) in path_parts.iter().enumerate() {
-        if i == path_parts.len() - 1 {
-            // We're at the final part - set the value
-            let mapping = if !current.is_mapping() {
-                *current = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
-                current.as_mapping_mut().unwrap()
-            } else {
-                current.as_mapping_mut().unwrap()
-            };
-
-            // Set the value based on the key type
-            match key {
-                "api.logFile" | "bui.logFile" => {
-                    mapping.insert(
-                        serde_yaml::Value::String(part.clone()),
-                        serde_yaml::Value::String(value.to_string()),
-                    );
-                }
-                "api.tls.useTls" | "api.localMode" | "bui.tls.useTls" | "bui.localMode" => {
-                    if let Ok(bool_value) = value.parse::<bool>() {
-                        mapping.insert(
-                            serde_yaml::Value::String(part.clone()),
-                            serde_yaml::Value::Bool(bool_value),
-                        );
-                    } else {
-                        return Err(format!("Invalid boolean value for {}", key));
-                    }
-                }
-                "api.llmProviders.anthropic.apiKey" => {
-                    // Only update if not masked
-                    if !value.ends_with("...") {
-                        mapping.insert(
-                            serde_yaml::Value::String(part.clone()),
-                            serde_yaml::Value::String(value.to_string()),
-                        );
-                    }
-                }
-                _ => return Err(format!("Unknown config key: {}", key)),
-            };
-        } else {
-            // Not at final part - create/get the mapping
-            let mapping = if !current.is_mapping() {
-                *current = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
-                current.as_mapping_mut().unwrap()
-            } else {
-                current.as_mapping_mut().unwrap()
-            };
-            current = mapping
-                .entry(serde_yaml::Value::String(part.clone()))
-                .or_insert(serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
+    for part in parents {
+        if !current.is_mapping() {
+            *current = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
         }
+        current = current
+            .as_mapping_mut()
+            .unwrap()
+            .entry(serde_yaml::Value::String(part.clone()))
+            .or_insert(serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
+    }
+    if !current.is_mapping() {
+        *current = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
     }
+    current
+        .as_mapping_mut()
+        .unwrap()
+        .insert(serde_yaml::Value::String(last.clone()), value);
 
     Ok(())
 }
 
-fn update_config_value(config: &mut GlobalConfig, key: &str, value: &str) -> Result<(), String> {
-    let parts: Vec<&str> = key.split('.').collect();
-
-    match parts.as_slice() {
-        ["api", "logFile"] => {
-            config.api.log_file = Some(value.to_string());
-        }
-        ["api", "tls", "useTls"] => {
-            let use_tls = value
-                .parse::<bool>()
-                .map_err(|_| "Invalid boolean for useTls".to_string())?;
-            config.api.tls.use_tls = use_tls;
-        }
-        ["api", "localMode"] => {
-            let local_mode = value
-                .parse::<bool>()
-                .map_err(|_| "Invalid boolean for localMode".to_string())?;
-            config.api.local_mode = local_mode;
-        }
-        ["api", "llmProviders", "anthropic", "apiKey"] => {
-            // Only update if the value has changed (not masked)
-            if !value.ends_with("...") {
-                config.api.llm_providers.anthropic = Some(LlmProviderConfig {
-                    api_key: Some(value.to_string()),
-                });
-            }
-        }
-        ["bui", "logFile"] => {
-            config.bui.log_file = Some(value.to_string());
-        }
-        ["bui", "tls", "useTls"] => {
-            let use_tls = value
-                .parse::<bool>()
-                .map_err(|_| "Invalid boolean for useTls".to_string())?;
-            config.bui.tls.use_tls = use_tls;
-        }
-        ["bui", "localMode"] => {
-            let local_mode = value
-                .parse::<bool>()
-                .map_err(|_| "Invalid boolean for localMode".to_string())?;
-            config.bui.local_mode = local_mode;
-        }
-        _ => {
-            error!("Unknown config key: {}", key);
-            return Err(format!("Unknown config key: {}", key));
+/// Set `value` at `path` in a serialized config. Optional sections (e.g.
+/// llmProviders.anthropic) are absent or null until first set, so they are created.
+/// Returns None if the path runs through a non-object value.
+fn set_json_path(
+    mut root: serde_json::Value,
+    path: &[String],
+    value: serde_json::Value,
+) -> Option<serde_json::Value> {
+    let (last, parents) = path.split_last()?;
+    let mut node = &mut root;
+    for part in parents {
+        node = node
+            .as_object_mut()?
+            .entry(part.clone())
+            .or_insert(serde_json::Value::Null);
+        if node.is_null() {
+            *node = serde_json::Value::Object(serde_json::Map::new());
         }
     }
+    node.as_object_mut()?.insert(last.clone(), value);
+    Some(root)
+}
 
-    Ok(())
+/// Set any field of `config` by dot-notation key, coercing `value` to the field's type.
+/// Returns the value that was stored, or None when nothing changed (a masked API key
+/// echoed back by the frontend).
+fn update_config_value(
+    config: &mut GlobalConfig,
+    key: &str,
+    value: &str,
+) -> Result<Option<serde_json::Value>, String> {
+    let path = config_key_path(key);
+    let field = path.join(".");
+
+    // The frontend only ever sees a masked key; sending it back must not overwrite the real one
+    if field == "api.llmProviders.anthropic.apiKey" && value.ends_with("...") {
+        return Ok(None);
+    }
+
+    let unknown_key = || {
+        error!("Unknown config key: {}", key);
+        format!("Unknown config key: {}", key)
+    };
+
+    let root =
+        serde_json::to_value(&*config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    let current = json_at(&root, &path).cloned();
+    let mut new_value = coerce_config_value(key, current.as_ref(), value)?;
+
+    let apply = |new_value: serde_json::Value| -> Result<GlobalConfig, String> {
+        let updated = set_json_path(root.clone(), &path, new_value).ok_or_else(unknown_key)?;
+        serde_json::from_value::<GlobalConfig>(updated)
+            .map_err(|e| format!("Invalid value for {}: {}", key, e))
+    };
+    let mut updated = apply(new_value.clone());
+    // A guessed bool/number for an unset field may really be a string
+    if updated.is_err() && current.is_none() && !new_value.is_string() {
+        new_value = serde_json::Value::String(value.to_string());
+        updated = apply(new_value.clone());
+    }
+    let updated = updated?;
+
+    // serde ignores unknown fields, so make sure the key survived the round trip
+    let reserialized =
+        serde_json::to_value(&updated).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    if json_at(&reserialized, &path).is_none() {
+        return Err(unknown_key());
+    }
+
+    if let Some(problem) = updated.validate().into_iter().find(|e| e.field == field) {
+        return Err(format!("Invalid value for {}: {}", key, problem.problem));
+    }
+
+    *config = updated;
+    Ok(Some(new_value))
 }
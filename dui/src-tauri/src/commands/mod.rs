@@ -1,7 +1,16 @@
 pub mod api_status;
+pub mod autostart;
 pub mod bui_status;
 pub mod config;
+pub mod disk_space;
+pub mod path_integration;
+pub mod pid_file;
+pub mod probe;
+pub mod process;
 pub mod proxy;
+pub mod retry;
 pub mod server_status;
+pub mod smoke_test;
+pub mod status;
 pub mod upgrade;
 pub mod version;
@@ -0,0 +1,304 @@
+use log::{debug, info};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+use crate::commands::upgrade::get_install_location;
+
+#[cfg(not(target_os = "windows"))]
+use std::fs;
+#[cfg(not(target_os = "windows"))]
+use std::io::Write;
+#[cfg(target_os = "windows")]
+use std::process::Command;
+
+// Written above the PATH line so users can tell where it came from
+#[cfg(not(target_os = "windows"))]
+const PROFILE_MARKER: &str = "# Added by Beyond Better";
+#[cfg(target_os = "windows")]
+const USER_PATH_LOCATION: &str = r"HKCU\Environment\Path";
+
+#[derive(Debug, Serialize)]
+pub struct PathIntegration {
+    install_dir: PathBuf,
+    /// The directory is on the PATH this app was started with
+    in_current_path: bool,
+    /// Shell profile or registry value that adds the directory for new shells
+    configured_in: Option<String>,
+    /// Whether `bb` can be run from a terminal, possibly after restarting it
+    on_path: bool,
+    /// Terminals that are already open won't see the directory until restarted
+    restart_required: bool,
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    let normalize = |path: &Path| {
+        let path = path.to_string_lossy();
+        let path = path.trim_end_matches(['/', '\\']);
+        if cfg!(target_os = "windows") {
+            path.to_lowercase()
+        } else {
+            path.to_string()
+        }
+    };
+    normalize(a) == normalize(b)
+}
+
+fn in_current_path(dir: &Path) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|entry| same_dir(&entry, dir)))
+        .unwrap_or(false)
+}
+
+/// Profile file read by new shells of the user's login shell
+#[cfg(not(target_os = "windows"))]
+fn shell_profile() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
+    let shell = std::env::var("SHELL").unwrap_or_default();
+    let profile = match Path::new(&shell).file_name().and_then(|name| name.to_str()) {
+        Some("zsh") => home.join(".zshrc"),
+        Some("fish") => home.join(".config").join("fish").join("config.fish"),
+        // Terminal.app starts login shells, which don't read .bashrc
+        Some("bash") if cfg!(target_os = "macos") => home.join(".bash_profile"),
+        Some("bash") => home.join(".bashrc"),
+        _ => home.join(".profile"),
+    };
+    Ok(profile)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn profile_line(profile: &Path, dir: &Path) -> String {
+    if profile.ends_with("config.fish") {
+        format!("fish_add_path \"{}\"", dir.display())
+    } else {
+        format!("export PATH=\"{}:$PATH\"", dir.display())
+    }
+}
+
+/// Whether `profile` already has the line adding `dir` to PATH
+#[cfg(not(target_os = "windows"))]
+fn profile_adds(profile: &Path, dir: &Path) -> bool {
+    fs::read_to_string(profile)
+        .map(|content| content.contains(&profile_line(profile, dir)))
+        .unwrap_or(false)
+}
+
+/// The profile that adds `dir` to PATH, if it already mentions it
+#[cfg(not(target_os = "windows"))]
+fn configured_in(dir: &Path) -> Result<Option<String>, String> {
+    let profile = shell_profile()?;
+    Ok(profile_adds(&profile, dir).then(|| profile.display().to_string()))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn configure_path(dir: &Path) -> Result<String, String> {
+    let profile = shell_profile()?;
+    append_profile_line(&profile, dir)?;
+    Ok(profile.display().to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn append_profile_line(profile: &Path, dir: &Path) -> Result<(), String> {
+    if let Some(parent) = profile.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(profile)
+        .map_err(|e| format!("Failed to open {}: {}", profile.display(), e))?;
+    writeln!(file, "\n{}\n{}", PROFILE_MARKER, profile_line(profile, dir))
+        .map_err(|e| format!("Failed to update {}: {}", profile.display(), e))
+}
+
+/// Run a PowerShell snippet with the install directory in BB_INSTALL_DIR, so the path is
+/// never interpolated into the script
+#[cfg(target_os = "windows")]
+fn run_powershell(script: &str, dir: &Path) -> Result<String, String> {
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .env("BB_INSTALL_DIR", dir)
+        .output()
+        .map_err(|e| format!("Failed to run powershell: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "PowerShell failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn user_path(dir: &Path) -> Result<String, String> {
+    run_powershell("[Environment]::GetEnvironmentVariable('Path', 'User')", dir)
+}
+
+#[cfg(target_os = "windows")]
+fn path_value_contains(path_value: &str, dir: &Path) -> bool {
+    path_value
+        .split(';')
+        .any(|entry| same_dir(Path::new(entry.trim()), dir))
+}
+
+#[cfg(target_os = "windows")]
+fn configured_in(dir: &Path) -> Result<Option<String>, String> {
+    let configured = path_value_contains(&user_path(dir)?, dir);
+    Ok(configured.then(|| USER_PATH_LOCATION.to_string()))
+}
+
+#[cfg(target_os = "windows")]
+fn configure_path(dir: &Path) -> Result<String, String> {
+    // Edit the registry value directly: SetEnvironmentVariable would expand entries like
+    // %USERPROFILE%\bin and turn the REG_EXPAND_SZ value into a plain REG_SZ
+    run_powershell(
+        "$key = [Microsoft.Win32.Registry]::CurrentUser.CreateSubKey('Environment'); \
+         $p = $key.GetValue('Path', '', 'DoNotExpandEnvironmentNames'); \
+         $kind = if ($key.GetValueNames() -contains 'Path') { $key.GetValueKind('Path') } else { 'ExpandString' }; \
+         if ($p -and -not $p.EndsWith(';')) { $p += ';' }; \
+         $key.SetValue('Path', $p + $env:BB_INSTALL_DIR, $kind); \
+         $key.Close()",
+        dir,
+    )?;
+    broadcast_environment_change();
+    Ok(USER_PATH_LOCATION.to_string())
+}
+
+/// Tell Explorer and other top-level windows to reload the environment, so terminals
+/// started from them see the new Path
+#[cfg(target_os = "windows")]
+fn broadcast_environment_change() {
+    use windows_sys::Win32::Foundation::HWND;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        SendMessageTimeoutW, SMTO_ABORTIFHUNG, WM_SETTINGCHANGE,
+    };
+
+    const HWND_BROADCAST: HWND = 0xffff;
+    let area: Vec<u16> = "Environment"
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut result = 0;
+    let sent = unsafe {
+        SendMessageTimeoutW(
+            HWND_BROADCAST,
+            WM_SETTINGCHANGE,
+            0,
+            area.as_ptr() as isize,
+            SMTO_ABORTIFHUNG,
+            5000,
+            &mut result,
+        )
+    };
+    if sent == 0 {
+        log::warn!(
+            "Failed to broadcast environment change: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+fn path_integration(install_dir: PathBuf) -> Result<PathIntegration, String> {
+    let in_current_path = in_current_path(&install_dir);
+    let configured_in = configured_in(&install_dir)?;
+    Ok(PathIntegration {
+        on_path: in_current_path || configured_in.is_some(),
+        restart_required: configured_in.is_some() && !in_current_path,
+        install_dir,
+        in_current_path,
+        configured_in,
+    })
+}
+
+/// Report whether the binary install directory is on the user's PATH
+#[command]
+pub async fn check_path_integration() -> Result<PathIntegration, String> {
+    let location = get_install_location().map_err(|e| e.to_string())?;
+    path_integration(location.path)
+}
+
+/// Add the binary install directory to PATH for new shells: via the login shell's profile on
+/// macOS and Linux, and the user Path environment variable on Windows. Does nothing when the
+/// directory is already on PATH or already configured.
+#[command]
+pub async fn add_to_path() -> Result<PathIntegration, String> {
+    let location = get_install_location().map_err(|e| e.to_string())?;
+    let install_dir = location.path;
+    if in_current_path(&install_dir) {
+        debug!("{} is already on PATH", install_dir.display());
+    } else if let Some(configured_in) = configured_in(&install_dir)? {
+        debug!(
+            "{} already adds {} to PATH",
+            configured_in,
+            install_dir.display()
+        );
+    } else {
+        let configured_in = configure_path(&install_dir)?;
+        info!(
+            "Added {} to PATH in {}",
+            install_dir.display(),
+            configured_in
+        );
+    }
+    path_integration(install_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_dir_ignores_trailing_separators() {
+        assert!(same_dir(
+            Path::new("/opt/bb/bin/"),
+            Path::new("/opt/bb/bin")
+        ));
+        assert!(!same_dir(Path::new("/opt/bb/bin"), Path::new("/opt/bb")));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn same_dir_ignores_case_on_windows() {
+        assert!(same_dir(
+            Path::new(r"C:\Users\Me\AppData\Local\BeyondBetter\bin\"),
+            Path::new(r"c:\users\me\appdata\local\beyondbetter\bin"),
+        ));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn path_value_entries_are_matched_whole() {
+        let dir = Path::new(r"C:\BB\bin");
+        assert!(path_value_contains(r"C:\Windows; c:\bb\bin\ ;", dir));
+        assert!(!path_value_contains(r"C:\BB\bin2;C:\BB", dir));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn profile_line_matches_shell() {
+        let dir = Path::new("/home/me/.bb/bin");
+        assert_eq!(
+            profile_line(Path::new("/home/me/.zshrc"), dir),
+            "export PATH=\"/home/me/.bb/bin:$PATH\""
+        );
+        assert_eq!(
+            profile_line(Path::new("/home/me/.config/fish/config.fish"), dir),
+            "fish_add_path \"/home/me/.bb/bin\""
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn appended_profile_line_is_detected() {
+        let home = tempfile::TempDir::new().unwrap();
+        let profile = home.path().join(".config").join("fish").join("config.fish");
+        let dir = Path::new("/home/me/.bb/bin");
+        assert!(!profile_adds(&profile, dir));
+
+        append_profile_line(&profile, dir).unwrap();
+
+        assert!(profile_adds(&profile, dir));
+        assert!(!profile_adds(&profile, Path::new("/home/me/.bb")));
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Contents of the api.pid/bui.pid files. Older versions (and the CLI) wrote just the PID,
+/// which is still accepted when reading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PidFileInfo {
+    pub pid: i32,
+    /// When the process was started; None for legacy files and recovered PIDs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl PidFileInfo {
+    /// Record for a process that was just started
+    pub fn started_now(pid: i32, port: u16, version: Option<String>) -> Self {
+        Self {
+            pid,
+            started_at: Some(Utc::now()),
+            port: Some(port),
+            version,
+        }
+    }
+
+    /// Record for an already running process whose start time is unknown
+    pub fn recovered(pid: i32) -> Self {
+        Self {
+            pid,
+            started_at: None,
+            port: None,
+            version: None,
+        }
+    }
+}
+
+/// Write `info` to a temp file and rename it into place so readers never see a partial file
+pub fn write_pid_file(path: &Path, info: &PidFileInfo) -> Result<(), String> {
+    let content =
+        serde_json::to_string(info).map_err(|e| format!("Failed to serialize PID file: {}", e))?;
+    let tmp_path = path.with_extension("pid.tmp");
+    fs::write(&tmp_path, content).map_err(|e| format!("Failed to write PID file: {}", e))?;
+    fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("Failed to write PID file: {}", e)
+    })
+}
+
+/// Read a PID file in either the JSON or the legacy bare-integer format.
+/// Missing or unparseable files yield None.
+pub fn read_pid_file(path: &Path) -> Result<Option<PidFileInfo>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read PID file: {}", e)),
+    };
+    let content = content.trim();
+    if let Ok(pid) = content.parse::<i32>() {
+        return Ok(Some(PidFileInfo::recovered(pid)));
+    }
+    Ok(serde_json::from_str(content).ok())
+}
+
+/// Executable name of the running process `pid`, if it can be determined
+fn process_name(pid: i32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        // exe can't be read for other users' processes; comm (truncated to 15 chars) can
+        if let Ok(exe) = fs::read_link(format!("/proc/{}/exe", pid)) {
+            return exe
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string());
+        }
+        fs::read_to_string(format!("/proc/{}/comm", pid))
+            .ok()
+            .map(|comm| comm.trim().to_string())
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("ps")
+            .args(["-p", &pid.to_string(), "-o", "comm="])
+            .output()
+            .ok()?;
+        let comm = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Path::new(&comm)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let output = std::process::Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+            .output()
+            .ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // "bb-api.exe","1234",...; an informational message when nothing matches
+        let line = stdout.lines().next()?;
+        line.starts_with('"')
+            .then(|| line.split(',').next())
+            .flatten()
+            .map(|name| name.trim_matches('"').to_string())
+    }
+}
+
+/// Whether `pid` is running `binary` (e.g. "bb-api"). PIDs are recycled, so after a
+/// reboot the PID in a stale PID file may belong to an unrelated program. When the name
+/// can't be determined the process is given the benefit of the doubt.
+pub fn is_service_process(pid: i32, binary: &str) -> bool {
+    match process_name(pid) {
+        Some(name) => name.to_lowercase().starts_with(binary),
+        None => true,
+    }
+}
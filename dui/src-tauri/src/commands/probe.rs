@@ -0,0 +1,87 @@
+use log::info;
+use once_cell::sync::Lazy;
+use std::net::Ipv6Addr;
+use std::time::Duration;
+
+/// How long a status probe may take before the service is considered unresponsive
+pub const SERVICE_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Shared so probes reuse connections instead of building a client each time
+static PROBE_CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+
+/// `hostname` as it must appear in a URL: IPv6 literals such as `::1` are wrapped in
+/// brackets (with any zone ID escaped); names and IPv4 addresses are used as-is
+pub fn url_host(hostname: &str) -> String {
+    let address = hostname.split('%').next().unwrap_or_default();
+    if address.parse::<Ipv6Addr>().is_ok() {
+        format!("[{}]", hostname.replace('%', "%25"))
+    } else {
+        hostname.to_string()
+    }
+}
+
+/// Status endpoint of an API or BUI listening on `hostname:port`
+pub fn service_status_url(scheme: &str, hostname: &str, port: u16) -> String {
+    format!("{}://{}:{}/api/v1/status", scheme, url_host(hostname), port)
+}
+
+/// GET `url` and report whether it answered with a success status within `timeout`.
+/// A hanging endpoint counts as not responding rather than blocking the caller.
+pub async fn probe_service(url: &str, timeout: Duration) -> bool {
+    match PROBE_CLIENT.get(url).timeout(timeout).send().await {
+        Ok(response) => {
+            info!("{} responded with status: {}", url, response.status());
+            response.status().is_success()
+        }
+        Err(e) if e.is_timeout() => {
+            info!("{} did not respond within {}s", url, timeout.as_secs());
+            false
+        }
+        Err(e) => {
+            info!("Failed to connect to {}: {}", url, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_valid(url: &str, host: url::Host<&str>, port: u16) {
+        let parsed =
+            reqwest::Url::parse(url).unwrap_or_else(|e| panic!("{} is invalid: {}", url, e));
+        assert_eq!(parsed.host(), Some(host));
+        assert_eq!(parsed.port(), Some(port));
+        assert_eq!(parsed.path(), "/api/v1/status");
+    }
+
+    #[test]
+    fn ipv6_loopback_is_bracketed() {
+        let url = service_status_url("https", "::1", 3162);
+
+        assert_eq!(url, "https://[::1]:3162/api/v1/status");
+        assert_valid(&url, url::Host::Ipv6(Ipv6Addr::LOCALHOST), 3162);
+    }
+
+    #[test]
+    fn ipv6_zone_id_is_escaped() {
+        assert_eq!(url_host("fe80::1%eth0"), "[fe80::1%25eth0]");
+    }
+
+    #[test]
+    fn hostname_is_used_as_is() {
+        let url = service_status_url("http", "localhost", 8000);
+
+        assert_eq!(url, "http://localhost:8000/api/v1/status");
+        assert_valid(&url, url::Host::Domain("localhost"), 8000);
+    }
+
+    #[test]
+    fn ipv4_address_is_used_as_is() {
+        let url = service_status_url("http", "127.0.0.1", 3162);
+
+        assert_eq!(url, "http://127.0.0.1:3162/api/v1/status");
+        assert_valid(&url, url::Host::Ipv4(std::net::Ipv4Addr::LOCALHOST), 3162);
+    }
+}
@@ -0,0 +1,49 @@
+use log::{error, info};
+use std::process::Command as StdCommand;
+
+/// PIDs of every running process for `binary` (e.g. "bb-api"), whether or not it was
+/// started by the app
+pub async fn find_all_processes(binary: &str) -> Result<Vec<i32>, String> {
+    let output = if cfg!(target_os = "windows") {
+        StdCommand::new("tasklist")
+            .args(["/fo", "csv", "/nh"])
+            .output()
+    } else if cfg!(target_os = "macos") {
+        StdCommand::new("pgrep").args(["-f", binary]).output()
+    } else {
+        // Linux
+        StdCommand::new("pgrep").args([binary]).output()
+    };
+
+    let output = output.map_err(|e| {
+        error!("Failed to list processes: {}", e);
+        format!("Failed to list processes: {}", e)
+    })?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let pids: Vec<i32> = if cfg!(target_os = "windows") {
+        // "bb-api.exe","1234","Console","1","12,345 K"; compare the image name exactly so
+        // other programs with the binary name in their path or title aren't picked up
+        let image_name = format!("{}.exe", binary);
+        stdout
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(',').map(|field| field.trim_matches('"'));
+                let name = fields.next()?;
+                let pid = fields.next()?;
+                name.eq_ignore_ascii_case(&image_name)
+                    .then(|| pid.parse::<i32>().ok())
+                    .flatten()
+            })
+            .collect()
+    } else {
+        // pgrep prints one PID per line
+        stdout
+            .lines()
+            .filter_map(|line| line.trim().parse::<i32>().ok())
+            .collect()
+    };
+
+    info!("Found {} {} processes: {:?}", pids.len(), binary, pids);
+    Ok(pids)
+}
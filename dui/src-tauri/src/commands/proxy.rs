@@ -1,22 +1,58 @@
-use crate::proxy::HttpProxy;
-use log::{debug, info};
+use crate::config::{read_global_config_file, write_global_config, GlobalConfig};
+use crate::logging::AccessLogEntry;
+use crate::proxy::{BenchmarkResult, BodyLimits, HttpProxy, ProxyStats, ProxyTargetHealth};
+use log::{debug, info, warn};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
+// Upper bound on entries returned by get_proxy_access_log, also used when limit is 0
+const MAX_ACCESS_LOG_ENTRIES: usize = 1000;
+const TARGET_PREFLIGHT_TIMEOUT: Duration = Duration::from_secs(3);
+
 #[tauri::command]
 pub async fn get_proxy_info(
     state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
 ) -> Result<crate::proxy::ProxyInfo, String> {
     debug!("get_proxy_info command invoked");
     let proxy = state.read().await;
+    Ok(proxy_info(&proxy).await)
+}
+
+pub(crate) async fn proxy_info(proxy: &HttpProxy) -> crate::proxy::ProxyInfo {
     let target = proxy.target_url.read().await.clone();
+    let fallback_targets = proxy.fallback_targets.read().await.clone();
+    let active_target = proxy.active_target().await;
     let is_running = proxy.is_running().await;
 
-    Ok(crate::proxy::ProxyInfo {
+    crate::proxy::ProxyInfo {
         port: proxy.port,
         target,
+        fallback_targets,
+        active_target,
         is_running,
-    })
+    }
+}
+
+#[tauri::command]
+pub async fn get_proxy_stats(
+    state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<ProxyStats, String> {
+    debug!("get_proxy_stats command invoked");
+    let proxy = state.read().await;
+    Ok(proxy.stats().await)
+}
+
+#[tauri::command]
+pub async fn get_proxy_target_health(
+    state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<ProxyTargetHealth, String> {
+    debug!("get_proxy_target_health command invoked");
+    let proxy = state.read().await;
+    Ok(proxy.target_health().await)
 }
 
 #[tauri::command]
@@ -37,6 +73,20 @@ pub async fn stop_proxy_server(
     proxy.stop().await
 }
 
+/// Save a setting changed at runtime to config.yaml so it survives a restart. The live
+/// change has already been applied, so a failed write is only logged.
+fn persist_proxy_setting(setting: &str, update: impl FnOnce(&mut GlobalConfig)) {
+    let result = read_global_config_file()
+        .map_err(|e| e.to_string())
+        .and_then(|mut config| {
+            update(&mut config);
+            write_global_config(&config)
+        });
+    if let Err(e) = result {
+        warn!("Failed to save {} to config: {}", setting, e);
+    }
+}
+
 #[tauri::command]
 pub async fn set_debug_mode(
     debug_mode: bool,
@@ -45,16 +95,126 @@ pub async fn set_debug_mode(
     debug!("set_debug_mode called with debug_mode: {}", debug_mode);
     let proxy = state.read().await;
     *proxy.debug_mode.write().await = debug_mode;
+    persist_proxy_setting("dui.proxyDebugMode", |config| {
+        config.dui.proxy_debug_mode = Some(debug_mode)
+    });
     debug!("Successfully updated debug mode to: {}", debug_mode);
     Ok(())
 }
 
+/// Set the largest request and response bodies the proxy passes through. Applies to
+/// requests started after the call.
+#[tauri::command]
+pub async fn set_proxy_body_limits(
+    max_request_bytes: u64,
+    max_response_bytes: u64,
+    state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<BodyLimits, String> {
+    if max_request_bytes == 0 || max_response_bytes == 0 {
+        return Err("Body limits must be greater than zero".to_string());
+    }
+    let limits = BodyLimits {
+        max_request_bytes,
+        max_response_bytes,
+    };
+    let proxy = state.read().await;
+    *proxy.body_limits.write().await = limits;
+    info!(
+        "Proxy body limits set to {} bytes (request), {} bytes (response)",
+        max_request_bytes, max_response_bytes
+    );
+    Ok(limits)
+}
+
+/// Write request and response bodies (the first `max_bytes` of each) to proxy-debug.log,
+/// with credentials redacted. Only available in debug mode, and stops when it is turned off.
+#[tauri::command]
+pub async fn set_proxy_capture_bodies(
+    enabled: bool,
+    max_bytes: usize,
+    state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<(), String> {
+    let proxy = state.read().await;
+    if enabled {
+        if !*proxy.debug_mode.read().await {
+            return Err("Body capture is only available in debug mode".to_string());
+        }
+        if max_bytes == 0 {
+            return Err("max_bytes must be greater than zero".to_string());
+        }
+    }
+    proxy.set_body_capture(enabled.then_some(max_bytes)).await;
+    info!(
+        "Proxy body capture {}",
+        if enabled {
+            format!("enabled ({} bytes per body)", max_bytes)
+        } else {
+            "disabled".to_string()
+        }
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_proxy_maintenance(
+    enabled: bool,
+    state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<(), String> {
+    debug!("set_proxy_maintenance called with enabled: {}", enabled);
+    let proxy = state.read().await;
+    *proxy.maintenance_mode.write().await = enabled;
+    info!(
+        "Proxy maintenance mode {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(())
+}
+
+/// Confirm the target answers HTTP at all; any status (even 404) counts as reachable
+async fn check_target_reachable(target: &str) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(TARGET_PREFLIGHT_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let base = target.trim_end_matches('/');
+    let mut last_error = None;
+    for url in [format!("{}/_health", base), format!("{}/", base)] {
+        match client.get(&url).send().await {
+            Ok(response) => {
+                debug!("Target pre-flight {} -> {}", url, response.status());
+                return Ok(());
+            }
+            Err(e) => {
+                debug!("Target pre-flight {} failed: {}", url, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    let e = last_error.expect("pre-flight tried at least one URL");
+    let reason = if e.is_timeout() {
+        format!("no response within {}s", TARGET_PREFLIGHT_TIMEOUT.as_secs())
+    } else if e.is_connect() {
+        format!("connection failed: {}", e)
+    } else {
+        e.to_string()
+    };
+    Err(format!("Target {} is not reachable ({})", target, reason))
+}
+
+/// Update the proxy target. Unless `force` is set, the target must answer a short
+/// pre-flight request so a typo doesn't leave the app proxying to a dead host.
 #[tauri::command]
 pub async fn set_proxy_target(
     target: String,
+    force: bool,
     state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
 ) -> Result<(), String> {
-    debug!("set_proxy_target called with target: {}", target);
+    debug!(
+        "set_proxy_target called with target: {}, force: {}",
+        target, force
+    );
     // Validate target URL
     let parsed_url =
         reqwest::Url::parse(&target).map_err(|e| format!("Invalid target URL: {}", e))?;
@@ -73,10 +233,135 @@ pub async fn set_proxy_target(
         parsed_url.port()
     );
 
+    if force {
+        warn!("Skipping reachability check for proxy target: {}", target);
+    } else {
+        check_target_reachable(&target).await?;
+    }
+
     debug!("Setting proxy target to: {}", target);
     let proxy = state.read().await;
     *proxy.target_url.write().await = target.clone();
+    proxy.reset_target_health();
+    persist_proxy_setting("dui.proxyTarget", |config| {
+        config.dui.proxy_target = Some(target.clone())
+    });
     debug!("Successfully updated proxy target to: {}", target);
     info!("Proxy target updated to: {}", target);
     Ok(())
 }
+
+/// Replace the default target and its fallbacks in one go. The first entry becomes the
+/// primary target; the rest are tried in order when it can't be reached.
+#[tauri::command]
+pub async fn set_proxy_targets(
+    targets: Vec<String>,
+    state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<(), String> {
+    debug!("set_proxy_targets called with targets: {:?}", targets);
+    if targets.is_empty() {
+        return Err("At least one target is required".to_string());
+    }
+    for target in &targets {
+        HttpProxy::validate_target(target)?;
+    }
+
+    let proxy = state.read().await;
+    *proxy.target_url.write().await = targets[0].clone();
+    *proxy.fallback_targets.write().await = targets[1..].to_vec();
+    proxy.reset_target_health();
+    persist_proxy_setting("proxy targets", |config| {
+        config.dui.proxy_target = Some(targets[0].clone());
+        config.proxy.fallback_targets = targets[1..].to_vec();
+    });
+    info!("Proxy targets updated to: {:?}", targets);
+    Ok(())
+}
+
+/// Replace the headers added to every proxied request and WebSocket handshake. Header
+/// values may be credentials, so only the names are logged.
+#[tauri::command]
+pub async fn set_proxy_headers(
+    headers: BTreeMap<String, String>,
+    state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<(), String> {
+    let parsed = HttpProxy::parse_extra_headers(&headers)?;
+    let proxy = state.read().await;
+    *proxy.extra_headers.write().await = parsed;
+    info!(
+        "Proxy extra headers updated: {:?}",
+        headers.keys().collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn benchmark_proxy(
+    requests: usize,
+    concurrency: usize,
+    state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<BenchmarkResult, String> {
+    debug!(
+        "benchmark_proxy called with requests: {}, concurrency: {}",
+        requests, concurrency
+    );
+    let proxy = state.read().await;
+    proxy.benchmark(requests, concurrency).await
+}
+
+/// Last failed proxy requests (4xx/5xx or transport errors), newest first, without
+/// reading the access log file
+#[tauri::command]
+pub async fn get_proxy_recent_errors(
+    state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<Vec<AccessLogEntry>, String> {
+    debug!("get_proxy_recent_errors command invoked");
+    let proxy = state.read().await;
+    Ok(proxy.recent_errors().await)
+}
+
+#[tauri::command]
+pub async fn get_proxy_access_log(
+    limit: usize,
+    state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<Vec<AccessLogEntry>, String> {
+    debug!("get_proxy_access_log called with limit: {}", limit);
+    let limit = if limit == 0 {
+        MAX_ACCESS_LOG_ENTRIES
+    } else {
+        limit.min(MAX_ACCESS_LOG_ENTRIES)
+    };
+
+    let path = state.read().await.access_log_path().await;
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to open access log: {}", e)),
+    };
+
+    // Keep only the last `limit` parseable entries while streaming through the file
+    let mut entries = VecDeque::with_capacity(limit);
+    let mut skipped = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read access log: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<AccessLogEntry>(&line) {
+            Ok(entry) => {
+                if entries.len() == limit {
+                    entries.pop_front();
+                }
+                entries.push_back(entry);
+            }
+            Err(_) => skipped += 1,
+        }
+    }
+
+    if skipped > 0 {
+        warn!("Skipped {} malformed lines in {:?}", skipped, path);
+    }
+
+    // Newest first
+    Ok(entries.into_iter().rev().collect())
+}
@@ -0,0 +1,87 @@
+use log::warn;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+
+// Attempts made for release server and GitHub requests before giving up
+const RELEASE_FETCH_ATTEMPTS: u32 = 3;
+// Doubled after each failed attempt: 500ms, 1s, ...
+const RELEASE_FETCH_BASE_DELAY: Duration = Duration::from_millis(500);
+// Per-attempt limit so a stalled connection is retried rather than hanging the check
+const RELEASE_FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub const RATE_LIMIT_ERROR: &str = "GitHub rate limit exceeded, try later";
+
+fn is_retryable_error(e: &reqwest::Error) -> bool {
+    // is_request covers connections reset while sending
+    e.is_timeout() || e.is_connect() || e.is_request()
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT
+}
+
+/// GitHub answers an exhausted quota with 403 and `x-ratelimit-remaining: 0`; any other 403
+/// (e.g. a forbidden download) is a plain error
+fn is_rate_limited(response: &Response) -> bool {
+    match response.status() {
+        StatusCode::TOO_MANY_REQUESTS => true,
+        StatusCode::FORBIDDEN => {
+            response.url().host_str() == Some("api.github.com")
+                && response
+                    .headers()
+                    .get("x-ratelimit-remaining")
+                    .is_some_and(|remaining| remaining.as_bytes() == b"0")
+        }
+        _ => false,
+    }
+}
+
+/// Send the request produced by `build`, retrying timeouts, dropped connections and 5xx
+/// responses with exponential backoff. A rate limit is reported straight away since retrying
+/// only burns more of the quota, and other 403s fail as HTTP errors; remaining statuses are
+/// left to the caller.
+pub async fn send_with_retry(
+    what: &str,
+    build: impl Fn() -> RequestBuilder,
+) -> Result<Response, String> {
+    let mut attempt = 1;
+    loop {
+        let error = match build().timeout(RELEASE_FETCH_TIMEOUT).send().await {
+            Ok(response) if is_rate_limited(&response) => {
+                warn!("{}: rate limited ({})", what, response.status());
+                return Err(RATE_LIMIT_ERROR.to_string());
+            }
+            Ok(response) if response.status() == StatusCode::FORBIDDEN => {
+                return Err(format!(
+                    "Failed to fetch {}: HTTP {}",
+                    what,
+                    response.status()
+                ));
+            }
+            Ok(response) if is_retryable_status(response.status()) => {
+                format!("server returned {}", response.status())
+            }
+            Ok(response) => return Ok(response),
+            Err(e) if is_retryable_error(&e) => e.to_string(),
+            Err(e) => return Err(format!("Failed to fetch {}: {}", what, e)),
+        };
+
+        if attempt >= RELEASE_FETCH_ATTEMPTS {
+            return Err(format!(
+                "Failed to fetch {} after {} attempts: {}",
+                what, attempt, error
+            ));
+        }
+        let delay = RELEASE_FETCH_BASE_DELAY * 2u32.pow(attempt - 1);
+        warn!(
+            "Fetching {} failed (attempt {}/{}), retrying in {}ms: {}",
+            what,
+            attempt,
+            RELEASE_FETCH_ATTEMPTS,
+            delay.as_millis(),
+            error
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
@@ -1,15 +1,42 @@
-use reqwest;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tauri::command;
+use tokio::sync::RwLock;
 
-use crate::config::read_global_config;
+use crate::commands::api_status::robust_terminate_process;
+use crate::commands::pid_file::{is_service_process, read_pid_file, write_pid_file, PidFileInfo};
+use crate::commands::probe::{probe_service, service_status_url, SERVICE_PROBE_TIMEOUT};
+use crate::commands::process::find_all_processes;
+use crate::config::{read_global_config, GlobalConfig};
+use crate::error::BBError;
+use crate::proxy::{HttpProxy, FALLBACK_PORTS};
 
 const API_PID_FILE_NAME: &str = "api.pid";
 const BUI_PID_FILE_NAME: &str = "bui.pid"; // Must match the name used in BUI's fresh.config.ts
 const APP_NAME: &str = "dev.beyondbetter.app";
 
+// When each service was first seen alive but unresponsive, cleared once it recovers or exits
+static UNRESPONSIVE_SINCE: Lazy<Mutex<HashMap<String, Instant>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceState {
+    /// No live process for the service
+    Stopped,
+    /// Process is alive and its endpoint responds
+    Running,
+    /// Process is alive but its endpoint does not respond
+    Zombie,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ServiceStatus {
     pub pid_exists: bool,
@@ -17,6 +44,9 @@ pub struct ServiceStatus {
     pub service_responds: bool,
     pub pid: Option<i32>,
     pub error: Option<String>,
+    pub state: ServiceState,
+    /// Seconds since the service was first observed unresponsive (only set when zombie)
+    pub unresponsive_secs: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -26,7 +56,49 @@ pub struct ServerStatus {
     pub all_services_ready: bool,
 }
 
-fn get_app_runtime_dir() -> Result<PathBuf, String> {
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServiceRestart {
+    pub service: String,
+    pub previous_state: ServiceState,
+    /// "none", "started" or "restarted"
+    pub action: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortCheck {
+    pub service: String,
+    pub port: u16,
+    pub in_use: bool,
+    pub pid: Option<i32>,
+    pub held_by_self: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortsStatus {
+    pub all_free: bool,
+    pub ports: Vec<PortCheck>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KilledProcess {
+    pub service: String,
+    pub pid: i32,
+    /// Whether the process was confirmed gone afterwards
+    pub terminated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForceStopReport {
+    pub processes: Vec<KilledProcess>,
+    /// Whether the proxy was running and has been stopped
+    pub proxy_stopped: bool,
+    pub pid_files_removed: bool,
+    pub errors: Vec<String>,
+}
+
+pub(crate) fn get_app_runtime_dir() -> Result<PathBuf, String> {
     #[cfg(target_os = "macos")]
     {
         let home_dir =
@@ -71,21 +143,16 @@ fn get_pid_file_path(service: &str) -> Result<PathBuf, String> {
     Ok(get_app_runtime_dir()?.join(filename))
 }
 
-pub async fn save_pid(service: &str, pid: i32) -> Result<(), String> {
-    let pid_file = get_pid_file_path(service)?;
-    fs::write(&pid_file, pid.to_string()).map_err(|e| format!("Failed to write PID file: {}", e))
+pub async fn save_pid(service: &str, info: PidFileInfo) -> Result<(), String> {
+    write_pid_file(&get_pid_file_path(service)?, &info)
+}
+
+pub async fn get_pid_info(service: &str) -> Result<Option<PidFileInfo>, String> {
+    read_pid_file(&get_pid_file_path(service)?)
 }
 
 pub async fn get_pid(service: &str) -> Result<Option<i32>, String> {
-    let pid_file = get_pid_file_path(service)?;
-    match fs::read_to_string(&pid_file) {
-        Ok(content) => match content.trim().parse::<i32>() {
-            Ok(pid) => Ok(Some(pid)),
-            Err(_) => Ok(None),
-        },
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
-        Err(e) => Err(format!("Failed to read PID file: {}", e)),
-    }
+    Ok(get_pid_info(service).await?.map(|info| info.pid))
 }
 
 pub async fn remove_pid(service: &str) -> Result<(), String> {
@@ -124,46 +191,19 @@ fn check_process_exists(pid: i32) -> bool {
     }
 }
 
-async fn check_api_responds(hostname: &str, port: u16, use_tls: bool) -> Result<bool, String> {
-    let scheme = if use_tls { "https" } else { "http" };
-    let url = format!("{}://{}:{}/api/v1/status", scheme, hostname, port);
-
-    println!("Checking API status at: {}", url);
-
-    match reqwest::get(&url).await {
-        Ok(response) => {
-            let status = response.status();
-            println!("API responded with status: {}", status);
-            Ok(status.is_success())
-        }
-        Err(e) => {
-            println!("Failed to connect to API: {}", e);
-            Ok(false)
-        }
-    }
-}
-
-async fn check_bui_responds(hostname: &str, port: u16, use_tls: bool) -> Result<bool, String> {
+async fn check_service_responds(hostname: &str, port: u16, use_tls: bool) -> Result<bool, String> {
     let scheme = if use_tls { "https" } else { "http" };
-    let url = format!("{}://{}:{}/api/v1/status", scheme, hostname, port);
-
-    println!("Checking BUI status at: {}", url);
-
-    match reqwest::get(&url).await {
-        Ok(response) => {
-            let status = response.status();
-            println!("BUI responded with status: {}", status);
-            Ok(status.is_success())
-        }
-        Err(e) => {
-            println!("Failed to connect to BUI: {}", e);
-            Ok(false)
-        }
-    }
+    let url = service_status_url(scheme, hostname, port);
+    Ok(probe_service(&url, SERVICE_PROBE_TIMEOUT).await)
 }
 
-async fn check_service_status(service: &str) -> Result<ServiceStatus, String> {
-    println!("Checking {} status...", service.to_uppercase());
+/// Check one service. `config` avoids re-reading the global config when the caller already
+/// has it; otherwise it is only read if the process turns out to be alive.
+async fn check_service_status(
+    service: &str,
+    config: Option<&GlobalConfig>,
+) -> Result<ServiceStatus, String> {
+    debug!("Checking {} status...", service.to_uppercase());
 
     let mut status = ServiceStatus {
         pid_exists: false,
@@ -171,31 +211,48 @@ async fn check_service_status(service: &str) -> Result<ServiceStatus, String> {
         service_responds: false,
         pid: None,
         error: None,
+        state: ServiceState::Stopped,
+        unresponsive_secs: None,
     };
 
     // Level 1: Check PID file
     let pid = get_pid(service).await?;
     match pid {
         Some(pid) => {
-            println!("Found PID file with PID: {}", pid);
+            debug!("Found PID file with PID: {}", pid);
             status.pid = Some(pid);
 
-            // Level 2: Check if process exists
+            // Level 2: Check if process exists, and that it is still ours
             status.pid_exists = check_process_exists(pid);
-            println!("Process exists: {}", status.pid_exists);
+            if status.pid_exists && !is_service_process(pid, &format!("bb-{}", service)) {
+                warn!(
+                    "PID {} belongs to another program, treating {} as stopped",
+                    pid,
+                    service.to_uppercase()
+                );
+                status.pid_exists = false;
+            }
+            debug!("Process exists: {}", status.pid_exists);
 
             // Level 3: Check if service endpoint responds
             if status.pid_exists {
-                let config = read_global_config()
-                    .map_err(|e| format!("Failed to read global config: {}", e))?;
+                let owned_config;
+                let config = match config {
+                    Some(config) => config,
+                    None => {
+                        owned_config = read_global_config()
+                            .map_err(|e| format!("Failed to read global config: {}", e))?;
+                        &owned_config
+                    }
+                };
 
                 match service {
                     "api" => {
-                        println!(
+                        debug!(
                             "Checking API endpoint at {}:{}",
                             config.api.hostname, config.api.port
                         );
-                        match check_api_responds(
+                        match check_service_responds(
                             &config.api.hostname,
                             config.api.port,
                             config.api.tls.use_tls,
@@ -205,20 +262,20 @@ async fn check_service_status(service: &str) -> Result<ServiceStatus, String> {
                             Ok(responds) => {
                                 status.service_responds = responds;
                                 status.process_responds = responds;
-                                println!("API responds: {}", responds);
+                                debug!("API responds: {}", responds);
                             }
                             Err(e) => {
-                                println!("Error checking API response: {}", e);
+                                debug!("Error checking API response: {}", e);
                                 status.error = Some(e);
                             }
                         }
                     }
                     "bui" => {
-                        println!(
+                        debug!(
                             "Checking BUI endpoint at {}:{}",
                             config.bui.hostname, config.bui.port
                         );
-                        match check_bui_responds(
+                        match check_service_responds(
                             &config.bui.hostname,
                             config.bui.port,
                             config.bui.tls.use_tls,
@@ -228,10 +285,10 @@ async fn check_service_status(service: &str) -> Result<ServiceStatus, String> {
                             Ok(responds) => {
                                 status.service_responds = responds;
                                 status.process_responds = responds;
-                                println!("BUI responds: {}", responds);
+                                debug!("BUI responds: {}", responds);
                             }
                             Err(e) => {
-                                println!("Error checking BUI response: {}", e);
+                                debug!("Error checking BUI response: {}", e);
                                 status.error = Some(e);
                             }
                         }
@@ -243,17 +300,52 @@ async fn check_service_status(service: &str) -> Result<ServiceStatus, String> {
             }
         }
         None => {
-            println!("No PID file found for {}", service.to_uppercase());
+            debug!("No PID file found for {}", service.to_uppercase());
         }
     }
 
+    update_service_state(service, &mut status);
+
     Ok(status)
 }
 
+/// Derive the overall state from the individual checks, tracking how long a zombie has been unresponsive
+fn update_service_state(service: &str, status: &mut ServiceStatus) {
+    status.state = if !status.pid_exists {
+        ServiceState::Stopped
+    } else if status.service_responds {
+        ServiceState::Running
+    } else {
+        ServiceState::Zombie
+    };
+
+    let mut unresponsive_since = UNRESPONSIVE_SINCE.lock().unwrap();
+    if status.state == ServiceState::Zombie {
+        let since = unresponsive_since
+            .entry(service.to_string())
+            .or_insert_with(Instant::now);
+        status.unresponsive_secs = Some(since.elapsed().as_secs());
+    } else {
+        unresponsive_since.remove(service);
+    }
+}
+
 #[command]
-pub async fn check_server_status() -> Result<ServerStatus, String> {
-    let api_status = check_service_status("api").await?;
-    let bui_status = check_service_status("bui").await?;
+pub async fn check_server_status() -> Result<ServerStatus, BBError> {
+    let config = read_global_config()
+        .map_err(|e| BBError::ConfigError(format!("Failed to read config: {}", e)))?;
+    server_status(Some(&config))
+        .await
+        .map_err(BBError::ProcessError)
+}
+
+/// Status of both services, checked concurrently
+pub(crate) async fn server_status(config: Option<&GlobalConfig>) -> Result<ServerStatus, String> {
+    let (api_status, bui_status) = tokio::join!(
+        check_service_status("api", config),
+        check_service_status("bui", config)
+    );
+    let (api_status, bui_status) = (api_status?, bui_status?);
 
     //let all_services_ready = api_status.service_responds && bui_status.service_responds;
     let all_services_ready = api_status.service_responds;
@@ -266,35 +358,325 @@ pub async fn check_server_status() -> Result<ServerStatus, String> {
 }
 
 pub async fn reconcile_service_state(service: &str) -> Result<(), String> {
-    let status = check_service_status(service).await?;
+    let status = check_service_status(service, None).await?;
     let pid = get_pid(service).await?;
 
     if !status.pid_exists && pid.is_some() {
-        // PID file exists but process doesn't - clean up
+        // PID file exists but process doesn't (or the PID was reused) - clean up
         remove_pid(service).await?;
-    } else if status.pid_exists && !status.service_responds {
+    } else if status.state == ServiceState::Zombie {
         // Process exists but service doesn't respond - potential zombie
-        println!(
-            "{} process exists but is not responding. Consider restarting.",
-            service.to_uppercase()
+        warn!(
+            "{} process exists but has not responded for {}s. Consider restarting.",
+            service.to_uppercase(),
+            status.unresponsive_secs.unwrap_or(0)
         );
     } else if status.service_responds && pid.is_none() {
         // Service responds but no PID file - recover state if possible
         if let Some(pid) = status.pid {
-            println!(
+            info!(
                 "Recovering PID file for {} with process ID: {}",
                 service.to_uppercase(),
                 pid
             );
-            save_pid(service, pid).await?;
+            save_pid(service, PidFileInfo::recovered(pid)).await?;
         }
     }
 
     Ok(())
 }
 
+/// Start a stopped service, or restart a zombie one
+async fn restart_service(service: &str, previous_state: ServiceState) -> ServiceRestart {
+    let mut report = ServiceRestart {
+        service: service.to_string(),
+        previous_state,
+        action: "none".to_string(),
+        success: true,
+        error: None,
+    };
+    if previous_state == ServiceState::Running {
+        return report;
+    }
+
+    // Zombies go through the full restart so a wedged process can't end up running twice
+    let zombie = previous_state == ServiceState::Zombie;
+    report.action = if zombie { "restarted" } else { "started" }.to_string();
+    let started = match (service, zombie) {
+        ("api", true) => crate::api::restart_api()
+            .await
+            .map(|result| (result.success, result.error)),
+        ("api", false) => crate::api::start_api()
+            .await
+            .map(|result| (result.success, result.error)),
+        (_, true) => crate::bui::restart_bui()
+            .await
+            .map(|result| (result.success, result.error)),
+        (_, false) => crate::bui::start_bui()
+            .await
+            .map(|result| (result.success, result.error)),
+    };
+    match started {
+        Ok((true, _)) => info!("{} {}", service.to_uppercase(), report.action),
+        Ok((false, error)) => {
+            report.success = false;
+            report.error = Some(
+                error
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("{} failed to start", service)),
+            );
+        }
+        Err(e) => {
+            report.success = false;
+            report.error = Some(e.to_string());
+        }
+    }
+    report
+}
+
+/// Restart only the services that are down or unresponsive, leaving healthy ones untouched.
+/// The API is handled first since the BUI depends on it.
+#[command]
+pub async fn restart_unhealthy_services() -> Result<Vec<ServiceRestart>, String> {
+    let status = check_server_status().await?;
+
+    let mut reports = Vec::new();
+    for (service, state) in [("api", status.api.state), ("bui", status.bui.state)] {
+        if state != ServiceState::Running {
+            info!("{} is {:?}, restarting", service.to_uppercase(), state);
+        }
+        reports.push(restart_service(service, state).await);
+    }
+
+    Ok(reports)
+}
+
 pub async fn reconcile_all_services() -> Result<(), String> {
     reconcile_service_state("api").await?;
     reconcile_service_state("bui").await?;
     Ok(())
 }
+
+pub(crate) fn is_port_free(port: u16) -> bool {
+    std::net::TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+/// Make sure `port` is free before spawning `process_name`. A stray copy of that process
+/// holding it is terminated; any other occupant is reported back to the caller.
+pub(crate) async fn ensure_port_available(
+    hostname: &str,
+    port: u16,
+    process_name: &str,
+) -> Result<(), String> {
+    let port_is_free = || std::net::TcpListener::bind((hostname, port)).is_ok();
+    if port_is_free() {
+        return Ok(());
+    }
+
+    let owner = find_port_owner(port);
+    let bb_pids = find_all_processes(process_name).await.unwrap_or_default();
+    match owner {
+        Some(pid) if bb_pids.contains(&pid) => {
+            warn!(
+                "Port {} is held by stray {} process {}, terminating it",
+                port, process_name, pid
+            );
+            if robust_terminate_process(pid, process_name).await && port_is_free() {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Port {} is held by an unresponsive {} process (PID {}) that could not be stopped",
+                    port, process_name, pid
+                ))
+            }
+        }
+        Some(pid) => Err(format!(
+            "Port {} is in use by another application (PID {})",
+            port, pid
+        )),
+        None => Err(format!("Port {} is in use by another application", port)),
+    }
+}
+
+/// Best-effort lookup of the PID listening on a local TCP port
+pub(crate) fn find_port_owner(port: u16) -> Option<i32> {
+    let output = if cfg!(target_os = "windows") {
+        Command::new("netstat").args(["-ano", "-p", "tcp"]).output()
+    } else {
+        Command::new("lsof")
+            .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-t"])
+            .output()
+    };
+
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            debug!("Failed to look up owner of port {}: {}", port, e);
+            return None;
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    if cfg!(target_os = "windows") {
+        // Columns: Proto, Local Address, Foreign Address, State, PID
+        let port_suffix = format!(":{}", port);
+        stdout.lines().find_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() >= 5 && parts[1].ends_with(&port_suffix) && parts[3] == "LISTENING" {
+                parts[4].parse::<i32>().ok()
+            } else {
+                None
+            }
+        })
+    } else {
+        stdout
+            .lines()
+            .find_map(|line| line.trim().parse::<i32>().ok())
+    }
+}
+
+fn check_port(service: &str, port: u16) -> PortCheck {
+    let in_use = !is_port_free(port);
+    let pid = if in_use { find_port_owner(port) } else { None };
+    let held_by_self = pid == Some(std::process::id() as i32);
+
+    if in_use {
+        info!(
+            "Port {} ({}) is still in use (PID: {:?}{})",
+            port,
+            service,
+            pid,
+            if held_by_self { ", this app" } else { "" }
+        );
+    }
+
+    PortCheck {
+        service: service.to_string(),
+        port,
+        in_use,
+        pid,
+        held_by_self,
+    }
+}
+
+/// Check that the API, BUI and proxy ports are free, e.g. after stopping services.
+/// Ports held by this app (the running proxy) don't count against `all_free`.
+#[command]
+pub async fn verify_ports_free() -> Result<PortsStatus, String> {
+    let config =
+        read_global_config().map_err(|e| format!("Failed to read global config: {}", e))?;
+
+    let mut ports = vec![
+        check_port("api", config.api.port),
+        check_port("bui", config.bui.port),
+    ];
+    if let Some(port) = config
+        .dui
+        .proxy_port
+        .filter(|port| *port != 0 && !FALLBACK_PORTS.contains(port))
+    {
+        ports.push(check_port("proxy", port));
+    }
+    ports.extend(FALLBACK_PORTS.iter().map(|&port| check_port("proxy", port)));
+
+    let all_free = ports.iter().all(|p| !p.in_use || p.held_by_self);
+    if !all_free {
+        warn!(
+            "Ports still occupied: {:?}",
+            ports
+                .iter()
+                .filter(|p| p.in_use && !p.held_by_self)
+                .map(|p| p.port)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    Ok(PortsStatus { all_free, ports })
+}
+
+/// Panic button: terminate every bb-api and bb-bui process, whether or not the app started
+/// it, stop the proxy and remove both PID files. Every step is attempted even when an
+/// earlier one fails, so this works when the normal stop commands report "not running".
+#[command]
+pub async fn force_stop_all(
+    proxy: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<ForceStopReport, String> {
+    warn!("Force stopping all BB processes");
+    let mut errors = Vec::new();
+
+    let mut processes = Vec::new();
+    for (service, binary) in [("api", "bb-api"), ("bui", "bb-bui")] {
+        // Keep the supervisor from bringing the service straight back
+        crate::supervisor::note_service_stopped(service);
+        let pids = match find_all_processes(binary).await {
+            Ok(pids) => pids,
+            Err(e) => {
+                errors.push(format!("{}: {}", binary, e));
+                continue;
+            }
+        };
+        let terminated = futures_util::future::join_all(
+            pids.iter()
+                .map(|&pid| robust_terminate_process(pid, binary)),
+        )
+        .await;
+        processes.extend(
+            pids.into_iter()
+                .zip(terminated)
+                .map(|(pid, terminated)| KilledProcess {
+                    service: service.to_string(),
+                    pid,
+                    terminated,
+                }),
+        );
+    }
+    errors.extend(
+        processes
+            .iter()
+            .filter(|process| !process.terminated)
+            .map(|process| {
+                format!(
+                    "{} process {} is still running",
+                    process.service.to_uppercase(),
+                    process.pid
+                )
+            }),
+    );
+
+    let proxy_stopped = {
+        let proxy = proxy.read().await;
+        let was_running = proxy.is_running().await;
+        match proxy.stop().await {
+            Ok(()) => was_running,
+            Err(e) => {
+                errors.push(format!("Failed to stop proxy: {}", e));
+                false
+            }
+        }
+    };
+
+    let mut pid_files_removed = true;
+    for service in ["api", "bui"] {
+        if let Err(e) = remove_pid(service).await {
+            errors.push(format!("{} PID file: {}", service.to_uppercase(), e));
+            pid_files_removed = false;
+        }
+    }
+
+    info!(
+        "Force stop finished: {} process(es) terminated, proxy stopped: {}, {} error(s)",
+        processes
+            .iter()
+            .filter(|process| process.terminated)
+            .count(),
+        proxy_stopped,
+        errors.len()
+    );
+
+    Ok(ForceStopReport {
+        processes,
+        proxy_stopped,
+        pid_files_removed,
+        errors,
+    })
+}
@@ -0,0 +1,266 @@
+use log::{debug, info, warn};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter};
+use tokio::sync::RwLock;
+
+use crate::api::{get_bb_api_path, start_api, stop_api};
+use crate::bui::{get_bb_bui_path, start_bui, stop_bui};
+use crate::commands::server_status::check_server_status;
+use crate::commands::version::run_version_command;
+use crate::proxy::HttpProxy;
+
+const SMOKE_TEST_STEPS: usize = 6;
+const PROXY_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// The proxy answers /_health itself; the deep variant has it fetch the target's /_health
+// and only succeeds when that returns 2xx
+const PROXY_HEALTH_PATH: &str = "/_health?deep=1";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SmokeTestStep {
+    pub name: String,
+    pub passed: bool,
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SmokeTestProgress {
+    step: String,
+    progress: f32,
+    // None while the step is running
+    result: Option<SmokeTestStep>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SmokeTestReport {
+    pub passed: bool,
+    pub steps: Vec<SmokeTestStep>,
+    pub stopped_services: Vec<String>,
+}
+
+struct SmokeTest<'a> {
+    app: &'a AppHandle,
+    steps: Vec<SmokeTestStep>,
+    started: Instant,
+}
+
+impl<'a> SmokeTest<'a> {
+    fn new(app: &'a AppHandle) -> Self {
+        Self {
+            app,
+            steps: Vec::new(),
+            started: Instant::now(),
+        }
+    }
+
+    fn emit(&self, step: &str, result: Option<SmokeTestStep>) {
+        let progress = SmokeTestProgress {
+            step: step.to_string(),
+            progress: (self.steps.len() as f32 / SMOKE_TEST_STEPS as f32) * 100.0,
+            result,
+        };
+        if let Err(e) = self.app.emit("smoke-test-progress", progress) {
+            warn!("Failed to emit smoke test progress: {}", e);
+        }
+    }
+
+    fn begin(&mut self, name: &str) {
+        debug!("Smoke test: running {}", name);
+        self.started = Instant::now();
+        self.emit(name, None);
+    }
+
+    fn finish(&mut self, name: &str, outcome: Result<String, String>) -> bool {
+        let (passed, message) = match outcome {
+            Ok(message) => (true, message),
+            Err(message) => (false, message),
+        };
+        info!(
+            "Smoke test: {} {} - {}",
+            name,
+            if passed { "passed" } else { "failed" },
+            message
+        );
+        let step = SmokeTestStep {
+            name: name.to_string(),
+            passed,
+            message,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+        };
+        self.steps.push(step.clone());
+        self.emit(name, Some(step));
+        passed
+    }
+}
+
+/// Run `<binary> --version` and return the first non-empty line of output
+async fn binary_version(path: &Path) -> Result<String, String> {
+    run_version_command(path)
+        .await
+        .map_err(|e| format!("Failed to run {:?}: {}", path, e))?
+        .ok_or_else(|| format!("{:?} --version failed or timed out", path))?
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .ok_or_else(|| format!("{:?} --version printed nothing", path))
+}
+
+async fn check_versions() -> Result<String, String> {
+    let api = binary_version(&get_bb_api_path()?).await?;
+    let bui = binary_version(&get_bb_bui_path()?).await?;
+    Ok(format!("{}; {}", api, bui))
+}
+
+/// Ask the local proxy to check the target's health endpoint, so the whole proxy -> target
+/// chain is exercised. Anything but a 2xx fails the step.
+async fn check_proxy(proxy: &HttpProxy) -> Result<String, String> {
+    if !proxy.is_running().await {
+        return Err("Proxy server is not running".to_string());
+    }
+    let target = proxy.target_url.read().await.clone();
+    let url = format!("http://127.0.0.1:{}{}", proxy.port, PROXY_HEALTH_PATH);
+
+    let client = reqwest::Client::builder()
+        .timeout(PROXY_REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Request through proxy failed: {}", e))?;
+
+    let status = response.status();
+    if status.is_success() {
+        Ok(format!(
+            "Target {} is healthy via proxy ({})",
+            target, status
+        ))
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(format!(
+            "Target {} health check via proxy returned {}: {}",
+            target,
+            status,
+            body.trim()
+        ))
+    }
+}
+
+/// Validate the installed services end to end: binary versions, service startup,
+/// endpoint health and a request through the proxy. Progress is emitted as
+/// "smoke-test-progress" events. When `stop_services` is set, services started by the
+/// test are stopped again afterwards.
+#[command]
+pub async fn run_post_install_smoke_test(
+    app: AppHandle,
+    stop_services: bool,
+    proxy: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<SmokeTestReport, String> {
+    info!("Starting post-install smoke test");
+    let mut test = SmokeTest::new(&app);
+
+    test.begin("versions");
+    test.finish("versions", check_versions().await);
+
+    // Remember what was already running so only services started here get stopped
+    let initial = check_server_status().await?;
+
+    test.begin("start_api");
+    let api_started = match start_api().await {
+        Ok(result) if result.success => Ok(if initial.api.service_responds {
+            "API already running".to_string()
+        } else {
+            format!("API started (pid {:?})", result.pid)
+        }),
+        Ok(result) => Err(result
+            .error
+            .map(String::from)
+            .unwrap_or_else(|| "API failed to start".to_string())),
+        Err(e) => Err(e.to_string()),
+    };
+    let api_ok = test.finish("start_api", api_started);
+
+    test.begin("start_bui");
+    let bui_started = match start_bui().await {
+        Ok(result) if result.success => Ok(if initial.bui.service_responds {
+            "BUI already running".to_string()
+        } else {
+            format!("BUI started (pid {:?})", result.pid)
+        }),
+        Ok(result) => Err(result
+            .error
+            .map(String::from)
+            .unwrap_or_else(|| "BUI failed to start".to_string())),
+        Err(e) => Err(e.to_string()),
+    };
+    let bui_ok = test.finish("start_bui", bui_started);
+
+    test.begin("api_responds");
+    let status = check_server_status().await?;
+    test.finish(
+        "api_responds",
+        if status.api.service_responds {
+            Ok("API endpoint responds".to_string())
+        } else {
+            Err(status
+                .api
+                .error
+                .unwrap_or_else(|| "API endpoint did not respond".to_string()))
+        },
+    );
+
+    test.begin("bui_responds");
+    test.finish(
+        "bui_responds",
+        if status.bui.service_responds {
+            Ok("BUI endpoint responds".to_string())
+        } else {
+            Err(status
+                .bui
+                .error
+                .unwrap_or_else(|| "BUI endpoint did not respond".to_string()))
+        },
+    );
+
+    test.begin("proxy");
+    let proxy_result = {
+        let proxy = proxy.read().await;
+        check_proxy(&proxy).await
+    };
+    test.finish("proxy", proxy_result);
+
+    let mut stopped_services = Vec::new();
+    if stop_services {
+        if bui_ok && !initial.bui.service_responds {
+            match stop_bui().await {
+                Ok(true) => stopped_services.push("bui".to_string()),
+                Ok(false) => warn!("Smoke test: BUI did not stop cleanly"),
+                Err(e) => warn!("Smoke test: failed to stop BUI: {}", e),
+            }
+        }
+        if api_ok && !initial.api.service_responds {
+            match stop_api().await {
+                Ok(true) => stopped_services.push("api".to_string()),
+                Ok(false) => warn!("Smoke test: API did not stop cleanly"),
+                Err(e) => warn!("Smoke test: failed to stop API: {}", e),
+            }
+        }
+    }
+
+    let passed = test.steps.iter().all(|step| step.passed);
+    info!(
+        "Post-install smoke test {}",
+        if passed { "passed" } else { "failed" }
+    );
+
+    Ok(SmokeTestReport {
+        passed,
+        steps: test.steps,
+        stopped_services,
+    })
+}
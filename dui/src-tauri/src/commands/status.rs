@@ -0,0 +1,76 @@
+use log::{debug, warn};
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::command;
+use tokio::sync::RwLock;
+
+use crate::commands::proxy::proxy_info;
+use crate::commands::server_status::{server_status, ServerStatus};
+use crate::commands::version::{
+    get_binary_version, version_compatibility_for, VersionCompatibility,
+};
+use crate::config::read_global_config;
+use crate::proxy::{HttpProxy, ProxyInfo};
+
+/// Everything the status page shows, gathered at one moment
+#[derive(Serialize)]
+pub struct FullStatus {
+    pub services: ServerStatus,
+    pub version: VersionCompatibility,
+    pub binary_version: Option<String>,
+    pub proxy: ProxyInfo,
+}
+
+/// How app startup went, kept in managed state so the UI can read it once its window has
+/// loaded instead of relying on an event sent before anything was listening
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupStatus {
+    /// Seconds startup waited for the API and BUI before showing the window anyway; None when
+    /// they came up (or failed) in time
+    pub services_timeout_secs: Option<u64>,
+}
+
+/// Called by the UI on mount to show a "still starting" banner until the status monitor
+/// reports the services as up
+#[command]
+pub fn get_startup_status(state: tauri::State<'_, StartupStatus>) -> StartupStatus {
+    state.inner().clone()
+}
+
+/// One-shot replacement for calling check_server_status, check_version_compatibility,
+/// get_proxy_info and get_binary_version separately. The config is read once and the
+/// independent checks run concurrently.
+#[command]
+pub async fn get_full_status(
+    state: tauri::State<'_, Arc<RwLock<HttpProxy>>>,
+) -> Result<FullStatus, String> {
+    debug!("get_full_status command invoked");
+    let config = read_global_config().map_err(|e| format!("Failed to read config: {}", e))?;
+    let update_channel = config.dui.update_channel;
+
+    let version = async {
+        // A missing binary shouldn't hide the service and proxy status
+        let binary_version = get_binary_version().await.unwrap_or_else(|e| {
+            warn!("Failed to determine binary version: {}", e);
+            None
+        });
+        let compatibility =
+            version_compatibility_for(binary_version.clone(), update_channel, true).await;
+        (binary_version, compatibility)
+    };
+    let proxy = async {
+        let proxy = state.read().await;
+        proxy_info(&proxy).await
+    };
+
+    let (services, (binary_version, version), proxy) =
+        tokio::join!(server_status(Some(&config)), version, proxy);
+
+    Ok(FullStatus {
+        services: services?,
+        version: version?,
+        binary_version,
+        proxy,
+    })
+}
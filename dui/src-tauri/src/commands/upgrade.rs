@@ -3,37 +3,87 @@ use dirs;
 use flate2::read::GzDecoder;
 
 use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
 use reqwest;
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 #[cfg(not(target_os = "windows"))]
 use tar::Archive;
 use tauri::{command, AppHandle, Emitter};
 use tokio;
-use tauri_plugin_updater::UpdaterExt;
+use tauri_plugin_updater::{Updater, UpdaterBuilder, UpdaterExt};
 use tempfile::TempDir;
 #[cfg(target_os = "windows")]
 use zip::ZipArchive;
 
 // Import stop functions for robust termination
-use crate::api::stop_api;
-use crate::bui::stop_bui;
-
-const RELEASE_API_URL: &str = "https://asyagnmzoxgyhqprdaky.storage.supabase.co/storage/v1/object/releases/latest.json";
+use crate::api::{find_bb_api_path, start_api, stop_api};
+use crate::bui::{find_bb_bui_path, start_bui, stop_bui};
+use crate::commands::disk_space::available_space;
+use crate::commands::retry::send_with_retry;
+use crate::commands::server_status::server_status;
+use crate::commands::version::get_binary_version;
+use crate::config::{get_install_directory, get_update_channel, UpdateChannel};
+use crate::error::BBError;
+
+const RELEASE_API_BASE_URL: &str = "https://asyagnmzoxgyhqprdaky.storage.supabase.co/storage/v1/object/releases";
+// Releases by tag, for repairing a specific version and for notes missing from manifests
+const GITHUB_RELEASES_API_URL: &str = "https://api.github.com/repos/Beyond-Better/bb/releases/tags";
+const MAX_DOWNLOAD_ATTEMPTS: u32 = 5;
+const DOWNLOAD_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+// Share of the install progress bar covered by the archive download
+const DOWNLOAD_PROGRESS_START: f32 = 50.0;
+const DOWNLOAD_PROGRESS_END: f32 = 70.0;
+// Without a content length, report received bytes at this granularity
+const DOWNLOAD_PROGRESS_UNKNOWN_STEP: u64 = 1024 * 1024;
+// Extracted binaries take up to this many times the size of the compressed archive
+const EXTRACTION_SIZE_FACTOR: u64 = 3;
+// Space left over after the install so it doesn't fill the disk completely
+const DISK_SPACE_HEADROOM: u64 = 50 * 1024 * 1024;
+const INSTALL_CANCELED_ERROR: &str = "Installation canceled";
+// How long the macOS update helper gets to signal that it is running before the app gives up
+#[cfg(target_os = "macos")]
+const UPDATE_HELPER_READY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 //const DUI_UPDATE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300); // 5 minutes
 
+// Only connecting is limited here: downloads can take minutes, and send_with_retry adds its
+// own per-request timeout
+const RELEASE_CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+// Set by cancel_install, checked between stages of a running install, upgrade or repair
+static INSTALL_CANCELED: AtomicBool = AtomicBool::new(false);
+
+// Shared by release lookups, checksum fetches and archive downloads
+static RELEASE_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .connect_timeout(RELEASE_CONNECT_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+});
+
 #[derive(Debug, Serialize, Deserialize)]
 struct GithubAsset {
     name: String,
     browser_download_url: String,
+    // Hex-encoded SHA-256 of the asset, when published in latest.json
+    #[serde(default)]
+    sha256: Option<String>,
+    // Archive size in bytes, when published in latest.json
+    #[serde(default)]
+    size: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct GithubRelease {
     tag_name: String,
     assets: Vec<GithubAsset>,
+    // Markdown release notes, when published in latest.json
+    #[serde(default, alias = "notes")]
+    body: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -45,11 +95,40 @@ pub struct InstallProgress {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InstallLocation {
-    path: PathBuf,
+    pub(crate) path: PathBuf,
     writable: bool,
     is_user_install: bool,
 }
 
+#[derive(Debug, Serialize)]
+pub struct BinaryLocation {
+    // None when the binary wasn't found in any of checked_paths
+    path: Option<PathBuf>,
+    checked_paths: Vec<PathBuf>,
+}
+
+impl From<Result<PathBuf, Vec<PathBuf>>> for BinaryLocation {
+    fn from(result: Result<PathBuf, Vec<PathBuf>>) -> Self {
+        match result {
+            Ok(path) => BinaryLocation {
+                path: Some(path),
+                checked_paths: Vec::new(),
+            },
+            Err(checked_paths) => BinaryLocation {
+                path: None,
+                checked_paths,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstallInfo {
+    location: InstallLocation,
+    bb_api: BinaryLocation,
+    bb_bui: BinaryLocation,
+}
+
 fn emit_progress(
     app: &AppHandle,
     stage: &str,
@@ -68,6 +147,96 @@ fn emit_progress(
     app.emit("install-progress", progress)
 }
 
+/// Abort the running install if cancel_install was called. Partial downloads live in a
+/// TempDir and are removed as the error unwinds; the command reports the "canceled" stage
+/// once the rest of its cleanup is done (see `report_canceled`).
+fn check_canceled() -> Result<(), String> {
+    if !INSTALL_CANCELED.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    info!("Installation canceled by user");
+    Err(INSTALL_CANCELED_ERROR.to_string())
+}
+
+/// Emit the final "canceled" stage if `result` is a cancel, after the command's cleanup ran
+fn report_canceled<T, E: std::fmt::Display>(app: &AppHandle, result: Result<T, E>) -> Result<T, E> {
+    if let Err(e) = &result {
+        if e.to_string() == INSTALL_CANCELED_ERROR {
+            let _ = emit_progress(app, "canceled", 0.0, Some(INSTALL_CANCELED_ERROR.to_string()));
+        }
+    }
+    result
+}
+
+/// Services that were running before an upgrade or repair stopped them
+struct StoppedServices {
+    api: bool,
+    bui: bool,
+}
+
+/// Stop the API and BUI so their binaries can be replaced, remembering which were running
+async fn stop_services_for_install() -> Result<StoppedServices, String> {
+    let stopped = match server_status(None).await {
+        Ok(status) => StoppedServices {
+            api: status.api.service_responds,
+            bui: status.bui.service_responds,
+        },
+        Err(e) => {
+            warn!("Could not tell which services are running: {}", e);
+            StoppedServices {
+                api: false,
+                bui: false,
+            }
+        }
+    };
+
+    info!("Stopping existing API and BUI processes");
+    if !stop_api()
+        .await
+        .map_err(|e| format!("Failed to stop API: {}", e))?
+    {
+        warn!("Some API processes may still be running after stop attempt");
+    }
+    if !stop_bui()
+        .await
+        .map_err(|e| format!("Failed to stop BUI: {}", e))?
+    {
+        warn!("Some BUI processes may still be running after stop attempt");
+    }
+    // Small delay to ensure ports are freed
+    tokio::time::sleep(std::time::Duration::from_millis(2000)).await;
+    Ok(stopped)
+}
+
+/// Start again the services `stop_services_for_install` stopped, after a repair or a failed
+/// or canceled upgrade
+async fn restart_stopped_services(stopped: &StoppedServices) {
+    if stopped.api {
+        match start_api().await {
+            Ok(result) if result.success => info!("Restarted API after install"),
+            Ok(result) => warn!("Failed to restart API after install: {:?}", result.error),
+            Err(e) => warn!("Failed to restart API after install: {}", e),
+        }
+    }
+    if stopped.bui {
+        match start_bui().await {
+            Ok(result) if result.success => info!("Restarted BUI after install"),
+            Ok(result) => warn!("Failed to restart BUI after install: {:?}", result.error),
+            Err(e) => warn!("Failed to restart BUI after install: {}", e),
+        }
+    }
+}
+
+/// Ask a running perform_install, perform_upgrade or repair_installation to stop at its next
+/// checkpoint. Replaced binaries are restored and services it stopped are started again
+/// before the "canceled" stage is reported.
+#[command]
+pub async fn cancel_install() -> Result<(), String> {
+    info!("Cancel requested for running installation");
+    INSTALL_CANCELED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
 #[cfg(target_os = "windows")]
 fn check_windows_path_length(path: &PathBuf) -> io::Result<()> {
     const MAX_PATH: usize = 260;
@@ -84,6 +253,47 @@ fn check_windows_path_length(path: &PathBuf) -> io::Result<()> {
     Ok(())
 }
 
+/// Build an updater for the configured update channel. The stable channel keeps the endpoint
+/// from tauri.conf.json but also offers the stable release when a newer-numbered beta is
+/// installed, so switching back from beta is a downgrade rather than a no-op.
+fn channel_updater(app: &AppHandle) -> Result<Updater, String> {
+    channel_updater_builder(app)?
+        .build()
+        .map_err(|e| format!("Failed to get updater: {}", e))
+}
+
+/// `channel_updater` for installing: on Windows the app is closed by the installer, so log
+/// that before it happens
+fn channel_install_updater(app: &AppHandle) -> Result<Updater, String> {
+    let builder = channel_updater_builder(app)?;
+    #[cfg(target_os = "windows")]
+    let builder = builder.on_before_exit(|| {
+        info!("Beyond Better app is about to exit on Windows for update installation");
+    });
+    builder.build().map_err(|e| {
+        error!("Failed to build updater: {}", e);
+        format!("Failed to build updater: {}", e)
+    })
+}
+
+fn channel_updater_builder(app: &AppHandle) -> Result<UpdaterBuilder, String> {
+    let channel = get_update_channel();
+    debug!("Using {:?} update channel", channel);
+    let builder = match channel {
+        UpdateChannel::Beta => {
+            let url = format!("{}/{}", RELEASE_API_BASE_URL, channel.updater_manifest());
+            let url = tauri::Url::parse(&url).map_err(|e| format!("Invalid updater URL {}: {}", url, e))?;
+            app.updater_builder()
+                .endpoints(vec![url])
+                .map_err(|e| format!("Failed to set updater endpoint: {}", e))?
+        }
+        UpdateChannel::Stable => app.updater_builder().version_comparator(|current, release| {
+            release.version > current || (!current.pre.is_empty() && release.version != current)
+        }),
+    };
+    Ok(builder)
+}
+
 #[command]
 pub async fn check_dui_update(app: AppHandle) -> Result<Option<DuiUpdateInfo>, String> {
     info!("Checking for application updates");
@@ -102,7 +312,7 @@ pub async fn check_dui_update(app: AppHandle) -> Result<Option<DuiUpdateInfo>, S
         }
     }
     
-    match app.updater().map_err(|e| format!("Failed to get updater: {}", e))?.check().await.map_err(|e| format!("Failed to check for updates: {}", e))? {
+    match channel_updater(&app)?.check().await.map_err(|e| format!("Failed to check for updates: {}", e))? {
         Some(update) => {
             info!("Application update available: version {}", update.version);
             Ok(Some(DuiUpdateInfo {
@@ -163,7 +373,7 @@ pub async fn perform_atomic_update(app: AppHandle) -> Result<(), String> {
     )
     .map_err(|e| format!("Failed to emit progress: {}", e))?;
     
-    match app.updater().map_err(|e| format!("Failed to get updater: {}", e))?.check().await.map_err(|e| format!("Failed to check for updates: {}", e))? {
+    match channel_updater(&app)?.check().await.map_err(|e| format!("Failed to check for updates: {}", e))? {
         Some(update) => {
             info!("Application update available, proceeding with download and install");
             
@@ -184,28 +394,13 @@ pub async fn perform_atomic_update(app: AppHandle) -> Result<(), String> {
                 
                 let archive_path = temp_dir.join(format!("update-{}.tar.gz", update.version));
                 
-                // Download the update archive
-                let response = reqwest::get(update.download_url.as_str()).await
-                    .map_err(|e| format!("Failed to download update: {}", e))?;
-                
-                if !response.status().is_success() {
-                    return Err(format!("Download failed with status: {}", response.status()));
-                }
-                
-                let _total_size = response.content_length();
-                let downloaded;
-                let mut file = std::fs::File::create(&archive_path)
-                    .map_err(|e| format!("Failed to create update file: {}", e))?;
-                
-                // Read response body in chunks
-                let bytes = response.bytes().await
-                    .map_err(|e| format!("Failed to read response body: {}", e))?;
-                
-                use std::io::Write;
-                file.write_all(&bytes)
+                // Download through the updater so the archive is checked against its
+                // signature before the helper script installs it
+                let bytes = download_verified_update(&update).await?;
+                std::fs::write(&archive_path, &bytes)
                     .map_err(|e| format!("Failed to write update file: {}", e))?;
                 
-                downloaded = bytes.len();
+                let downloaded = bytes.len();
                 let progress = 90.0;
                 let _ = emit_progress(
                     &app,
@@ -232,19 +427,8 @@ pub async fn perform_atomic_update(app: AppHandle) -> Result<(), String> {
             {
                 let mut downloaded = 0;
                 
-                // On Windows, we need to handle the before-exit callback
-                #[cfg(target_os = "windows")]
-                let update_builder = app.updater_builder().on_before_exit(|| {
-                    info!("Beyond Better app is about to exit on Windows for update installation");
-                });
-                
-                #[cfg(not(target_os = "windows"))]
-                let update_builder = app.updater_builder();
-                
-                let updater = update_builder.build().map_err(|e| {
-                    error!("Failed to build updater: {}", e);
-                    format!("Failed to build updater: {}", e)
-                })?;
+                // Same channel as the check, so beta users get the beta release back
+                let updater = channel_install_updater(&app)?;
                 
                 let update = updater.check().await.map_err(|e| {
                     error!("Failed to re-check for updates: {}", e);
@@ -334,7 +518,7 @@ pub async fn perform_dui_update_only(app: AppHandle) -> Result<(), String> {
     )
     .map_err(|e| format!("Failed to emit progress: {}", e))?;
     
-    match app.updater().map_err(|e| format!("Failed to get updater: {}", e))?.check().await.map_err(|e| format!("Failed to check for updates: {}", e))? {
+    match channel_updater(&app)?.check().await.map_err(|e| format!("Failed to check for updates: {}", e))? {
         Some(update) => {
             info!("Application update available, proceeding with download and install");
             
@@ -348,18 +532,7 @@ pub async fn perform_dui_update_only(app: AppHandle) -> Result<(), String> {
             
             let mut downloaded = 0;
             
-            #[cfg(target_os = "windows")]
-            let update_builder = app.updater_builder().on_before_exit(|| {
-                info!("Application app is about to exit on Windows for update installation");
-            });
-            
-            #[cfg(not(target_os = "windows"))]
-            let update_builder = app.updater_builder();
-            
-            let updater = update_builder.build().map_err(|e| {
-                error!("Failed to build updater: {}", e);
-                format!("Failed to build updater: {}", e)
-            })?;
+            let updater = channel_install_updater(&app)?;
             
             let update = updater.check().await.map_err(|e| {
                 error!("Failed to re-check for updates: {}", e);
@@ -375,28 +548,13 @@ pub async fn perform_dui_update_only(app: AppHandle) -> Result<(), String> {
                 
                 let archive_path = temp_dir.join(format!("update-{}.tar.gz", update.version));
                 
-                // Download the update archive
-                let response = reqwest::get(update.download_url.as_str()).await
-                    .map_err(|e| format!("Failed to download update: {}", e))?;
-                
-                if !response.status().is_success() {
-                    return Err(format!("Download failed with status: {}", response.status()));
-                }
-                
-                let _total_size = response.content_length();
-                let downloaded;
-                let mut file = std::fs::File::create(&archive_path)
-                    .map_err(|e| format!("Failed to create update file: {}", e))?;
-                
-                // Read response body in chunks
-                let bytes = response.bytes().await
-                    .map_err(|e| format!("Failed to read response body: {}", e))?;
-                
-                use std::io::Write;
-                file.write_all(&bytes)
+                // Download through the updater so the archive is checked against its
+                // signature before the helper script installs it
+                let bytes = download_verified_update(&update).await?;
+                std::fs::write(&archive_path, &bytes)
                     .map_err(|e| format!("Failed to write update file: {}", e))?;
                 
-                downloaded = bytes.len();
+                let downloaded = bytes.len();
                 let progress = 80.0;
                 let _ = emit_progress(
                     &app,
@@ -492,8 +650,38 @@ pub struct DuiUpdateInfo {
     pub download_url: String,
 }
 
-fn get_install_location() -> io::Result<InstallLocation> {
+#[derive(Debug, Serialize)]
+pub struct ServerUpdateInfo {
+    current_version: Option<String>,
+    latest_version: String,
+    update_available: bool,
+    // Markdown release notes for latest_version, when published
+    notes: Option<String>,
+}
+
+// Metadata permissions don't reflect ACLs or ownership, so actually try creating a file
+fn is_dir_writable(path: &Path) -> bool {
+    tempfile::tempfile_in(path).is_ok()
+}
+
+pub(crate) fn get_install_location() -> io::Result<InstallLocation> {
     debug!("Determining installation location");
+    // Configured location overrides the defaults when it can be used
+    if let Some(custom_install) = get_install_directory() {
+        debug!("Checking configured install location: {:?}", custom_install);
+        if fs::create_dir_all(&custom_install).is_ok() && is_dir_writable(&custom_install) {
+            return Ok(InstallLocation {
+                path: custom_install,
+                writable: true,
+                is_user_install: true,
+            });
+        }
+        warn!(
+            "Configured install location {:?} is not usable, falling back to default",
+            custom_install
+        );
+    }
+
     // Try user-specific location first
     if let Some(home) = dirs::home_dir() {
         let user_install = if cfg!(target_os = "windows") {
@@ -563,17 +751,46 @@ fn get_install_location() -> io::Result<InstallLocation> {
     })
 }
 
+/// Where binaries get installed and where bb-api/bb-bui were actually found, for
+/// diagnosing "Could not find bb-api" errors
 #[command]
-pub async fn perform_install(app: AppHandle) -> Result<(), String> {
+pub async fn get_install_info() -> Result<InstallInfo, String> {
+    let location = get_install_location().map_err(|e| e.to_string())?;
+    Ok(InstallInfo {
+        location,
+        bb_api: find_bb_api_path().into(),
+        bb_bui: find_bb_bui_path().into(),
+    })
+}
+
+#[command]
+pub async fn perform_install(app: AppHandle) -> Result<(), BBError> {
+    let result = install(&app).await;
+    report_canceled(&app, result)
+}
+
+async fn install(app: &AppHandle) -> Result<(), BBError> {
     info!("Starting fresh installation process");
+    INSTALL_CANCELED.store(false, Ordering::SeqCst);
     emit_progress(
-        &app,
+        app,
         "preparing",
         0.0,
         Some("Checking installation location...".to_string()),
     )
     .map_err(|e| format!("Failed to emit progress: {}", e))?;
 
+    // Don't quietly install somewhere else when the configured directory can't be written
+    if let Some(custom_install) = get_install_directory() {
+        if custom_install.exists() && !is_dir_writable(&custom_install) {
+            error!("Configured install location not writable: {:?}", custom_install);
+            return Err(BBError::PermissionDenied(format!(
+                "Configured install directory {} is not writable. Please fix its permissions or change dui.installDirectory.",
+                custom_install.display()
+            )));
+        }
+    }
+
     let install_location = get_install_location().map_err(|e| e.to_string())?;
 
     if !install_location.writable {
@@ -583,61 +800,62 @@ pub async fn perform_install(app: AppHandle) -> Result<(), String> {
                 "Installation location not writable: {:?}",
                 install_location.path
             );
-            return Err(
+            return Err(BBError::PermissionDenied(
                 "Installation requires administrator privileges. Please run application as administrator."
                     .to_string(),
-            );
+            ));
         }
 
         #[cfg(not(target_os = "windows"))]
         {
-            return Err(
+            return Err(BBError::PermissionDenied(
                 "Installation location is not writable. Please run with elevated privileges."
                     .to_string(),
-            );
+            ));
         }
     }
 
     // Create installation directory if it doesn't exist
     emit_progress(
-        &app,
+        app,
         "preparing",
         10.0,
         Some("Creating installation directory...".to_string()),
     )
     .map_err(|e| format!("Failed to emit progress: {}", e))?;
-    fs::create_dir_all(&install_location.path)
-        .map_err(|e| {
-            error!(
-                "Failed to create directory {:?}: {}",
-                install_location.path, e
-            );
-            e
-        })
-        .map_err(|e| format!("Failed to create installation directory: {}", e))?;
+    fs::create_dir_all(&install_location.path).map_err(|e| {
+        error!(
+            "Failed to create directory {:?}: {}",
+            install_location.path, e
+        );
+        BBError::from_io("Failed to create installation directory", &e)
+    })?;
 
     // Download latest release
     emit_progress(
-        &app,
+        app,
         "downloading",
         20.0,
         Some("Fetching latest release information...".to_string()),
     )
     .map_err(|e| format!("Failed to emit progress: {}", e))?;
-    let latest_release = fetch_latest_release().await?;
+    let latest_release = fetch_latest_release()
+        .await
+        .map_err(BBError::NetworkError)?;
+    check_canceled()?;
 
     // Download and install binaries
     emit_progress(
-        &app,
+        app,
         "installing",
         40.0,
         Some("Installing binaries...".to_string()),
     )
     .map_err(|e| format!("Failed to emit progress: {}", e))?;
-    install_binaries(&app, &latest_release, &install_location).await?;
+    install_binaries(app, &latest_release, &install_location).await?;
 
     emit_progress(
-        &app,
+        app,
         "complete",
         100.0,
         Some("Installation complete".to_string()),
@@ -647,10 +865,16 @@ pub async fn perform_install(app: AppHandle) -> Result<(), String> {
 }
 
 #[command]
-pub async fn perform_upgrade(app: AppHandle) -> Result<(), String> {
+pub async fn perform_upgrade(app: AppHandle) -> Result<(), BBError> {
+    let result = upgrade(&app).await;
+    report_canceled(&app, result)
+}
+
+async fn upgrade(app: &AppHandle) -> Result<(), BBError> {
     info!("Starting upgrade process");
+    INSTALL_CANCELED.store(false, Ordering::SeqCst);
     emit_progress(
-        &app,
+        app,
         "preparing",
         0.0,
         Some("Checking upgrade location...".to_string()),
@@ -660,66 +884,137 @@ pub async fn perform_upgrade(app: AppHandle) -> Result<(), String> {
     let install_location = get_install_location().map_err(|e| e.to_string())?;
 
     if !install_location.writable {
-        return Err(
+        return Err(BBError::PermissionDenied(
             "Upgrade location is not writable. Please run with elevated privileges.".to_string(),
-        );
+        ));
     }
 
     // Backup current installation
-    emit_progress(&app, "backup", 10.0, Some("Creating backup...".to_string()))
+    emit_progress(app, "backup", 10.0, Some("Creating backup...".to_string()))
         .map_err(|e| format!("Failed to emit progress: {}", e))?;
-    backup_current_installation(&install_location)?;
+    // Kept until the new binaries are verified so a failed upgrade can be rolled back
+    let backup_dir = backup_current_installation(&install_location)?;
+    check_canceled()?;
 
     // Stop all existing processes robustly before upgrade
     emit_progress(
-        &app,
+        app,
         "stopping",
         15.0,
         Some("Stopping existing processes...".to_string()),
     )
     .map_err(|e| format!("Failed to emit progress: {}", e))?;
-
-    info!("Stopping existing API and BUI processes for upgrade");
-    let api_stopped = stop_api()
-        .await
-        .map_err(|e| format!("Failed to stop API: {}", e))?;
-    let bui_stopped = stop_bui()
+    let stopped = stop_services_for_install()
         .await
-        .map_err(|e| format!("Failed to stop BUI: {}", e))?;
-
-    if !api_stopped {
-        warn!("Some API processes may still be running after stop attempt");
-    }
-    if !bui_stopped {
-        warn!("Some BUI processes may still be running after stop attempt");
-    }
-
-    // Small delay to ensure ports are freed
-    std::thread::sleep(std::time::Duration::from_millis(2000));
+        .map_err(BBError::ProcessError)?;
     info!("Process termination complete, proceeding with upgrade");
 
+    let Err(e) = upgrade_stopped_installation(app, &install_location).await else {
+        return Ok(());
+    };
+    // Put back the previous binaries and start what was running before; the backup
+    // directory itself is removed when it drops. The error says how the rollback went,
+    // except for a cancel that rolled back cleanly, which is reported as just that.
+    let e = match restore_backup(backup_dir.path(), &install_location) {
+        Ok(()) => {
+            info!("Rolled back to previous installation");
+            if e.message() == INSTALL_CANCELED_ERROR {
+                e
+            } else {
+                e.with_detail("The previous version has been restored.")
+            }
+        }
+        Err(restore_error) => {
+            error!(
+                "Failed to restore backup after aborted upgrade: {}",
+                restore_error
+            );
+            e.with_detail(&format!(
+                "Restoring the previous version also failed ({}). Please reinstall BB.",
+                restore_error
+            ))
+        }
+    };
+    restart_stopped_services(&stopped).await;
+    Err(e)
+}
+
+/// The part of an upgrade that runs with the services stopped. Any error leaves the
+/// rollback to the caller.
+async fn upgrade_stopped_installation(
+    app: &AppHandle,
+    install_location: &InstallLocation,
+) -> Result<(), BBError> {
     // Download latest release
     emit_progress(
-        &app,
+        app,
         "downloading",
         20.0,
         Some("Fetching latest release information...".to_string()),
     )
     .map_err(|e| format!("Failed to emit progress: {}", e))?;
-    let latest_release = fetch_latest_release().await?;
+    let latest_release = fetch_latest_release()
+        .await
+        .map_err(BBError::NetworkError)?;
+    check_canceled()?;
 
     // Download and install binaries
     emit_progress(
-        &app,
+        app,
         "installing",
         40.0,
         Some("Installing binaries...".to_string()),
     )
     .map_err(|e| format!("Failed to emit progress: {}", e))?;
-    install_binaries(&app, &latest_release, &install_location).await?;
+    install_binaries(app, &latest_release, install_location).await?;
 
+    // Make sure the new API actually comes up before declaring success
     emit_progress(
-        &app,
+        app,
+        "verifying",
+        90.0,
+        Some("Verifying new server version...".to_string()),
+    )
+    .map_err(|e| format!("Failed to emit progress: {}", e))?;
+    let start_error = match start_api().await {
+        Ok(result) if result.success => None,
+        Ok(result) => Some(
+            result
+                .error
+                .map(String::from)
+                .unwrap_or_else(|| "API failed to start".to_string()),
+        ),
+        Err(e) => Some(e.to_string()),
+    };
+
+    if let Some(start_error) = start_error {
+        error!(
+            "New API version {} failed to start: {}",
+            latest_release.tag_name, start_error
+        );
+        emit_progress(
+            app,
+            "rolling-back",
+            95.0,
+            Some(format!(
+                "New server version failed to start, restoring previous version: {}",
+                start_error
+            )),
+        )
+        .map_err(|e| format!("Failed to emit progress: {}", e))?;
+
+        if let Err(e) = stop_api().await {
+            warn!("Failed to stop API before rollback: {}", e);
+        }
+
+        return Err(BBError::ProcessError(format!(
+            "Upgrade to {} failed because the new API did not start ({}).",
+            latest_release.tag_name, start_error
+        )));
+    }
+
+    emit_progress(
+        app,
         "complete",
         100.0,
         Some("Upgrade complete".to_string()),
@@ -728,20 +1023,227 @@ pub async fn perform_upgrade(app: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+#[derive(Debug, Serialize)]
+pub struct RepairResult {
+    version: String,
+    // Binaries that were missing or differed from the release
+    replaced: Vec<String>,
+}
+
+fn installed_checksums(location: &InstallLocation) -> Vec<(&'static str, Option<String>)> {
+    installed_binaries()
+        .into_iter()
+        .map(|binary| {
+            let checksum = fs::read(location.path.join(binary))
+                .ok()
+                .map(|content| hex::encode(Sha256::digest(content)));
+            (binary, checksum)
+        })
+        .collect()
+}
+
+/// Reinstall the currently installed version from the release server, for installs with
+/// missing or damaged binaries
+#[command]
+pub async fn repair_installation(app: AppHandle) -> Result<RepairResult, String> {
+    info!("Starting installation repair");
+    INSTALL_CANCELED.store(false, Ordering::SeqCst);
+    emit_progress(
+        &app,
+        "preparing",
+        0.0,
+        Some("Checking installed version...".to_string()),
+    )
+    .map_err(|e| format!("Failed to emit progress: {}", e))?;
+
+    let version = get_binary_version()
+        .await
+        .ok()
+        .flatten()
+        .ok_or_else(|| {
+            "Could not determine the installed version. Please upgrade to reinstall the latest version instead."
+                .to_string()
+        })?;
+    info!("Repairing installation of version {}", version);
+
+    let install_location = get_install_location().map_err(|e| e.to_string())?;
+    if !install_location.writable {
+        return Err(
+            "Installation location is not writable. Please run with elevated privileges."
+                .to_string(),
+        );
+    }
+
+    emit_progress(
+        &app,
+        "downloading",
+        10.0,
+        Some(format!("Fetching release information for {}...", version)),
+    )
+    .map_err(|e| format!("Failed to emit progress: {}", e))?;
+    let release = fetch_release_for_version(&version).await?;
+    report_canceled(&app, check_canceled())?;
+
+    emit_progress(
+        &app,
+        "stopping",
+        15.0,
+        Some("Stopping existing processes...".to_string()),
+    )
+    .map_err(|e| format!("Failed to emit progress: {}", e))?;
+    let stopped = stop_services_for_install().await?;
+
+    let before = installed_checksums(&install_location);
+    // install_binaries puts back the binaries it replaced before returning an error
+    if let Err(e) = install_binaries(&app, &release, &install_location).await {
+        restart_stopped_services(&stopped).await;
+        return report_canceled(&app, Err(e.to_string()));
+    }
+    let after = installed_checksums(&install_location);
+    restart_stopped_services(&stopped).await;
+
+    let replaced: Vec<String> = before
+        .into_iter()
+        .zip(after)
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((binary, _), _)| binary.to_string())
+        .collect();
+    info!(
+        "Repaired installation of {}, replaced: {:?}",
+        release.tag_name, replaced
+    );
+
+    emit_progress(
+        &app,
+        "complete",
+        100.0,
+        Some("Repair complete".to_string()),
+    )
+    .map_err(|e| format!("Failed to emit progress: {}", e))?;
+    Ok(RepairResult { version, replaced })
+}
+
 async fn fetch_latest_release() -> Result<GithubRelease, String> {
-    debug!("Fetching latest release from release server");
-    let client = reqwest::Client::new();
+    fetch_channel_release(get_update_channel()).await
+}
+
+/// Notes for `release`: the manifest's `body`/`notes`, or else the GitHub release body
+async fn fetch_release_notes(release: &GithubRelease) -> Option<String> {
+    if let Some(body) = release.body.as_ref().filter(|body| !body.trim().is_empty()) {
+        return Some(body.clone());
+    }
+
+    #[derive(Deserialize)]
+    struct GithubReleaseBody {
+        body: Option<String>,
+    }
+
+    let client = &*RELEASE_CLIENT;
     let user_agent = format!("BB-APP/{}", env!("CARGO_PKG_VERSION"));
-    let response = client
-        .get(RELEASE_API_URL)
-        .header("User-Agent", &user_agent)
-        .send()
-        .await
-        .map_err(|e| {
-            error!("Release API request failed: {}", e);
-            e
+    let url = format!("{}/{}", GITHUB_RELEASES_API_URL, release.tag_name);
+    let response = match send_with_retry("release notes", || {
+        client
+            .get(&url)
+            .header("User-Agent", &user_agent)
+            .header("Accept", "application/vnd.github+json")
+    })
+    .await
+    {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            warn!("Release notes for {} unavailable: {}", release.tag_name, response.status());
+            return None;
+        }
+        Err(e) => {
+            warn!("Failed to fetch release notes for {}: {}", release.tag_name, e);
+            return None;
+        }
+    };
+    match response.json::<GithubReleaseBody>().await {
+        Ok(release) => release.body.filter(|body| !body.trim().is_empty()),
+        Err(e) => {
+            warn!("Failed to parse release notes: {}", e);
+            None
+        }
+    }
+}
+
+/// Latest server release on the configured channel, with its release notes, so the UI can
+/// show what changes before starting perform_upgrade
+#[command]
+pub async fn get_server_update_info() -> Result<ServerUpdateInfo, String> {
+    let release = fetch_latest_release().await?;
+    let current_version = get_binary_version().await.ok().flatten();
+    let latest_version = release.tag_name.trim_start_matches('v').to_string();
+
+    let update_available = match current_version.as_deref() {
+        None => true,
+        Some(current) => {
+            let current = current.trim_start_matches('v');
+            match (semver::Version::parse(current), semver::Version::parse(&latest_version)) {
+                (Ok(current), Ok(latest)) => latest > current,
+                _ => current != latest_version,
+            }
+        }
+    };
+
+    Ok(ServerUpdateInfo {
+        notes: fetch_release_notes(&release).await,
+        current_version,
+        latest_version,
+        update_available,
+    })
+}
+
+/// The GitHub release tagged with `version`, however far the channels have moved on since
+async fn fetch_release_for_version(version: &str) -> Result<GithubRelease, String> {
+    let version = version.trim_start_matches('v');
+    let client = &*RELEASE_CLIENT;
+    let user_agent = format!("BB-APP/{}", env!("CARGO_PKG_VERSION"));
+    for tag in [format!("v{}", version), version.to_string()] {
+        let url = format!("{}/{}", GITHUB_RELEASES_API_URL, tag);
+        let response = send_with_retry("release", || {
+            client
+                .get(&url)
+                .header("User-Agent", &user_agent)
+                .header("Accept", "application/vnd.github+json")
         })
-        .map_err(|e| format!("Failed to fetch latest release: {}", e))?;
+        .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            debug!("No release tagged {}", tag);
+            continue;
+        }
+        if !response.status().is_success() {
+            return Err(format!(
+                "Release API error for {}: {}",
+                tag,
+                response.status()
+            ));
+        }
+        return response
+            .json::<GithubRelease>()
+            .await
+            .map_err(|e| format!("Failed to parse release {}: {}", tag, e));
+    }
+    Err(format!(
+        "Version {} is not available from the release server. Please upgrade to the latest version instead.",
+        version
+    ))
+}
+
+async fn fetch_channel_release(channel: UpdateChannel) -> Result<GithubRelease, String> {
+    debug!("Fetching latest {:?} release from release server", channel);
+    let client = &*RELEASE_CLIENT;
+    let user_agent = format!("BB-APP/{}", env!("CARGO_PKG_VERSION"));
+    let url = format!("{}/{}", RELEASE_API_BASE_URL, channel.release_manifest());
+    let response = send_with_retry("latest release", || {
+        client.get(&url).header("User-Agent", &user_agent)
+    })
+    .await
+    .map_err(|e| {
+        error!("Release API request failed: {}", e);
+        e
+    })?;
 
     if !response.status().is_success() {
         error!(
@@ -768,12 +1270,312 @@ async fn fetch_latest_release() -> Result<GithubRelease, String> {
         .map_err(|e| format!("Failed to parse release response: {}", e))
 }
 
+/// Expected SHA-256 for `asset`: the `sha256` field from latest.json, or else the
+/// `<asset>.sha256` file published alongside it (in `sha256sum` output format)
+async fn expected_checksum(release: &GithubRelease, asset: &GithubAsset) -> Result<String, String> {
+    if let Some(checksum) = asset.sha256.as_ref() {
+        return Ok(checksum.trim().to_lowercase());
+    }
+
+    let checksum_name = format!("{}.sha256", asset.name);
+    let checksum_asset = release
+        .assets
+        .iter()
+        .find(|a| a.name == checksum_name)
+        .ok_or_else(|| format!("No checksum published for {}; refusing to install", asset.name))?;
+
+    debug!("Fetching checksum from {}", checksum_asset.browser_download_url);
+    let response = send_with_retry("checksum", || {
+        RELEASE_CLIENT.get(&checksum_asset.browser_download_url)
+    })
+    .await?;
+    if !response.status().is_success() {
+        return Err(format!("Checksum download failed: {}", response.status()));
+    }
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read checksum: {}", e))?;
+
+    text.split_whitespace()
+        .next()
+        .map(|checksum| checksum.to_lowercase())
+        .ok_or_else(|| format!("Checksum file {} is empty", checksum_name))
+}
+
+/// Size of the release archive, from latest.json or else the download's Content-Length
+async fn asset_size(asset: &GithubAsset) -> Option<u64> {
+    if asset.size.is_some() {
+        return asset.size;
+    }
+    match RELEASE_CLIENT.head(&asset.browser_download_url).send().await {
+        Ok(response) if response.status().is_success() => response.content_length(),
+        Ok(response) => {
+            warn!("Size lookup for {} returned {}", asset.name, response.status());
+            None
+        }
+        Err(e) => {
+            warn!("Failed to look up size of {}: {}", asset.name, e);
+            None
+        }
+    }
+}
+
+/// Fail before downloading when the temp dir can't hold the archive plus its extracted
+/// contents, or the install location can't hold the binaries. A location whose free space
+/// can't be determined is skipped with a warning.
+fn check_disk_space(archive_size: u64, install_dir: &Path) -> Result<(), String> {
+    let extracted_size = archive_size * EXTRACTION_SIZE_FACTOR;
+    let checks = [
+        (
+            std::env::temp_dir(),
+            archive_size + extracted_size + DISK_SPACE_HEADROOM,
+            "download and extraction",
+        ),
+        (install_dir.to_path_buf(), extracted_size + DISK_SPACE_HEADROOM, "installation"),
+    ];
+    for (dir, needed, purpose) in checks {
+        let available = match available_space(&dir) {
+            Ok(available) => available,
+            Err(e) => {
+                warn!("Skipping disk space check: {}", e);
+                continue;
+            }
+        };
+        debug!("{:?}: {} bytes available, {} needed for {}", dir, available, needed, purpose);
+        if available < needed {
+            let message = format!(
+                "Insufficient disk space in {:?} for {}: {} MB needed, {} MB available",
+                dir,
+                purpose,
+                needed.div_ceil(1024 * 1024),
+                available / (1024 * 1024)
+            );
+            error!("{}", message);
+            return Err(message);
+        }
+    }
+    Ok(())
+}
+
+fn verify_checksum(actual: &str, expected: &str, asset_name: &str) -> Result<(), String> {
+    if actual != expected {
+        error!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            asset_name, expected, actual
+        );
+        return Err(format!(
+            "Downloaded {} failed integrity check (SHA-256 mismatch); the download may be corrupted or tampered with",
+            asset_name
+        ));
+    }
+    debug!("Checksum verified for {}: {}", asset_name, actual);
+    Ok(())
+}
+
+enum DownloadAttempt {
+    Complete,
+    // Connection dropped or the body was cut short; worth resuming
+    Interrupted(String),
+}
+
+/// Total size of the resource from a `Content-Range: bytes a-b/total` header
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Emit download progress, interpolating between the download stages when the size is known
+fn emit_download_progress(app: &AppHandle, received: u64, total: Option<u64>) {
+    let (progress, message) = match total {
+        Some(total) if total > 0 => (
+            DOWNLOAD_PROGRESS_START
+                + (DOWNLOAD_PROGRESS_END - DOWNLOAD_PROGRESS_START)
+                    * (received.min(total) as f32 / total as f32),
+            format!(
+                "Downloaded {:.1} of {:.1} MB",
+                received as f64 / 1_048_576.0,
+                total as f64 / 1_048_576.0
+            ),
+        ),
+        // Size unknown: keep the bar where it is rather than show a made-up percentage
+        _ => (
+            DOWNLOAD_PROGRESS_START,
+            format!(
+                "Downloading... {:.1} MB received",
+                received as f64 / 1_048_576.0
+            ),
+        ),
+    };
+    let _ = emit_progress(app, "downloading", progress, Some(message));
+}
+
+/// One pass at downloading `url` into `part_path`, continuing from whatever is already there.
+/// `hasher` holds the SHA-256 state of the bytes already in `part_path`.
+async fn download_attempt(
+    app: &AppHandle,
+    url: &str,
+    part_path: &Path,
+    hasher: &mut Sha256,
+) -> Result<DownloadAttempt, String> {
+    let downloaded = fs::metadata(part_path).map(|m| m.len()).unwrap_or(0);
+    let mut request = RELEASE_CLIENT.get(url);
+    if downloaded > 0 {
+        debug!("Resuming download at byte {}", downloaded);
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", downloaded));
+    }
+
+    let mut response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return Ok(DownloadAttempt::Interrupted(e.to_string())),
+    };
+
+    let status = response.status();
+    let (mut file, mut received, total) = if status == reqwest::StatusCode::PARTIAL_CONTENT {
+        let file = OpenOptions::new()
+            .append(true)
+            .open(part_path)
+            .map_err(|e| format!("Failed to open partial download: {}", e))?;
+        (file, downloaded, content_range_total(&response))
+    } else if status.is_success() {
+        // Server ignored the range (or this is the first attempt): start from scratch
+        if downloaded > 0 {
+            debug!("Server does not support range requests, restarting download");
+        }
+        let file = File::create(part_path)
+            .map_err(|e| format!("Failed to create download file: {}", e))?;
+        *hasher = Sha256::new();
+        (file, 0, response.content_length())
+    } else if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // Our partial file is unusable (e.g. the asset changed); throw it away and retry
+        let _ = fs::remove_file(part_path);
+        *hasher = Sha256::new();
+        return Ok(DownloadAttempt::Interrupted(format!("Server returned {}", status)));
+    } else if status.is_server_error() {
+        return Ok(DownloadAttempt::Interrupted(format!("Server returned {}", status)));
+    } else {
+        error!(
+            "Asset download failed: {} - {}",
+            status,
+            status.canonical_reason().unwrap_or("Unknown error")
+        );
+        return Err(format!(
+            "Download failed: {} - {}",
+            status,
+            status.canonical_reason().unwrap_or("Unknown error")
+        ));
+    };
+
+    emit_download_progress(app, received, total);
+    let mut last_reported = received;
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                check_canceled()?;
+                file.write_all(&chunk)
+                    .map_err(|e| format!("Failed to write download: {}", e))?;
+                hasher.update(&chunk);
+                received += chunk.len() as u64;
+
+                // Report whole-percent steps, or every MB when the size is unknown
+                let report = match total {
+                    Some(total) if total > 0 => received * 100 / total > last_reported * 100 / total,
+                    _ => received - last_reported >= DOWNLOAD_PROGRESS_UNKNOWN_STEP,
+                };
+                if report {
+                    emit_download_progress(app, received, total);
+                    last_reported = received;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => return Ok(DownloadAttempt::Interrupted(e.to_string())),
+        }
+    }
+
+    match total {
+        Some(total) if received < total => Ok(DownloadAttempt::Interrupted(format!(
+            "Received {} of {} bytes",
+            received, total
+        ))),
+        _ => Ok(DownloadAttempt::Complete),
+    }
+}
+
+/// Download `url` to `download_path` via a `.part` file, resuming with HTTP range requests
+/// when the connection drops. The final file only appears once the full content arrived.
+/// Returns the hex SHA-256 of the file, hashed as it was written.
+async fn download_resumable(
+    app: &AppHandle,
+    url: &str,
+    download_path: &Path,
+) -> Result<String, String> {
+    let mut part_name = download_path.as_os_str().to_owned();
+    part_name.push(".part");
+    let part_path = PathBuf::from(part_name);
+
+    let mut hasher = Sha256::new();
+    let mut last_error = String::new();
+    for attempt in 1..=MAX_DOWNLOAD_ATTEMPTS {
+        match download_attempt(app, url, &part_path, &mut hasher).await? {
+            DownloadAttempt::Complete => {
+                fs::rename(&part_path, download_path)
+                    .map_err(|e| format!("Failed to finalize download: {}", e))?;
+                return Ok(hex::encode(hasher.finalize()));
+            }
+            DownloadAttempt::Interrupted(reason) => {
+                warn!(
+                    "Download interrupted (attempt {}/{}): {}",
+                    attempt, MAX_DOWNLOAD_ATTEMPTS, reason
+                );
+                last_error = reason;
+            }
+        }
+
+        if attempt < MAX_DOWNLOAD_ATTEMPTS {
+            let received = fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+            emit_progress(
+                app,
+                "downloading",
+                DOWNLOAD_PROGRESS_START,
+                Some(format!(
+                    "Connection interrupted after {:.1} MB, resuming download (attempt {})...",
+                    received as f64 / 1_048_576.0,
+                    attempt + 1
+                )),
+            )
+            .map_err(|e| format!("Failed to emit progress: {}", e))?;
+            tokio::time::sleep(DOWNLOAD_RETRY_DELAY).await;
+        }
+    }
+
+    error!("Failed to download asset: {}", last_error);
+    Err(format!("Failed to download release: {}", last_error))
+}
+
 async fn install_binaries(
     app: &AppHandle,
     release: &GithubRelease,
     location: &InstallLocation,
-) -> Result<(), String> {
+) -> Result<(), BBError> {
     info!("Starting binary installation process");
+    let (temp_dir, download_path) = download_release_asset(app, release, location).await?;
+    install_downloaded_asset(app, &temp_dir, &download_path, location)
+        .map_err(BBError::ProcessError)
+}
+
+/// Download and verify the release archive for this platform into a new TempDir
+async fn download_release_asset(
+    app: &AppHandle,
+    release: &GithubRelease,
+    location: &InstallLocation,
+) -> Result<(TempDir, PathBuf), BBError> {
     // Determine platform-specific asset name
     let os = if cfg!(target_os = "windows") {
         "pc-windows-msvc"
@@ -788,7 +1590,7 @@ async fn install_binaries(
     } else if cfg!(target_arch = "aarch64") {
         "aarch64"
     } else {
-        return Err("Unsupported architecture".to_string());
+        return Err(BBError::Other("Unsupported architecture".to_string()));
     };
 
     let asset_name = if cfg!(target_os = "windows") {
@@ -810,22 +1612,31 @@ async fn install_binaries(
             },
             Some,
         )
-        .ok_or_else(|| format!("No compatible release found for {}-{}", arch, os))?;
+        .ok_or_else(|| {
+            BBError::NetworkError(format!("No compatible release found for {}-{}", arch, os))
+        })?;
 
     debug!(
         "Found matching asset: {} at URL: {}",
         asset.name, asset.browser_download_url
     );
+
+    match asset_size(asset).await {
+        Some(size) => check_disk_space(size, &location.path).map_err(BBError::ProcessError)?,
+        None => warn!("Size of {} unknown; skipping disk space check", asset.name),
+    }
+
     emit_progress(
         app,
         "downloading",
-        50.0,
+        DOWNLOAD_PROGRESS_START,
         Some(format!("Downloading {} from GitHub...", asset_name)),
     )
     .map_err(|e| format!("Failed to emit progress: {}", e))?;
 
     // Create temporary directory for download
-    let temp_dir = TempDir::new().map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let temp_dir =
+        TempDir::new().map_err(|e| BBError::from_io("Failed to create temp directory", &e))?;
     let download_path = temp_dir.path().join(if cfg!(target_os = "windows") {
         "bb.zip"
     } else {
@@ -833,65 +1644,35 @@ async fn install_binaries(
     });
 
     // Download the asset
-    let response = reqwest::get(&asset.browser_download_url)
+    let actual = download_resumable(app, &asset.browser_download_url, &download_path)
         .await
-        .map_err(|e| {
-            error!("Failed to download asset: {}", e);
-            e
-        })
-        .map_err(|e| format!("Failed to download release: {}", e))?;
-
-    if !response.status().is_success() {
-        error!(
-            "Asset download failed: {} - {}",
-            response.status(),
-            response
-                .status()
-                .canonical_reason()
-                .unwrap_or("Unknown error")
-        );
-        return Err(format!(
-            "Download failed: {} - {}",
-            response.status(),
-            response
-                .status()
-                .canonical_reason()
-                .unwrap_or("Unknown error")
-        ));
-    }
+        .map_err(BBError::NetworkError)?;
 
     emit_progress(
         app,
         "downloading",
-        70.0,
-        Some("Saving download...".to_string()),
+        DOWNLOAD_PROGRESS_END,
+        Some("Verifying download...".to_string()),
     )
     .map_err(|e| format!("Failed to emit progress: {}", e))?;
 
-    // Save the download
-    let content = response
-        .bytes()
+    check_canceled()?;
+    // Verify before extracting anything; temp_dir is removed when we bail out
+    let expected = expected_checksum(release, asset)
         .await
-        .map_err(|e| {
-            error!("Failed to read download content: {}", e);
-            e
-        })
-        .map_err(|e| format!("Failed to read download: {}", e))?;
-
-    let mut file = File::create(&download_path)
-        .map_err(|e| {
-            error!("Failed to create file at {:?}: {}", download_path, e);
-            e
-        })
-        .map_err(|e| format!("Failed to create download file: {}", e))?;
-
-    file.write_all(&content)
-        .map_err(|e| {
-            error!("Failed to write content to {:?}: {}", download_path, e);
-            e
-        })
-        .map_err(|e| format!("Failed to write download: {}", e))?;
+        .map_err(BBError::NetworkError)?;
+    verify_checksum(&actual, &expected, &asset.name).map_err(BBError::NetworkError)?;
+    Ok((temp_dir, download_path))
+}
 
+/// Extract a verified release archive and replace the installed binaries, putting back the
+/// ones already replaced if canceled part way through
+fn install_downloaded_asset(
+    app: &AppHandle,
+    temp_dir: &TempDir,
+    download_path: &Path,
+    location: &InstallLocation,
+) -> Result<(), String> {
     emit_progress(
         app,
         "installing",
@@ -904,7 +1685,7 @@ async fn install_binaries(
     #[cfg(target_os = "windows")]
     {
         debug!("Extracting Windows zip archive");
-        let file = File::open(&download_path)
+        let file = File::open(download_path)
             .map_err(|e| {
                 error!("Failed to open zip archive: {}", e);
                 e
@@ -919,6 +1700,7 @@ async fn install_binaries(
             .map_err(|e| format!("Failed to read archive: {}", e))?;
 
         for i in 0..archive.len() {
+            check_canceled()?;
             let mut file = archive
                 .by_index(i)
                 .map_err(|e| {
@@ -927,7 +1709,17 @@ async fn install_binaries(
                 })
                 .map_err(|e| format!("Failed to read zip entry: {}", e))?;
 
-            let outpath = temp_dir.path().join(file.name());
+            // Entries with absolute paths or `..` could write outside temp_dir (zip-slip)
+            let outpath = match file.enclosed_name() {
+                Some(name) => temp_dir.path().join(name),
+                None => {
+                    error!("Refusing to extract unsafe archive entry {:?}", file.name());
+                    return Err(format!(
+                        "Release archive contains an unsafe path: {}",
+                        file.name()
+                    ));
+                }
+            };
             debug!("Extracting to {:?}", outpath);
 
             if file.name().ends_with('/') {
@@ -968,18 +1760,37 @@ async fn install_binaries(
     {
         debug!("Extracting Unix tar.gz archive");
         let tar_gz =
-            File::open(&download_path).map_err(|e| format!("Failed to open archive: {}", e))?;
+            File::open(download_path).map_err(|e| format!("Failed to open archive: {}", e))?;
 
         let tar = GzDecoder::new(tar_gz);
         let mut archive = Archive::new(tar);
 
-        archive
-            .unpack(temp_dir.path())
-            .map_err(|e| {
-                error!("Failed to extract archive: {}", e);
-                e
-            })
-            .map_err(|e| format!("Failed to extract archive: {}", e))?;
+        let entries = archive
+            .entries()
+            .map_err(|e| format!("Failed to read archive: {}", e))?;
+        for entry in entries {
+            check_canceled()?;
+            let mut entry = entry.map_err(|e| format!("Failed to read archive entry: {}", e))?;
+            let path = entry
+                .path()
+                .map_err(|e| format!("Failed to read archive entry: {}", e))?
+                .into_owned();
+            // Entries with absolute paths or `..` could write outside temp_dir (zip-slip)
+            if !is_safe_archive_path(&path) {
+                error!("Refusing to extract unsafe archive entry {:?}", path);
+                return Err(format!(
+                    "Release archive contains an unsafe path: {}",
+                    path.display()
+                ));
+            }
+            entry
+                .unpack_in(temp_dir.path())
+                .map_err(|e| {
+                    error!("Failed to extract archive: {}", e);
+                    e
+                })
+                .map_err(|e| format!("Failed to extract archive: {}", e))?;
+        }
     }
 
     emit_progress(
@@ -996,8 +1807,26 @@ async fn install_binaries(
     } else {
         vec!["bb", "bb-api", "bb-bui"]
     };
+    verify_extracted_binaries(temp_dir.path(), &binaries)?;
+
+    // Keep the binaries being replaced so a cancel part way through leaves the old set
+    let previous_dir = temp_dir.path().join("previous");
+    fs::create_dir_all(&previous_dir)
+        .map_err(|e| format!("Failed to create directory: {}", e))?;
+    for binary in &binaries {
+        let target = location.path.join(binary);
+        if target.exists() {
+            fs::copy(&target, previous_dir.join(binary))
+                .map_err(|e| format!("Failed to save existing {}: {}", binary, e))?;
+        }
+    }
+
+    for (index, binary) in binaries.iter().copied().enumerate() {
+        if let Err(e) = check_canceled() {
+            restore_replaced_binaries(&binaries[..index], &previous_dir, location);
+            return Err(e);
+        }
 
-    for binary in binaries {
         let source = temp_dir.path().join(binary);
         let target = location.path.join(binary);
         debug!("Installing binary from {:?} to {:?}", source, target);
@@ -1082,41 +1911,107 @@ pub async fn open_external_url(url: String, _app: AppHandle) -> Result<(), Strin
         url
     );
 
-    // Create command based on platform
-    let cmd = if cfg!(target_os = "windows") {
-        "cmd"
-    } else if cfg!(target_os = "macos") {
-        "open"
-    } else {
-        "xdg-open"
-    };
+    // The opener hands the URL to the OS (ShellExecuteW on Windows) as a single argument,
+    // unlike `cmd /c start`, which re-parses it and would run anything after a `&`
+    match open_web_url(&url, |url| tauri_plugin_opener::open_url(url, None::<&str>)) {
+        Ok(()) => {
+            info!("[DOWNLOAD HANDLER] Opened URL in default browser");
+            Ok(())
+        }
+        Err(e) => {
+            error!("[DOWNLOAD HANDLER] {}", e);
+            Err(e)
+        }
+    }
+}
 
-    let args = if cfg!(target_os = "windows") {
-        vec!["/c", "start", "", url.as_str()]
-    } else if cfg!(target_os = "macos") {
-        vec![url.as_str()]
-    } else {
-        vec![url.as_str()]
-    };
+/// Pass `url` unchanged to `open` if it is an http(s) URL. Other schemes (`file:`,
+/// `ms-settings:`, ...) would have the OS launch files or protocol handlers instead of a browser.
+fn open_web_url<E: std::fmt::Display>(
+    url: &str,
+    open: impl FnOnce(&str) -> Result<(), E>,
+) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!(
+            "Refusing to open URL with scheme '{}'",
+            parsed.scheme()
+        ));
+    }
+    open(url).map_err(|e| format!("Failed to open URL: {}", e))
+}
 
-    info!(
-        "[DOWNLOAD HANDLER] Executing command: {} with args: {:?}",
-        cmd, args
-    );
+/// Download the application update, verifying it against the updater's public key. The
+/// macOS helper script installs the archive itself, bypassing the updater's own install.
+#[cfg(target_os = "macos")]
+async fn download_verified_update(update: &tauri_plugin_updater::Update) -> Result<Vec<u8>, String> {
+    update.download(|_, _| {}, || {}).await.map_err(|e| {
+        error!("Application update download failed verification: {}", e);
+        format!("Failed to download or verify application update: {}", e)
+    })
+}
 
-    // Open URL using the standard Rust command for compatibility
-    match std::process::Command::new(cmd).args(args).spawn() {
-        Ok(child) => {
-            info!(
-                "[DOWNLOAD HANDLER] Successfully spawned process with ID: {:?}",
-                child.id()
-            );
-            Ok(())
+/// Write and start bb-update-helper.sh, returning once the helper has signalled that it is
+/// running. Only then is it safe for the app to exit and leave the update to the helper.
+#[cfg(target_os = "macos")]
+async fn launch_update_helper(update_archive_path: &str, app_bundle: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Create helper script in temp directory
+    let temp_dir = std::env::temp_dir();
+    let helper_script = temp_dir.join("bb-update-helper.sh");
+    let ready_file = temp_dir.join("bb-update-helper.ready");
+
+    // Embed the helper script content
+    let script_content = include_str!("../../../update-helper.sh");
+
+    std::fs::write(&helper_script, script_content)
+        .map_err(|e| format!("Failed to write helper script: {}", e))?;
+
+    // Make script executable
+    std::fs::set_permissions(&helper_script, std::fs::Permissions::from_mode(0o755))
+        .map_err(|e| format!("Failed to make helper script executable: {}", e))?;
+
+    // A ready file left by an earlier attempt would look like a successful launch
+    if ready_file.exists() {
+        std::fs::remove_file(&ready_file)
+            .map_err(|e| format!("Failed to remove stale helper ready file: {}", e))?;
+    }
+
+    // Get current process ID
+    let current_pid = std::process::id();
+
+    info!("Launching update helper script from: {:?}", helper_script);
+
+    // Launch the helper script with proper detachment
+    let mut child = std::process::Command::new(&helper_script)
+        .arg(update_archive_path)
+        .arg(app_bundle)
+        .arg(current_pid.to_string())
+        .arg(&ready_file)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to launch update helper: {}", e))?;
+
+    let deadline = std::time::Instant::now() + UPDATE_HELPER_READY_TIMEOUT;
+    loop {
+        if ready_file.exists() {
+            let _ = std::fs::remove_file(&ready_file);
+            return Ok(());
         }
-        Err(e) => {
-            error!("[DOWNLOAD HANDLER] Failed to open URL: {}", e);
-            Err(format!("Failed to open URL: {}", e))
+        if let Ok(Some(status)) = child.try_wait() {
+            return Err(format!("Update helper exited before it was ready ({})", status));
+        }
+        if std::time::Instant::now() >= deadline {
+            let _ = child.kill();
+            return Err(format!(
+                "Update helper did not start within {}s",
+                UPDATE_HELPER_READY_TIMEOUT.as_secs()
+            ));
         }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
     }
 }
 
@@ -1137,45 +2032,18 @@ async fn restart_application_safely_two_stage(_app: &AppHandle, _update_archive_
         
         info!("Found app bundle at: {:?}", app_bundle);
         
-        // Create helper script in temp directory
-        let temp_dir = std::env::temp_dir();
-        let helper_script = temp_dir.join("bb-update-helper.sh");
-        
-        // Embed the helper script content
-        let script_content = include_str!("../../../update-helper.sh");
-        
-        std::fs::write(&helper_script, script_content)
-            .map_err(|e| format!("Failed to write helper script: {}", e))?;
-        
-        // Make script executable
-        std::process::Command::new("chmod")
-            .args(["+x", helper_script.to_str().unwrap_or("")])
-            .output()
-            .map_err(|e| format!("Failed to make helper script executable: {}", e))?;
-        
-        // Get current process ID
-        let current_pid = std::process::id();
-        
-        info!("Launching update helper script from: {:?}", helper_script);
-        
-        // Launch the helper script with proper detachment
-        std::process::Command::new(&helper_script)
-            .args([
-                _update_archive_path,
-                app_bundle.to_str().unwrap_or(""),
-                &current_pid.to_string(),
-            ])
-            .stdin(std::process::Stdio::null())
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .spawn()
-            .map_err(|e| format!("Failed to launch update helper: {}", e))?;
+        if let Err(e) = launch_update_helper(_update_archive_path, app_bundle).await {
+            // Exiting now would leave no app running and the update not applied
+            error!("Update helper failed, restarting without applying the update: {}", e);
+            restart_application_safely(_app).await?;
+            return Err(format!(
+                "The update could not be applied ({}). Restarting the current version.",
+                e
+            ));
+        }
         
         info!("Update helper launched, current process will exit");
         
-        // Give the helper script a moment to start
-        tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
-        
         // Exit this process cleanly - this will not return
         std::process::exit(0);
     }
@@ -1204,7 +2072,79 @@ async fn restart_application_safely(app: &AppHandle) -> Result<(), String> {
     Ok(())
 }
 
-fn backup_current_installation(location: &InstallLocation) -> Result<(), String> {
+/// Whether an archive entry stays inside the directory it is extracted to
+#[cfg(not(target_os = "windows"))]
+fn is_safe_archive_path(path: &Path) -> bool {
+    use std::path::Component;
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(target_os = "windows")]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    true
+}
+
+/// Check that extraction produced every binary as a non-empty executable file, so a
+/// changed archive layout fails with one clear error before anything is replaced
+fn verify_extracted_binaries(dir: &Path, binaries: &[&str]) -> Result<(), String> {
+    let mut problems = Vec::new();
+    for binary in binaries {
+        let path = dir.join(binary);
+        match fs::metadata(&path) {
+            Err(_) => problems.push(format!("{} is missing", binary)),
+            Ok(metadata) if !metadata.is_file() => problems.push(format!("{} is not a file", binary)),
+            Ok(metadata) if metadata.len() == 0 => problems.push(format!("{} is empty", binary)),
+            Ok(metadata) if !is_executable(&metadata) => {
+                problems.push(format!("{} is not executable", binary))
+            }
+            Ok(_) => {}
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    error!("Release archive failed verification: {:?}", problems);
+    Err(format!(
+        "Release archive does not contain valid binaries: {}",
+        problems.join(", ")
+    ))
+}
+
+/// Undo an interrupted install_binaries: put back the saved copies of `replaced`, removing
+/// binaries that didn't exist before
+fn restore_replaced_binaries(replaced: &[&str], previous_dir: &Path, location: &InstallLocation) {
+    for binary in replaced {
+        let previous = previous_dir.join(binary);
+        let target = location.path.join(binary);
+        let result = if previous.exists() {
+            fs::copy(&previous, &target).map(|_| ())
+        } else {
+            fs::remove_file(&target)
+        };
+        match result {
+            Ok(()) => debug!("Restored {:?}", target),
+            Err(e) => error!("Failed to restore {:?} after cancel: {}", target, e),
+        }
+    }
+}
+
+fn installed_binaries() -> Vec<&'static str> {
+    if cfg!(target_os = "windows") {
+        vec!["bb.exe", "bb-api.exe", "bb-bui.exe"]
+    } else {
+        vec!["bb", "bb-api", "bb-bui"]
+    }
+}
+
+fn backup_current_installation(location: &InstallLocation) -> Result<TempDir, String> {
     debug!(
         "Creating backup of current installation from {:?}",
         location.path
@@ -1212,13 +2152,7 @@ fn backup_current_installation(location: &InstallLocation) -> Result<(), String>
     let backup_dir =
         tempfile::tempdir().map_err(|e| format!("Failed to create backup directory: {}", e))?;
 
-    let binaries = if cfg!(target_os = "windows") {
-        vec!["bb.exe", "bb-api.exe", "bb-bui.exe"]
-    } else {
-        vec!["bb", "bb-api", "bb-bui"]
-    };
-
-    for binary in binaries {
+    for binary in installed_binaries() {
         let source = location.path.join(binary);
         if source.exists() {
             debug!("Backing up binary: {:?}", source);
@@ -1228,5 +2162,90 @@ fn backup_current_installation(location: &InstallLocation) -> Result<(), String>
         }
     }
 
+    Ok(backup_dir)
+}
+
+/// Copy binaries saved by `backup_current_installation` back over the installed ones
+fn restore_backup(backup_dir: &Path, location: &InstallLocation) -> Result<(), String> {
+    debug!(
+        "Restoring backup from {:?} to {:?}",
+        backup_dir, location.path
+    );
+    for binary in installed_binaries() {
+        let backup = backup_dir.join(binary);
+        if backup.exists() {
+            debug!("Restoring binary: {:?}", backup);
+            fs::copy(&backup, location.path.join(binary))
+                .map_err(|e| format!("Failed to restore {}: {}", binary, e))?;
+        }
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatched_checksum_is_refused() {
+        let actual = hex::encode(Sha256::digest(b"not the release archive"));
+        let expected = hex::encode(Sha256::digest(b"the release archive"));
+
+        let error = verify_checksum(&actual, &expected, "bb.tar.gz")
+            .expect_err("install should be refused");
+
+        assert!(
+            error.contains("integrity check"),
+            "unexpected error: {}",
+            error
+        );
+    }
+
+    /// Run `open_web_url` with an opener that records what it was given
+    fn open_recorded(url: &str) -> (Result<(), String>, Vec<String>) {
+        let mut opened = Vec::new();
+        let result = open_web_url(url, |url| {
+            opened.push(url.to_string());
+            Ok::<_, String>(())
+        });
+        (result, opened)
+    }
+
+    #[test]
+    fn shell_metacharacters_reach_the_opener_as_one_argument() {
+        for url in [
+            "https://github.com/Beyond-Better/bb/releases?a=1&calc.exe",
+            "https://beyondbetter.app/download?name=\"bb\"&x='y'",
+            "https://beyondbetter.app/download|calc",
+            "http://localhost:8080/a&b|c\"d",
+        ] {
+            let (result, opened) = open_recorded(url);
+
+            assert!(result.is_ok(), "{} was refused: {:?}", url, result);
+            assert_eq!(opened, vec![url.to_string()]);
+        }
+    }
+
+    #[test]
+    fn non_web_schemes_are_not_opened() {
+        for url in [
+            "file:///C:/Windows/System32/calc.exe",
+            "ms-settings:privacy",
+            "javascript:alert(1)",
+            "smb://evil.example/share&calc",
+            "not a url|calc",
+        ] {
+            let (result, opened) = open_recorded(url);
+
+            assert!(result.is_err(), "{} was opened", url);
+            assert!(opened.is_empty(), "{} reached the opener", url);
+        }
+    }
+
+    #[test]
+    fn matching_checksum_is_accepted() {
+        let checksum = hex::encode(Sha256::digest(b"the release archive"));
+
+        assert!(verify_checksum(&checksum, &checksum, "bb.tar.gz").is_ok());
+    }
+}
@@ -2,18 +2,26 @@
 // Installation/upgrade functionality has been moved to commands/upgrade.rs
 
 use crate::api::get_bb_api_path;
+use crate::commands::retry::send_with_retry;
+use crate::commands::server_status::get_app_runtime_dir;
+use crate::config::{get_update_channel, UpdateChannel};
 use log::{debug, error, info, warn};
 use once_cell::sync::Lazy;
 use reqwest;
 use semver::Version;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use std::time::{Duration, Instant};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::command;
+use tokio::process::Command;
 
-const RELEASE_API_URL: &str = "https://asyagnmzoxgyhqprdaky.storage.supabase.co/storage/v1/object/releases/latest.json";
+const RELEASE_API_BASE_URL: &str =
+    "https://asyagnmzoxgyhqprdaky.storage.supabase.co/storage/v1/object/releases";
 const GITHUB_CACHE_DURATION: Duration = Duration::from_secs(3600); // 1 hour
+const BINARY_VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+const VERSION_CACHE_FILE_NAME: &str = "version-cache.json";
 
 #[derive(Debug, Deserialize)]
 struct GithubRelease {
@@ -21,16 +29,65 @@ struct GithubRelease {
     body: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct VersionCache {
+    channel: UpdateChannel,
     version: String,
     release_notes: Option<String>,
     has_breaking_changes: Option<bool>,
     critical_notice: Option<String>,
-    timestamp: Instant,
+    #[serde(default)]
+    etag: Option<String>,
+    // Seconds since the Unix epoch, so the age survives app restarts
+    timestamp: u64,
 }
 
-static GITHUB_VERSION_CACHE: Lazy<Mutex<Option<VersionCache>>> = Lazy::new(|| Mutex::new(None));
+impl VersionCache {
+    fn is_fresh(&self) -> bool {
+        unix_now().saturating_sub(self.timestamp) < GITHUB_CACHE_DURATION.as_secs()
+    }
+}
+
+// Seeded from disk on first use so a restart doesn't force a new release lookup
+static GITHUB_VERSION_CACHE: Lazy<Mutex<Option<VersionCache>>> =
+    Lazy::new(|| Mutex::new(load_version_cache()));
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn version_cache_path() -> Result<PathBuf, String> {
+    Ok(get_app_runtime_dir()?.join(VERSION_CACHE_FILE_NAME))
+}
+
+fn load_version_cache() -> Option<VersionCache> {
+    let path = version_cache_path().ok()?;
+    let contents = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(cache) => Some(cache),
+        Err(e) => {
+            warn!("Ignoring unreadable version cache {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Store `version_cache` in memory and on disk
+fn save_version_cache(version_cache: &VersionCache) {
+    if let Ok(mut cache) = GITHUB_VERSION_CACHE.lock() {
+        *cache = Some(version_cache.clone());
+    }
+    let result = version_cache_path().and_then(|path| {
+        let json = serde_json::to_string(version_cache).map_err(|e| e.to_string())?;
+        fs::write(&path, json).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        warn!("Failed to persist version cache: {}", e);
+    }
+}
 
 #[derive(Serialize)]
 pub struct VersionInfo {
@@ -58,6 +115,11 @@ pub struct VersionCompatibility {
     has_breaking_changes: Option<bool>,
     #[serde(rename = "criticalNotice")]
     critical_notice: Option<String>,
+    #[serde(rename = "updateChannel")]
+    update_channel: UpdateChannel,
+    // Installed version is a beta but the stable channel is selected
+    #[serde(rename = "isDowngrade")]
+    is_downgrade: bool,
 }
 
 fn compare_versions(current: &str, required: &str) -> bool {
@@ -75,35 +137,62 @@ fn compare_versions(current: &str, required: &str) -> bool {
 }
 
 fn clean_version_string(s: &str) -> String {
-    s.chars()
-        .filter(|c| c.is_digit(10) || *c == '.')
+    // Keep pre-release/build suffixes (e.g. 0.9.0-beta.1) so beta builds parse
+    s.trim()
+        .trim_start_matches('v')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
         .collect::<String>()
 }
 
-async fn fetch_latest_version() -> Option<VersionCache> {
-    // Check cache first
-    if let Ok(cache) = GITHUB_VERSION_CACHE.lock() {
-        if let Some(cached) = cache.as_ref() {
-            if cached.timestamp.elapsed() < GITHUB_CACHE_DURATION {
-                return Some(cached.clone());
+async fn fetch_latest_version(channel: UpdateChannel) -> Option<VersionCache> {
+    // Check cache first; a stale entry for this channel still supplies an ETag
+    let stale = match GITHUB_VERSION_CACHE.lock() {
+        Ok(cache) => match cache.as_ref() {
+            Some(cached) if cached.channel == channel => {
+                if cached.is_fresh() {
+                    return Some(cached.clone());
+                }
+                Some(cached.clone())
             }
-        }
-    }
+            _ => None,
+        },
+        Err(_) => None,
+    };
 
     // Only fetch from release server if we don't have a valid cache
-    debug!("Version cache miss, fetching from release API");
+    debug!(
+        "Version cache miss, fetching {:?} channel from release API",
+        channel
+    );
     let user_agent = format!("BB-APP/{}", env!("CARGO_PKG_VERSION"));
-    match reqwest::Client::new()
-        .get(RELEASE_API_URL)
-        .header("User-Agent", &user_agent)
-        .header("Accept", "application/json")
-        .send()
-        .await
-    {
+    let release_url = format!("{}/{}", RELEASE_API_BASE_URL, channel.release_manifest());
+    let client = reqwest::Client::new();
+    let etag = stale.as_ref().and_then(|cached| cached.etag.clone());
+    let build_request = || {
+        let request = client
+            .get(&release_url)
+            .header("User-Agent", &user_agent)
+            .header("Accept", "application/json");
+        match etag.as_ref() {
+            Some(etag) => request.header(reqwest::header::IF_NONE_MATCH, etag),
+            None => request,
+        }
+    };
+
+    // Transient failures are retried; a rate limit comes back as an error right away
+    match send_with_retry("release manifest", build_request).await {
         Ok(response) => {
-            if response.status() == reqwest::StatusCode::FORBIDDEN {
-                warn!("Release API rate limit exceeded");
-                return None;
+            if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+                if let Some(mut cached) = stale {
+                    debug!(
+                        "Release unchanged (304), refreshing cached version {}",
+                        cached.version
+                    );
+                    cached.timestamp = unix_now();
+                    save_version_cache(&cached);
+                    return Some(cached);
+                }
             }
 
             if !response.status().is_success() {
@@ -118,6 +207,12 @@ async fn fetch_latest_version() -> Option<VersionCache> {
                 return None;
             }
 
+            let etag = response
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string());
+
             match response.json::<GithubRelease>().await {
                 Ok(release) => {
                     let version = release.tag_name.trim_start_matches('v').to_string();
@@ -161,18 +256,18 @@ async fn fetch_latest_version() -> Option<VersionCache> {
                     };
 
                     let version_cache = VersionCache {
+                        channel,
                         version: version.clone(),
                         release_notes,
                         has_breaking_changes,
                         critical_notice,
-                        timestamp: Instant::now(),
+                        etag,
+                        timestamp: unix_now(),
                     };
 
                     // Update cache
                     debug!("Successfully fetched latest version: {}", version);
-                    if let Ok(mut cache) = GITHUB_VERSION_CACHE.lock() {
-                        *cache = Some(version_cache.clone());
-                    }
+                    save_version_cache(&version_cache);
                     Some(version_cache)
                 }
                 Err(e) => {
@@ -204,7 +299,7 @@ fn get_min_version() -> String {
                     .trim_matches(';')
                     .trim();
 
-                // Further clean to ensure only digits and dots remain
+                // Further clean to a bare semver string
                 clean_version_string(cleaned)
             }) {
                 // Validate that we have a proper semver string
@@ -233,30 +328,60 @@ pub async fn get_version_info() -> Result<VersionInfo, String> {
     })
 }
 
+/// Run `<path> --version` and return its output, or None if it failed or didn't finish
+/// within BINARY_VERSION_TIMEOUT
+pub(crate) async fn run_version_command(path: &Path) -> Result<Option<String>, String> {
+    // A binary that hangs (e.g. waiting on a prompt) must not block the caller forever;
+    // kill_on_drop cleans up the child when the timeout drops the future
+    let output = match tokio::time::timeout(
+        BINARY_VERSION_TIMEOUT,
+        Command::new(path)
+            .arg("--version")
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    {
+        Ok(output) => output.map_err(|e| e.to_string())?,
+        Err(_) => {
+            warn!(
+                "Timed out after {:?} waiting for {:?} --version",
+                BINARY_VERSION_TIMEOUT, path
+            );
+            return Ok(None);
+        }
+    };
+
+    debug!("Binary version command output: {:?}", output);
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
 #[command]
 pub async fn get_binary_version() -> Result<Option<String>, String> {
     let bb_api_path = get_bb_api_path()?;
     debug!("Checking binary version at path: {:?}", bb_api_path);
+    binary_version_at(&bb_api_path).await
+}
 
-    let output = Command::new(bb_api_path)
-        .arg("--version")
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    debug!("Binary version command output: {:?}", output);
-    if !output.status.success() {
+/// Version reported by `<path> --version` on a `BB API version 1.2.3` style line, or None
+/// if the binary gave no parsable version in time
+pub(crate) async fn binary_version_at(path: &Path) -> Result<Option<String>, String> {
+    let Some(stdout) = run_version_command(path).await? else {
         return Ok(None);
-    }
+    };
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let version_line = stdout
-        .lines()
-        .find(|line| line.trim().starts_with("BB API version "));
+    let version_line = stdout.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("BB ")
+            .and_then(|rest| rest.split_once(" version "))
+    });
 
     match version_line {
-        Some(line) => {
-            let raw_version = line["BB API version ".len()..].trim().to_string();
-            let cleaned_version = clean_version_string(&raw_version);
+        Some((_, raw_version)) => {
+            let cleaned_version = clean_version_string(raw_version);
 
             match Version::parse(&cleaned_version) {
                 Ok(_) => {
@@ -281,8 +406,32 @@ pub async fn get_binary_version() -> Result<Option<String>, String> {
 
 #[command]
 pub async fn check_version_compatibility() -> Result<VersionCompatibility, String> {
-    info!("Checking version compatibility");
+    version_compatibility(true).await
+}
+
+/// Compare the installed API against REQUIRED_API_VERSION only, without contacting the
+/// release server. Cheap enough for periodic checks; `latestVersion` is always null.
+#[command]
+pub async fn check_version_compatibility_local() -> Result<VersionCompatibility, String> {
+    version_compatibility(false).await
+}
+
+async fn version_compatibility(online: bool) -> Result<VersionCompatibility, String> {
     let api_version = get_binary_version().await?;
+    version_compatibility_for(api_version, get_update_channel(), online).await
+}
+
+/// Compatibility of an already determined API version, so callers that also need the
+/// version itself don't run the binary twice
+pub(crate) async fn version_compatibility_for(
+    api_version: Option<String>,
+    update_channel: UpdateChannel,
+    online: bool,
+) -> Result<VersionCompatibility, String> {
+    info!(
+        "Checking version compatibility ({})",
+        if online { "online" } else { "local" }
+    );
     let min_version = get_min_version();
 
     debug!(
@@ -306,8 +455,12 @@ pub async fn check_version_compatibility() -> Result<VersionCompatibility, Strin
         "API installed: {}, compatible with minimum version: {}",
         api_installed, compatible
     );
-    // Fetch latest release info from release server
-    let latest_release = fetch_latest_version().await;
+    // Fetch latest release info for the selected channel from release server
+    let latest_release = if online {
+        fetch_latest_version(update_channel).await
+    } else {
+        None
+    };
     debug!("Latest release from release server: {:?}", latest_release);
 
     let (latest_version, release_notes, has_breaking_changes, critical_notice) =
@@ -322,10 +475,26 @@ pub async fn check_version_compatibility() -> Result<VersionCompatibility, Strin
             (None, None, None, None)
         };
 
+    // Moving from a beta back to the stable channel offers the latest stable release
+    // even when it is a lower version than the installed beta
+    let is_downgrade = update_channel == UpdateChannel::Stable
+        && match (api_version.as_ref(), latest_version.as_ref()) {
+            (Some(current), Some(latest)) => {
+                match (Version::parse(current), Version::parse(latest)) {
+                    (Ok(current_ver), Ok(latest_ver)) => {
+                        !current_ver.pre.is_empty() && latest_ver < current_ver
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+
     // Check if update is available
     // An update is available if either:
     // 1. The current version is below the required version, OR
-    // 2. There's a newer version available on GitHub
+    // 2. There's a newer version available on GitHub, OR
+    // 3. A beta is installed and the stable channel is selected
     let update_available = if let Some(current) = api_version.as_ref() {
         let needs_min_update = match Version::parse(current) {
             Ok(current_ver) => match Version::parse(&min_version) {
@@ -344,7 +513,7 @@ pub async fn check_version_compatibility() -> Result<VersionCompatibility, Strin
             false
         };
 
-        needs_min_update || needs_latest_update
+        needs_min_update || needs_latest_update || is_downgrade
     } else {
         false
     };
@@ -357,7 +526,11 @@ pub async fn check_version_compatibility() -> Result<VersionCompatibility, Strin
         current_version: api_version.unwrap_or_else(|| "not installed".to_string()),
         required_version: min_version,
         update_available,
-        latest_version: if update_available {
+        latest_version: if !online {
+            None // Nothing known about releases without asking the server
+        } else if is_downgrade {
+            latest_version // Offer the stable release rather than the minimum version
+        } else if update_available {
             Some(min_version_clone) // Always use required version when update needed
         } else {
             latest_version // Only show latest version when we're compatible but there's a newer version
@@ -365,5 +538,51 @@ pub async fn check_version_compatibility() -> Result<VersionCompatibility, Strin
         release_notes,
         has_breaking_changes,
         critical_notice,
+        update_channel,
+        is_downgrade,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// Executable script standing in for bb-api that runs `body` when called
+    #[cfg(unix)]
+    fn fake_binary(dir: &Path, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("bb-api");
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn hanging_binary_times_out_promptly() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = fake_binary(dir.path(), "sleep 60");
+
+        let started = Instant::now();
+        let version = binary_version_at(&path).await.unwrap();
+
+        assert_eq!(version, None);
+        assert!(
+            started.elapsed() < BINARY_VERSION_TIMEOUT + Duration::from_secs(2),
+            "version probe took {:?}",
+            started.elapsed()
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn version_line_is_parsed() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = fake_binary(dir.path(), "echo 'BB API version v0.9.1-beta.2'");
+
+        let version = binary_version_at(&path).await.unwrap();
+
+        assert_eq!(version.as_deref(), Some("0.9.1-beta.2"));
+    }
+}
@@ -1,11 +1,45 @@
 use dirs;
-use log::{debug, error};
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 pub const APP_NAME: &str = "dev.beyondbetter.app";
 
+// Matches the shared LogLevel type (src/shared/config/types.ts) that bb-api and bb-bui accept
+const LOG_LEVELS: [&str; 7] = [
+    "debug", "debug0", "debug1", "debug2", "info", "warn", "error",
+];
+
+/// A single problem found in config.yaml
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFieldError {
+    pub field: String,
+    pub problem: String,
+}
+
+/// Every problem found in config.yaml, so users can fix them in one pass
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigValidationError {
+    pub path: PathBuf,
+    pub errors: Vec<ConfigFieldError>,
+}
+
+impl fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid config file {}:", self.path.display())?;
+        for error in &self.errors {
+            write!(f, "\n  - {}: {}", error.field, error.problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigValidationError {}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "camelCase"))]
 pub struct TlsConfig {
@@ -139,6 +173,178 @@ pub struct DuiConfig {
     #[serde(rename = "recentProjects")]
     #[serde(default)]
     pub recent_projects: u32,
+    #[serde(rename = "logDirectory")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_directory: Option<String>,
+    #[serde(rename = "installDirectory")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub install_directory: Option<String>,
+    #[serde(rename = "proxyPort")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_port: Option<u16>,
+    // Proxy debug logging as last set with set_debug_mode; unset uses the build's default
+    #[serde(rename = "proxyDebugMode")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_debug_mode: Option<bool>,
+    // Proxy target as last set with set_proxy_target; takes precedence over proxy.target
+    #[serde(rename = "proxyTarget")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_target: Option<String>,
+    #[serde(rename = "proxyDecompress")]
+    #[serde(default)]
+    pub proxy_decompress: bool,
+    // Static headers added to every proxied request, e.g. an API gateway key or tenant id
+    #[serde(rename = "proxyExtraHeaders")]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub proxy_extra_headers: BTreeMap<String, String>,
+    // Insecure: disables TLS certificate verification for proxy targets (self-signed dev servers)
+    #[serde(rename = "proxyAcceptInvalidCerts")]
+    #[serde(default)]
+    pub proxy_accept_invalid_certs: bool,
+    // Offer HTTP/2 to HTTPS proxy targets via ALPN; HTTP/1.1 is used when they decline
+    #[serde(rename = "proxyHttp2")]
+    #[serde(default)]
+    pub proxy_http2: bool,
+    // Idle upstream connections kept per host; more are closed once requests finish
+    #[serde(rename = "proxyPoolMaxIdle")]
+    #[serde(default = "default_proxy_pool_max_idle")]
+    pub proxy_pool_max_idle: usize,
+    // Seconds an idle upstream connection is kept before closing; 0 keeps them until the target closes them
+    #[serde(rename = "proxyPoolIdleTimeoutSecs")]
+    #[serde(default = "default_proxy_pool_idle_timeout_secs")]
+    pub proxy_pool_idle_timeout_secs: u64,
+    // Add permissive CORS headers to proxied responses, for local dev targets without them
+    #[serde(rename = "proxyInjectCors")]
+    #[serde(default)]
+    pub proxy_inject_cors: bool,
+    // First wait before checking a freshly started API or BUI; later waits back off from here
+    #[serde(rename = "apiStartupPollIntervalMs")]
+    #[serde(default = "default_api_startup_poll_interval_ms")]
+    pub api_startup_poll_interval_ms: u64,
+    // Seconds to wait for a started API or BUI to respond before reporting failure
+    #[serde(rename = "serviceStartupTimeout")]
+    #[serde(default = "default_service_startup_timeout")]
+    pub service_startup_timeout: u64,
+    #[serde(rename = "autoRestartServices")]
+    #[serde(default = "default_auto_restart_services")]
+    pub auto_restart_services: bool,
+    #[serde(rename = "serviceCheckIntervalSecs")]
+    #[serde(default = "default_service_check_interval_secs")]
+    pub service_check_interval_secs: u64,
+    // Stop the API and BUI when the app quits instead of leaving them running
+    #[serde(rename = "stopServicesOnExit")]
+    #[serde(default)]
+    pub stop_services_on_exit: bool,
+    // Host suffixes bblink:// may open in the system browser; anything else is refused
+    #[serde(rename = "linkAllowedHosts")]
+    #[serde(default = "default_link_allowed_hosts")]
+    pub link_allowed_hosts: Vec<String>,
+    #[serde(rename = "updateChannel")]
+    #[serde(default)]
+    pub update_channel: UpdateChannel,
+    #[serde(rename = "logFormat")]
+    #[serde(default)]
+    pub log_format: LogFormat,
+    #[serde(rename = "windowSizingMode")]
+    #[serde(default)]
+    pub window_sizing_mode: WindowSizingMode,
+    #[serde(rename = "serviceLogMaxBytes")]
+    #[serde(default = "default_service_log_max_bytes")]
+    pub service_log_max_bytes: u64,
+    #[serde(rename = "serviceLogMaxFiles")]
+    #[serde(default = "default_service_log_max_files")]
+    pub service_log_max_files: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    /// Release manifest for the server components, relative to the releases bucket
+    pub fn release_manifest(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "latest.json",
+            UpdateChannel::Beta => "latest-beta.json",
+        }
+    }
+
+    /// Tauri updater manifest for the app itself, relative to the releases bucket
+    pub fn updater_manifest(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "updater-latest.json",
+            UpdateChannel::Beta => "updater-latest-beta.json",
+        }
+    }
+}
+
+/// Format of the app log: the classic text pattern, or one JSON object per line
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// How saved window geometry is restored. Physical reapplies the exact pixel values;
+/// logical rescales them by the saved scale factor so windows keep their apparent size
+/// on monitors with a different DPI.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowSizingMode {
+    #[default]
+    Physical,
+    Logical,
+}
+
+fn default_service_startup_timeout() -> u64 {
+    30
+}
+
+fn default_api_startup_poll_interval_ms() -> u64 {
+    500
+}
+
+fn default_proxy_pool_max_idle() -> usize {
+    // Enough for the webview's parallel requests without holding sockets open for every burst
+    32
+}
+
+fn default_proxy_pool_idle_timeout_secs() -> u64 {
+    90
+}
+
+fn default_auto_restart_services() -> bool {
+    true
+}
+
+fn default_service_log_max_bytes() -> u64 {
+    20 * 1024 * 1024
+}
+
+fn default_service_log_max_files() -> usize {
+    5
+}
+
+fn default_service_check_interval_secs() -> u64 {
+    30
+}
+
+fn default_link_allowed_hosts() -> Vec<String> {
+    [
+        "beyondbetter.dev",
+        "beyondbetter.app",
+        "github.com",
+        "githubusercontent.com",
+    ]
+    .iter()
+    .map(|host| host.to_string())
+    .collect()
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -160,6 +366,45 @@ pub struct DefaultModels {
     pub chat: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "camelCase"))]
+pub struct ProxyRoute {
+    pub path_prefix: String,
+    pub target: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "camelCase"))]
+pub struct ProxyConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<ProxyRoute>,
+    #[serde(default = "default_proxy_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_proxy_access_log_max_bytes")]
+    pub access_log_max_bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    // Tried in order when `target` can't be reached
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_targets: Vec<String>,
+    #[serde(default = "default_proxy_max_body_bytes")]
+    pub max_request_body_bytes: u64,
+    #[serde(default = "default_proxy_max_body_bytes")]
+    pub max_response_body_bytes: u64,
+}
+
+fn default_proxy_max_retries() -> u32 {
+    2
+}
+
+fn default_proxy_max_body_bytes() -> u64 {
+    512 * 1024 * 1024
+}
+
+fn default_proxy_access_log_max_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "camelCase"))]
 pub struct GlobalConfig {
@@ -182,6 +427,8 @@ pub struct GlobalConfig {
     pub cli: CliConfig,
     #[serde(default)]
     pub dui: DuiConfig,
+    #[serde(default)]
+    pub proxy: ProxyConfig,
     #[serde(rename = "bbExeName")]
     #[serde(default)]
     pub bb_exe_name: String,
@@ -283,6 +530,29 @@ impl Default for DuiConfig {
             default_api_config: serde_json::Value::Object(serde_json::Map::new()),
             projects_directory: "./projects".to_string(),
             recent_projects: 5,
+            log_directory: None,
+            install_directory: None,
+            proxy_port: None,
+            proxy_debug_mode: None,
+            proxy_target: None,
+            proxy_decompress: false,
+            proxy_extra_headers: BTreeMap::new(),
+            proxy_accept_invalid_certs: false,
+            proxy_http2: false,
+            proxy_pool_max_idle: default_proxy_pool_max_idle(),
+            proxy_pool_idle_timeout_secs: default_proxy_pool_idle_timeout_secs(),
+            proxy_inject_cors: false,
+            api_startup_poll_interval_ms: default_api_startup_poll_interval_ms(),
+            service_startup_timeout: default_service_startup_timeout(),
+            auto_restart_services: default_auto_restart_services(),
+            service_check_interval_secs: default_service_check_interval_secs(),
+            stop_services_on_exit: false,
+            link_allowed_hosts: default_link_allowed_hosts(),
+            update_channel: UpdateChannel::default(),
+            log_format: LogFormat::default(),
+            window_sizing_mode: WindowSizingMode::default(),
+            service_log_max_bytes: default_service_log_max_bytes(),
+            service_log_max_files: default_service_log_max_files(),
         }
     }
 }
@@ -297,6 +567,20 @@ impl Default for CliConfig {
     }
 }
 
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        ProxyConfig {
+            routes: Vec::new(),
+            max_retries: default_proxy_max_retries(),
+            access_log_max_bytes: default_proxy_access_log_max_bytes(),
+            target: None,
+            fallback_targets: Vec::new(),
+            max_request_body_bytes: default_proxy_max_body_bytes(),
+            max_response_body_bytes: default_proxy_max_body_bytes(),
+        }
+    }
+}
+
 impl Default for DefaultModels {
     fn default() -> Self {
         DefaultModels {
@@ -319,6 +603,7 @@ impl Default for GlobalConfig {
             bui: BuiConfig::default(),
             cli: CliConfig::default(),
             dui: DuiConfig::default(),
+            proxy: ProxyConfig::default(),
             bb_exe_name: if cfg!(target_os = "windows") {
                 "bb.exe".to_string()
             } else {
@@ -338,6 +623,71 @@ impl Default for GlobalConfig {
     }
 }
 
+fn validate_port(errors: &mut Vec<ConfigFieldError>, field: &str, port: u16) {
+    if port == 0 {
+        errors.push(ConfigFieldError {
+            field: field.to_string(),
+            problem: "must be between 1 and 65535".to_string(),
+        });
+    }
+}
+
+fn validate_non_empty(errors: &mut Vec<ConfigFieldError>, field: &str, value: &str) {
+    if value.trim().is_empty() {
+        errors.push(ConfigFieldError {
+            field: field.to_string(),
+            problem: "must not be empty".to_string(),
+        });
+    }
+}
+
+fn validate_log_level(errors: &mut Vec<ConfigFieldError>, field: &str, level: &str) {
+    // An empty level means "not set" and lets the service pick its default
+    if !level.is_empty() && !LOG_LEVELS.contains(&level.to_lowercase().as_str()) {
+        errors.push(ConfigFieldError {
+            field: field.to_string(),
+            problem: format!(
+                "unrecognized log level '{}' (expected one of {})",
+                level,
+                LOG_LEVELS.join(", ")
+            ),
+        });
+    }
+}
+
+impl GlobalConfig {
+    /// Check semantic constraints serde can't express, returning every violation found
+    pub fn validate(&self) -> Vec<ConfigFieldError> {
+        let mut errors = Vec::new();
+
+        validate_non_empty(&mut errors, "api.hostname", &self.api.hostname);
+        validate_port(&mut errors, "api.port", self.api.port);
+        validate_log_level(&mut errors, "api.logLevel", &self.api.log_level);
+
+        validate_non_empty(&mut errors, "bui.hostname", &self.bui.hostname);
+        validate_port(&mut errors, "bui.port", self.bui.port);
+        validate_log_level(&mut errors, "bui.logLevel", &self.bui.log_level);
+
+        if let Some(port) = self.dui.proxy_port {
+            validate_port(&mut errors, "dui.proxyPort", port);
+        }
+
+        validate_non_empty(
+            &mut errors,
+            "defaultModels.orchestrator",
+            &self.default_models.orchestrator,
+        );
+        validate_non_empty(
+            &mut errors,
+            "defaultModels.agent",
+            &self.default_models.agent,
+        );
+        validate_non_empty(&mut errors, "defaultModels.chat", &self.default_models.chat);
+
+        errors
+    }
+}
+
 pub fn get_default_log_path(filename: &str) -> Option<String> {
     #[cfg(target_os = "macos")]
     {
@@ -404,7 +754,316 @@ pub fn get_global_config_dir() -> Result<PathBuf, std::io::Error> {
     Ok(config_dir)
 }
 
+/// Replace config.yaml with `yaml` atomically: the new contents go to a temp file in the
+/// same directory which is then renamed over the original, so a crash mid-write leaves
+/// the previous config intact.
+pub fn write_global_config_yaml(yaml: &str) -> Result<(), std::io::Error> {
+    let config_dir = get_global_config_dir()?;
+    fs::create_dir_all(&config_dir)?;
+    replace_file_atomically(&config_dir.join("config.yaml"), |file| {
+        file.write_all(yaml.as_bytes())
+    })
+}
+
+// Config writes come from commands, watchers and startup migrations on different threads;
+// only one may replace config.yaml at a time
+static CONFIG_WRITE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Replace `path` with what `write` puts in a uniquely named temp file next to it. If
+/// `write` fails the temp file is removed and `path` is left untouched.
+fn replace_file_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> Result<(), std::io::Error>,
+) -> Result<(), std::io::Error> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let _guard = CONFIG_WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    // Dropping the temp file on any error below removes it
+    let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+    write(temp.as_file_mut())?;
+    temp.as_file().sync_all()?;
+
+    // Renaming replaces an existing file on all platforms, but on Windows it fails while
+    // another process (editor, antivirus) briefly holds config.yaml open, so retry a little
+    let mut attempts = 0;
+    loop {
+        match temp.persist(path) {
+            Ok(_) => break,
+            Err(e) if cfg!(target_os = "windows") && attempts < 5 => {
+                debug!("Retrying config replace after error: {}", e.error);
+                attempts += 1;
+                temp = e.file;
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+            Err(e) => {
+                error!("Failed to replace {:?}: {}", path, e.error);
+                return Err(e.error);
+            }
+        }
+    }
+
+    // Make the rename itself durable, not just the new contents
+    #[cfg(unix)]
+    if let Err(e) = fs::File::open(dir).and_then(|dir| dir.sync_all()) {
+        warn!("Failed to sync {:?} after replacing {:?}: {}", dir, path, e);
+    }
+    Ok(())
+}
+
+type ConfigMigration = fn(&mut serde_yaml::Mapping);
+
+// Ordered oldest first: each step brings a config older than its version up to it.
+// These mirror the CLI/API config manager (src/shared/config/configManager.ts) for the
+// fields this app reads; later versions are left to that manager.
+const CONFIG_MIGRATIONS: &[(&str, ConfigMigration)] = &[("2.2.0", migrate_llm_keys_to_providers)];
+
+/// 2.2.0: API keys moved from `api.llmKeys.<provider>` (and the older top-level
+/// `anthropicApiKey`) to `api.llmProviders.<provider>.apiKey`
+fn migrate_llm_keys_to_providers(root: &mut serde_yaml::Mapping) {
+    let mut keys: Vec<(String, serde_yaml::Value)> = Vec::new();
+    if let Some(key) = root.remove("anthropicApiKey") {
+        keys.push(("anthropic".to_string(), key));
+    }
+
+    let Some(api) = root
+        .entry("api".into())
+        .or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()))
+        .as_mapping_mut()
+    else {
+        return;
+    };
+    if let Some(serde_yaml::Value::Mapping(llm_keys)) = api.remove("llmKeys") {
+        for (provider, key) in llm_keys {
+            if let Some(provider) = provider.as_str() {
+                keys.push((provider.to_string(), key));
+            }
+        }
+    }
+    if keys.is_empty() {
+        return;
+    }
+
+    let Some(providers) = api
+        .entry("llmProviders".into())
+        .or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()))
+        .as_mapping_mut()
+    else {
+        return;
+    };
+    for (provider, key) in keys {
+        if key.as_str().is_none_or(str::is_empty) {
+            continue;
+        }
+        let entry = providers
+            .entry(provider.as_str().into())
+            .or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
+        // Never clobber a key that was already set in the new location
+        if let Some(entry) = entry.as_mapping_mut() {
+            if !entry.contains_key("apiKey") {
+                debug!("Migrating {} API key to api.llmProviders", provider);
+                entry.insert("apiKey".into(), key);
+            }
+        }
+    }
+}
+
+/// Apply pending migrations to a parsed config, based on its `version` field.
+/// Returns true if anything changed. Configs without a readable version are left alone,
+/// since their shape can't be known.
+pub fn migrate_config(config: &mut serde_yaml::Value) -> bool {
+    let Some(root) = config.as_mapping_mut() else {
+        return false;
+    };
+    let Some(stored) = root
+        .get("version")
+        .and_then(|v| v.as_str())
+        .and_then(|v| semver::Version::parse(v).ok())
+    else {
+        debug!("Config has no recognizable version, skipping migrations");
+        return false;
+    };
+
+    let mut migrated = false;
+    for (version, migration) in CONFIG_MIGRATIONS {
+        let Ok(target) = semver::Version::parse(version) else {
+            continue;
+        };
+        if stored < target {
+            info!("Migrating config from {} to {}", stored, target);
+            migration(root);
+            root.insert(
+                "version".into(),
+                serde_yaml::Value::String(version.to_string()),
+            );
+            migrated = true;
+        }
+    }
+    migrated
+}
+
+/// Migrate raw config.yaml contents in memory. The file itself is rewritten at startup by
+/// `keychain::migrate_plaintext_secrets`, so a migrated legacy API key never lands in it
+/// as plaintext. Returns the contents to parse; on any problem the original text is used
+/// unchanged.
+fn migrate_config_file(contents: String) -> String {
+    let Ok(mut value) = serde_yaml::from_str::<serde_yaml::Value>(&contents) else {
+        return contents;
+    };
+    if !migrate_config(&mut value) {
+        return contents;
+    }
+    match serde_yaml::to_string(&value) {
+        Ok(migrated) => migrated,
+        Err(e) => {
+            warn!("Failed to serialize migrated config: {}", e);
+            contents
+        }
+    }
+}
+
+/// Add keys from `defaults` that are missing in `config`, recursing into nested mappings.
+/// Existing values are never replaced, even when their type differs from the default, and
+/// null defaults are skipped since they add nothing over a missing key.
+/// Returns true if anything was added.
+fn fill_missing_defaults(config: &mut serde_yaml::Mapping, defaults: &serde_yaml::Mapping) -> bool {
+    let mut added = false;
+    for (key, default) in defaults {
+        match config.get_mut(key) {
+            Some(serde_yaml::Value::Mapping(existing)) => {
+                if let serde_yaml::Value::Mapping(default) = default {
+                    added |= fill_missing_defaults(existing, default);
+                }
+            }
+            Some(_) => {}
+            None if default.is_null() => {}
+            None => {
+                debug!("Adding missing config field {:?}", key);
+                config.insert(key.clone(), default.clone());
+                added = true;
+            }
+        }
+    }
+    added
+}
+
+/// Write defaults for fields missing from an existing config.yaml, e.g. settings added
+/// since it was created, so they survive later edits of the raw file. User values and key
+/// order are kept; new keys are appended. The file is only rewritten when something was
+/// added, and the result says whether it was.
+pub fn fill_missing_config_defaults() -> Result<bool, Box<dyn std::error::Error>> {
+    let config_path = get_global_config_dir()?.join("config.yaml");
+    let contents = fs::read_to_string(&config_path)?;
+    let mut value = serde_yaml::from_str::<serde_yaml::Value>(&contents)?;
+    let Some(root) = value.as_mapping_mut() else {
+        warn!("Config is not a mapping, not adding missing defaults");
+        return Ok(false);
+    };
+
+    let mut defaults = serde_yaml::to_value(GlobalConfig::default())?;
+    let Some(defaults) = defaults.as_mapping_mut() else {
+        return Ok(false);
+    };
+    // A missing version means the config's shape is unknown, which migrations rely on
+    defaults.remove("version");
+
+    if !fill_missing_defaults(root, defaults) {
+        return Ok(false);
+    }
+    write_global_config_yaml(&serde_yaml::to_string(&value)?)?;
+    Ok(true)
+}
+
+/// Serialize and write `config`, moving a plaintext API key into the keychain first.
+/// If no keychain is available the key is written as-is, as it was before.
+pub fn write_global_config(config: &GlobalConfig) -> Result<(), String> {
+    let mut config = config.clone();
+    if let Err(e) = crate::keychain::protect_secrets(&mut config) {
+        warn!("Storing API key in config.yaml: {}", e);
+    }
+    let yaml = serde_yaml::to_string(&config).map_err(|e| e.to_string())?;
+    write_global_config_yaml(&yaml).map_err(|e| e.to_string())
+}
+
+fn env_override<T: std::str::FromStr>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    name: &str,
+) -> Option<T> {
+    let raw = lookup(name).filter(|v| !v.trim().is_empty())?;
+    match raw.trim().parse::<T>() {
+        Ok(value) => {
+            debug!("Config override from {}", name);
+            Some(value)
+        }
+        Err(_) => {
+            warn!("Ignoring {}: could not parse '{}'", name, raw);
+            None
+        }
+    }
+}
+
+fn env_override_bool(lookup: &impl Fn(&str) -> Option<String>, name: &str) -> Option<bool> {
+    match lookup(name)?.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        other => {
+            warn!("Ignoring {}: expected a boolean, got '{}'", name, other);
+            None
+        }
+    }
+}
+
+fn env_override_port(lookup: &impl Fn(&str) -> Option<String>, name: &str) -> Option<u16> {
+    env_override::<u16>(lookup, name).filter(|port| {
+        if *port == 0 {
+            warn!("Ignoring {}: port must be between 1 and 65535", name);
+        }
+        *port != 0
+    })
+}
+
+/// Apply BB_* environment overrides, e.g. for CI or reproducing a bug report without
+/// editing config.yaml. Precedence is env > config.yaml > built-in default. Unparseable
+/// values are logged and ignored. `lookup` reads a variable, normally from the process
+/// environment.
+fn apply_env_overrides(config: &mut GlobalConfig, lookup: impl Fn(&str) -> Option<String>) {
+    let lookup = &lookup;
+    if let Some(hostname) = env_override::<String>(lookup, "BB_API_HOSTNAME") {
+        config.api.hostname = hostname;
+    }
+    if let Some(port) = env_override_port(lookup, "BB_API_PORT") {
+        config.api.port = port;
+    }
+    if let Some(use_tls) = env_override_bool(lookup, "BB_API_USE_TLS") {
+        config.api.tls.use_tls = use_tls;
+    }
+    if let Some(hostname) = env_override::<String>(lookup, "BB_BUI_HOSTNAME") {
+        config.bui.hostname = hostname;
+    }
+    if let Some(port) = env_override_port(lookup, "BB_BUI_PORT") {
+        config.bui.port = port;
+    }
+    if let Some(use_tls) = env_override_bool(lookup, "BB_BUI_USE_TLS") {
+        config.bui.tls.use_tls = use_tls;
+    }
+    if let Some(debug_mode) = env_override_bool(lookup, "BB_DUI_DEBUG_MODE") {
+        config.dui.debug_mode = debug_mode;
+    }
+}
+
+/// Effective configuration: config.yaml (or defaults) with environment overrides applied.
+/// Use `read_global_config_file` when the result will be written back, so overrides
+/// don't get persisted.
 pub fn read_global_config() -> Result<GlobalConfig, Box<dyn std::error::Error>> {
+    let mut config = read_global_config_file()?;
+    apply_env_overrides(&mut config, |name| std::env::var(name).ok());
+    Ok(config)
+}
+
+/// Configuration exactly as stored in config.yaml (or defaults if it doesn't exist).
+/// Semantic problems found by `GlobalConfig::validate` are logged rather than returned, so a
+/// bad field doesn't stop the app from starting or keep the user from fixing it; the
+/// `validate_global_config` command reports them to the UI.
+pub fn read_global_config_file() -> Result<GlobalConfig, Box<dyn std::error::Error>> {
     let config_dir = get_global_config_dir()?;
     let config_path = config_dir.join("config.yaml");
 
@@ -416,12 +1075,38 @@ pub fn read_global_config() -> Result<GlobalConfig, Box<dyn std::error::Error>>
         })?;
     }
 
-    match fs::read_to_string(&config_path) {
-        Ok(contents) => match serde_yaml::from_str(&contents) {
-            Ok(config) => Ok(config),
+    match fs::read_to_string(&config_path).map(migrate_config_file) {
+        Ok(contents) => match serde_yaml::from_str::<GlobalConfig>(&contents) {
+            Ok(mut config) => {
+                let errors = config.validate();
+                if !errors.is_empty() {
+                    warn!(
+                        "{}",
+                        ConfigValidationError {
+                            path: config_path,
+                            errors,
+                        }
+                    );
+                }
+                crate::keychain::resolve_secrets(&mut config);
+                Ok(config)
+            }
             Err(e) => {
                 error!("Failed to parse config YAML: {}", e);
-                Err(Box::new(e))
+                // serde stops at the first problem; report it with its position
+                let field = match e.location() {
+                    Some(location) => {
+                        format!("line {}, column {}", location.line(), location.column())
+                    }
+                    None => "config".to_string(),
+                };
+                Err(Box::new(ConfigValidationError {
+                    path: config_path,
+                    errors: vec![ConfigFieldError {
+                        field,
+                        problem: e.to_string(),
+                    }],
+                }))
             }
         },
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
@@ -447,16 +1132,45 @@ pub fn get_dui_debug_mode() -> bool {
     }
 }
 
+pub fn get_window_sizing_mode() -> WindowSizingMode {
+    read_global_config()
+        .map(|config| config.dui.window_sizing_mode)
+        .unwrap_or_default()
+}
+
 #[tauri::command]
 pub async fn set_dui_debug_mode(debug_mode: bool) -> Result<(), String> {
-    let config_dir = get_global_config_dir().map_err(|e| e.to_string())?;
-    let config_path = config_dir.join("config.yaml");
-
-    let mut config = read_global_config().map_err(|e| e.to_string())?;
+    let mut config = read_global_config_file().map_err(|e| e.to_string())?;
     config.dui.debug_mode = debug_mode;
 
-    let yaml = serde_yaml::to_string(&config).map_err(|e| e.to_string())?;
-    fs::write(config_path, yaml).map_err(|e| e.to_string())?;
+    write_global_config(&config)?;
+
+    Ok(())
+}
+
+/// Custom directory for the bb binaries from `dui.installDirectory`, if configured
+pub fn get_install_directory() -> Option<PathBuf> {
+    read_global_config()
+        .ok()
+        .and_then(|config| config.dui.install_directory)
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+#[tauri::command]
+pub fn get_update_channel() -> UpdateChannel {
+    match read_global_config() {
+        Ok(config) => config.dui.update_channel,
+        Err(_) => UpdateChannel::default(),
+    }
+}
+
+#[tauri::command]
+pub async fn set_update_channel(channel: UpdateChannel) -> Result<(), String> {
+    let mut config = read_global_config_file().map_err(|e| e.to_string())?;
+    config.dui.update_channel = channel;
+
+    write_global_config(&config)?;
 
     Ok(())
 }
@@ -482,3 +1196,195 @@ pub async fn get_bui_config() -> Result<BuiConfig, String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply overrides to `config` as if exactly the given BB_* variables were set
+    fn with_env_overrides(vars: &[(&str, &str)], mut config: GlobalConfig) -> GlobalConfig {
+        let env: BTreeMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        apply_env_overrides(&mut config, |name| env.get(name).cloned());
+        config
+    }
+
+    /// Defaults with the values a user might have in config.yaml
+    fn file_config() -> GlobalConfig {
+        let mut config = GlobalConfig::default();
+        config.api.hostname = "api.local".to_string();
+        config.api.port = 4000;
+        config.api.tls.use_tls = false;
+        config.bui.hostname = "bui.local".to_string();
+        config.bui.port = 9000;
+        config.bui.tls.use_tls = false;
+        config.dui.debug_mode = false;
+        config
+    }
+
+    #[test]
+    fn env_overrides_win_over_config_file() {
+        let config = with_env_overrides(
+            &[
+                ("BB_API_HOSTNAME", "127.0.0.1"),
+                ("BB_API_PORT", "5000"),
+                ("BB_API_USE_TLS", "true"),
+                ("BB_BUI_HOSTNAME", "::1"),
+                ("BB_BUI_PORT", " 9100 "),
+                ("BB_BUI_USE_TLS", "yes"),
+                ("BB_DUI_DEBUG_MODE", "1"),
+            ],
+            file_config(),
+        );
+
+        assert_eq!(config.api.hostname, "127.0.0.1");
+        assert_eq!(config.api.port, 5000);
+        assert!(config.api.tls.use_tls);
+        assert_eq!(config.bui.hostname, "::1");
+        assert_eq!(config.bui.port, 9100);
+        assert!(config.bui.tls.use_tls);
+        assert!(config.dui.debug_mode);
+    }
+
+    #[test]
+    fn config_file_wins_without_env_overrides() {
+        let config = with_env_overrides(&[], file_config());
+
+        assert_eq!(config.api.hostname, "api.local");
+        assert_eq!(config.api.port, 4000);
+        assert_eq!(config.bui.port, 9000);
+        assert!(!config.dui.debug_mode);
+    }
+
+    #[test]
+    fn env_overrides_win_over_defaults() {
+        let config = with_env_overrides(
+            &[("BB_API_PORT", "5000"), ("BB_DUI_DEBUG_MODE", "true")],
+            GlobalConfig::default(),
+        );
+        let defaults = GlobalConfig::default();
+
+        assert_eq!(config.api.port, 5000);
+        assert!(config.dui.debug_mode);
+        assert_eq!(config.bui.port, defaults.bui.port);
+    }
+
+    #[test]
+    fn invalid_env_overrides_are_ignored() {
+        let config = with_env_overrides(
+            &[
+                ("BB_API_PORT", "not-a-port"),
+                ("BB_BUI_PORT", "0"),
+                ("BB_API_USE_TLS", "maybe"),
+                ("BB_BUI_USE_TLS", ""),
+                ("BB_DUI_DEBUG_MODE", "on?"),
+                ("BB_API_HOSTNAME", "   "),
+            ],
+            file_config(),
+        );
+
+        assert_eq!(config.api.port, 4000);
+        assert_eq!(config.bui.port, 9000);
+        assert!(!config.api.tls.use_tls);
+        assert!(!config.bui.tls.use_tls);
+        assert!(!config.dui.debug_mode);
+        assert_eq!(config.api.hostname, "api.local");
+    }
+
+    #[test]
+    fn out_of_range_port_override_is_ignored() {
+        let config = with_env_overrides(&[("BB_API_PORT", "70000")], file_config());
+
+        assert_eq!(config.api.port, 4000);
+    }
+
+    #[test]
+    fn shared_log_levels_are_valid() {
+        for level in ["debug", "debug0", "debug1", "debug2", "warn"] {
+            let mut config = GlobalConfig::default();
+            config.api.log_level = level.to_string();
+            config.bui.log_level = level.to_uppercase();
+
+            assert!(config.validate().is_empty(), "{} was rejected", level);
+        }
+
+        let mut config = GlobalConfig::default();
+        config.api.log_level = "verbose".to_string();
+        let errors = config.validate();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "api.logLevel");
+    }
+
+    #[test]
+    fn failed_write_leaves_previous_config_intact() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_path = dir.path().join("config.yaml");
+        fs::write(&config_path, "version: 2.2.0\napi:\n  port: 3162\n").unwrap();
+
+        // Stop part way through the new contents, as a crash or full disk would
+        let result = replace_file_atomically(&config_path, |file| {
+            file.write_all(b"version: 2.2.0\napi:\n  po")?;
+            Err(std::io::Error::other("simulated crash"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "version: 2.2.0\napi:\n  port: 3162\n"
+        );
+        let leftovers: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name != "config.yaml")
+            .collect();
+        assert!(
+            leftovers.is_empty(),
+            "temp files left behind: {:?}",
+            leftovers
+        );
+    }
+
+    #[test]
+    fn concurrent_writes_never_leave_a_partial_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_path = dir.path().join("config.yaml");
+        let contents: Vec<String> = (0..8)
+            .map(|i| format!("api:\n  port: {}\n{}", 4000 + i, "#".repeat(64 * 1024)))
+            .collect();
+
+        std::thread::scope(|scope| {
+            for content in &contents {
+                let config_path = &config_path;
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        replace_file_atomically(config_path, |file| {
+                            file.write_all(content.as_bytes())
+                        })
+                        .unwrap();
+                    }
+                });
+            }
+        });
+
+        let written = fs::read_to_string(&config_path).unwrap();
+        assert!(contents.contains(&written), "config.yaml was left partial");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn completed_write_replaces_config() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_path = dir.path().join("config.yaml");
+        fs::write(&config_path, "api:\n  port: 3162\n").unwrap();
+
+        replace_file_atomically(&config_path, |file| file.write_all(b"api:\n  port: 4000\n"))
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "api:\n  port: 4000\n"
+        );
+    }
+}
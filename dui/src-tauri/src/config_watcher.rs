@@ -0,0 +1,268 @@
+use log::{debug, error, info, warn};
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{mpsc, RwLock};
+use tokio::time::sleep;
+
+use crate::config::{get_global_config_dir, read_global_config, GlobalConfig};
+use crate::proxy::HttpProxy;
+
+// Editors and write_global_config_yaml touch the file several times per save
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(500);
+
+// Keys applied to the running app as soon as they change; maps match on their entries too
+const LIVE_KEYS: &[&str] = &[
+    "dui.debugMode",
+    "dui.proxyDebugMode",
+    "dui.proxyExtraHeaders",
+    "dui.proxyTarget",
+    "proxy.target",
+    "proxy.fallbackTargets",
+];
+// Sections read by the API and BUI processes at startup
+const SERVICE_SECTIONS: &[&str] = &["api", "bui"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChangedEvent {
+    /// Every key that changed, in dotted camelCase form (e.g. `api.logLevel`)
+    pub changed: Vec<String>,
+    /// Changed keys that were applied without a restart
+    pub applied: Vec<String>,
+    /// Changed keys that only take effect once the API/BUI is restarted
+    pub requires_restart: Vec<String>,
+}
+
+/// Flatten `value` into dotted key paths; arrays are compared as a whole
+pub(crate) fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&path, value, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+fn changed_keys(old: &GlobalConfig, new: &GlobalConfig) -> Vec<String> {
+    let (mut old_keys, mut new_keys) = (BTreeMap::new(), BTreeMap::new());
+    flatten(
+        "",
+        &serde_json::to_value(old).unwrap_or_default(),
+        &mut old_keys,
+    );
+    flatten(
+        "",
+        &serde_json::to_value(new).unwrap_or_default(),
+        &mut new_keys,
+    );
+
+    let mut changed: Vec<String> = old_keys
+        .iter()
+        .filter(|(key, value)| new_keys.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect();
+    changed.extend(
+        new_keys
+            .keys()
+            .filter(|key| !old_keys.contains_key(*key))
+            .cloned(),
+    );
+    changed.sort();
+    changed
+}
+
+fn is_live_key(key: &str) -> bool {
+    LIVE_KEYS.iter().any(|live| {
+        key == *live
+            || key
+                .strip_prefix(live)
+                .is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+fn requires_service_restart(key: &str) -> bool {
+    let section = key.split('.').next().unwrap_or_default();
+    // Top-level keys (e.g. myPersonsName, defaultModels) are read by the API as well
+    SERVICE_SECTIONS.contains(&section) || !key.contains('.')
+}
+
+/// Push live-safe values into the running proxy. DUI debug mode needs no action since
+/// `get_dui_debug_mode` reads the config on every call.
+async fn apply_live_changes(app: &AppHandle, config: &GlobalConfig, changed: &[String]) {
+    let Some(state) = app.try_state::<Arc<RwLock<HttpProxy>>>() else {
+        warn!("Config watcher: proxy state not available");
+        return;
+    };
+    let proxy = state.read().await;
+
+    if changed.iter().any(|key| key == "dui.proxyDebugMode") {
+        let debug_mode = HttpProxy::configured_debug_mode(&config.dui);
+        *proxy.debug_mode.write().await = debug_mode;
+        info!("Config watcher: proxy debug mode set to {}", debug_mode);
+    }
+
+    if changed
+        .iter()
+        .any(|key| key.starts_with("dui.proxyExtraHeaders"))
+    {
+        match HttpProxy::parse_extra_headers(&config.dui.proxy_extra_headers) {
+            Ok(headers) => {
+                *proxy.extra_headers.write().await = headers;
+                info!("Config watcher: proxy extra headers updated");
+            }
+            Err(e) => warn!("Config watcher: ignoring dui.proxyExtraHeaders: {}", e),
+        }
+    }
+
+    if changed
+        .iter()
+        .any(|key| key == "dui.proxyTarget" || key == "proxy.target")
+    {
+        let target = HttpProxy::configured_target(&config.proxy, &config.dui);
+        *proxy.target_url.write().await = target.clone();
+        proxy.reset_target_health();
+        info!("Config watcher: proxy target updated to {}", target);
+    }
+
+    if changed.iter().any(|key| key == "proxy.fallbackTargets") {
+        let (valid, invalid): (Vec<String>, Vec<String>) = config
+            .proxy
+            .fallback_targets
+            .iter()
+            .cloned()
+            .partition(|target| HttpProxy::validate_target(target).is_ok());
+        if !invalid.is_empty() {
+            warn!(
+                "Config watcher: ignoring invalid fallback targets {:?} (must be HTTPS URLs)",
+                invalid
+            );
+        }
+        *proxy.fallback_targets.write().await = valid.clone();
+        proxy.reset_target_health();
+        info!(
+            "Config watcher: proxy fallback targets updated to {:?}",
+            valid
+        );
+    }
+}
+
+async fn reload(app: &AppHandle, current: &mut GlobalConfig) {
+    let config = match read_global_config() {
+        Ok(config) => config,
+        Err(e) => {
+            // Likely a half-written or hand-edited file; keep running on the last good config
+            warn!("Config watcher: failed to reload config: {}", e);
+            return;
+        }
+    };
+
+    let changed = changed_keys(current, &config);
+    if changed.is_empty() {
+        debug!("Config watcher: config.yaml changed on disk but no values differ");
+        return;
+    }
+    info!("Config watcher: changed keys: {:?}", changed);
+
+    apply_live_changes(app, &config, &changed).await;
+    let event = ConfigChangedEvent {
+        applied: changed
+            .iter()
+            .filter(|key| is_live_key(key))
+            .cloned()
+            .collect(),
+        requires_restart: changed
+            .iter()
+            .filter(|key| requires_service_restart(key))
+            .cloned()
+            .collect(),
+        changed,
+    };
+    *current = config;
+
+    if let Err(e) = app.emit("config-changed", event) {
+        warn!("Config watcher: failed to emit config-changed event: {}", e);
+    }
+}
+
+/// Watch config.yaml and apply changes made outside the app (or by the BUI/CLI) without
+/// a restart where possible, emitting `config-changed` with what changed.
+pub fn start_config_watcher(app: AppHandle) {
+    let config_dir = match get_global_config_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            error!("Config watcher: failed to get config directory: {}", e);
+            return;
+        }
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher =
+        match notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => {
+                if event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name().is_some_and(|name| name == "config.yaml"))
+                {
+                    let _ = tx.send(());
+                }
+            }
+            Err(e) => warn!("Config watcher: watch error: {}", e),
+        }) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                error!("Config watcher: failed to create watcher: {}", e);
+                return;
+            }
+        };
+    // Watch the directory rather than the file: config writes replace the file via rename
+    if let Err(e) = watcher.watch(&config_dir, RecursiveMode::NonRecursive) {
+        error!("Config watcher: failed to watch {:?}: {}", config_dir, e);
+        return;
+    }
+    info!(
+        "Watching {:?} for config changes",
+        config_dir.join("config.yaml")
+    );
+
+    tauri::async_runtime::spawn(async move {
+        // Keep the watcher alive for as long as the task runs
+        let _watcher = watcher;
+        let mut current = match read_global_config() {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Config watcher: failed to read initial config: {}", e);
+                GlobalConfig::default()
+            }
+        };
+
+        while rx.recv().await.is_some() {
+            // Wait until writes settle before reloading
+            loop {
+                tokio::select! {
+                    more = rx.recv() => {
+                        if more.is_none() {
+                            return;
+                        }
+                    }
+                    _ = sleep(DEBOUNCE_DURATION) => break,
+                }
+            }
+            reload(&app, &mut current).await;
+        }
+    });
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+use std::fmt;
+
+/// Error returned by commands as `{ "type": "PortInUse", "message": "..." }`, so the UI can
+/// branch on the category while still showing the message as-is
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "message")]
+pub enum BBError {
+    /// A bb, bb-api or bb-bui executable is missing or can't be located
+    BinaryNotFound(String),
+    /// A service port is taken by another application
+    PortInUse(String),
+    PermissionDenied(String),
+    /// Downloads, GitHub requests and service health checks
+    NetworkError(String),
+    /// config.yaml can't be read, parsed or used
+    ConfigError(String),
+    /// Starting, stopping or finding a service process failed
+    ProcessError(String),
+    /// Anything not categorized yet, e.g. errors from helpers that still return strings
+    Other(String),
+}
+
+impl BBError {
+    pub fn message(&self) -> &str {
+        match self {
+            BBError::BinaryNotFound(message)
+            | BBError::PortInUse(message)
+            | BBError::PermissionDenied(message)
+            | BBError::NetworkError(message)
+            | BBError::ConfigError(message)
+            | BBError::ProcessError(message)
+            | BBError::Other(message) => message,
+        }
+    }
+
+    /// Append `detail` to the message, keeping the category
+    pub fn with_detail(mut self, detail: &str) -> Self {
+        let (BBError::BinaryNotFound(message)
+        | BBError::PortInUse(message)
+        | BBError::PermissionDenied(message)
+        | BBError::NetworkError(message)
+        | BBError::ConfigError(message)
+        | BBError::ProcessError(message)
+        | BBError::Other(message)) = &mut self;
+        message.push(' ');
+        message.push_str(detail);
+        self
+    }
+
+    /// Categorize an I/O error by its kind, prefixing the message with `context`
+    pub fn from_io(context: &str, e: &std::io::Error) -> Self {
+        let message = format!("{}: {}", context, e);
+        match e.kind() {
+            std::io::ErrorKind::PermissionDenied => BBError::PermissionDenied(message),
+            std::io::ErrorKind::AddrInUse => BBError::PortInUse(message),
+            _ => BBError::ProcessError(message),
+        }
+    }
+
+    /// Like `from_io`, for errors from spawning a binary, where NotFound means the binary is
+    /// missing
+    pub fn from_spawn_io(context: &str, e: &std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => BBError::BinaryNotFound(format!("{}: {}", context, e)),
+            _ => BBError::from_io(context, e),
+        }
+    }
+}
+
+impl fmt::Display for BBError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for BBError {}
+
+impl From<String> for BBError {
+    fn from(message: String) -> Self {
+        BBError::Other(message)
+    }
+}
+
+// Lets functions that still return `Result<_, String>` use `?` on these errors
+impl From<BBError> for String {
+    fn from(e: BBError) -> Self {
+        e.to_string()
+    }
+}
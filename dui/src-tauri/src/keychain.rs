@@ -0,0 +1,339 @@
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+#[cfg(not(target_os = "windows"))]
+use std::io::Write;
+#[cfg(not(target_os = "windows"))]
+use std::process::{Command, Output, Stdio};
+use std::sync::Mutex;
+
+use crate::config::{get_global_config_dir, write_global_config_yaml, GlobalConfig, APP_NAME};
+
+/// Keychain account holding the Anthropic API key
+pub const ANTHROPIC_API_KEY_ACCOUNT: &str = "anthropic-api-key";
+/// Environment variable bb-api falls back to when config.yaml only holds a reference
+pub const ANTHROPIC_API_KEY_ENV: &str = "ANTHROPIC_API_KEY";
+// Config values with this prefix refer to a keychain entry rather than holding the secret
+const KEYCHAIN_REF_PREFIX: &str = "keychain:";
+
+// Config is re-read constantly; avoid spawning a keychain helper (and on macOS possibly
+// an access prompt) every time. Misses are cached as None until the next store_secret.
+static SECRET_CACHE: Lazy<Mutex<HashMap<String, Option<String>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Placeholder stored in config.yaml in place of the secret for `account`
+pub fn keychain_ref(account: &str) -> String {
+    format!("{}{}", KEYCHAIN_REF_PREFIX, account)
+}
+
+/// The keychain account referenced by a config value, if it is a placeholder
+pub fn keychain_account(value: &str) -> Option<&str> {
+    value.strip_prefix(KEYCHAIN_REF_PREFIX)
+}
+
+/// Run `command` with `input` on stdin so secrets never appear in the process list
+#[cfg(not(target_os = "windows"))]
+fn run_with_stdin(mut command: Command, input: &str) -> Result<Output, String> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run keychain helper: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("Failed to pass secret to keychain helper: {}", e))?;
+    }
+    child
+        .wait_with_output()
+        .map_err(|e| format!("Keychain helper failed: {}", e))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn check_output(output: Output, action: &str) -> Result<String, String> {
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(format!(
+            "Failed to {}: {}",
+            action,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn credential_target(account: &str) -> Vec<u16> {
+    format!("{}/{}", APP_NAME, account)
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect()
+}
+
+/// Store `secret` in the platform keychain: the login Keychain on macOS, the Secret Service
+/// (via `secret-tool`) on Linux, and Credential Manager on Windows
+pub fn store_secret(account: &str, secret: &str) -> Result<(), String> {
+    platform_store_secret(account, secret)?;
+    SECRET_CACHE
+        .lock()
+        .unwrap()
+        .insert(account.to_string(), Some(secret.to_string()));
+    Ok(())
+}
+
+fn platform_store_secret(account: &str, secret: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        // Interactive mode reads commands from stdin a line at a time, keeping the secret out
+        // of argv; -U updates an existing item
+        if secret.contains(['\n', '\r']) {
+            return Err("Secrets containing line breaks can't be stored in Keychain".to_string());
+        }
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let script = format!(
+            "add-generic-password -U -s {} -a {} -w {}\n",
+            quote(APP_NAME),
+            quote(account),
+            quote(secret)
+        );
+        let mut command = Command::new("security");
+        command.arg("-i");
+        check_output(
+            run_with_stdin(command, &script)?,
+            "store secret in Keychain",
+        )?;
+        // security -i exits 0 even when a command in the script failed, so read it back
+        if platform_load_secret(account)?.as_deref() != Some(secret) {
+            return Err("Failed to store secret in Keychain".to_string());
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let mut command = Command::new("secret-tool");
+        command.args([
+            "store",
+            "--label=Beyond Better",
+            "service",
+            APP_NAME,
+            "account",
+            account,
+        ]);
+        check_output(
+            run_with_stdin(command, secret)?,
+            "store secret in Secret Service",
+        )
+        .map(|_| ())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::Security::Credentials::{
+            CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+        };
+
+        let mut target = credential_target(account);
+        let mut user: Vec<u16> = account.encode_utf16().chain(std::iter::once(0)).collect();
+        // UTF-16 like other generic credentials, so the secret reads correctly elsewhere
+        let mut blob: Vec<u8> = secret.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let credential = CREDENTIALW {
+            Flags: 0,
+            Type: CRED_TYPE_GENERIC,
+            TargetName: target.as_mut_ptr(),
+            Comment: std::ptr::null_mut(),
+            LastWritten: unsafe { std::mem::zeroed() },
+            CredentialBlobSize: blob.len() as u32,
+            CredentialBlob: blob.as_mut_ptr(),
+            Persist: CRED_PERSIST_LOCAL_MACHINE,
+            AttributeCount: 0,
+            Attributes: std::ptr::null_mut(),
+            TargetAlias: std::ptr::null_mut(),
+            UserName: user.as_mut_ptr(),
+        };
+        if unsafe { CredWriteW(&credential, 0) } == 0 {
+            return Err(format!(
+                "Failed to store secret in Credential Manager: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Look up the secret for `account`, returning None when no entry exists
+pub fn load_secret(account: &str) -> Result<Option<String>, String> {
+    if let Some(secret) = SECRET_CACHE.lock().unwrap().get(account) {
+        return Ok(secret.clone());
+    }
+    let secret = platform_load_secret(account)?;
+    SECRET_CACHE
+        .lock()
+        .unwrap()
+        .insert(account.to_string(), secret.clone());
+    Ok(secret)
+}
+
+fn platform_load_secret(account: &str) -> Result<Option<String>, String> {
+    #[cfg(target_os = "macos")]
+    {
+        let output = Command::new("security")
+            .args(["find-generic-password", "-s", APP_NAME, "-a", account, "-w"])
+            .output()
+            .map_err(|e| format!("Failed to run security: {}", e))?;
+        // Exit code 44: item not found
+        if output.status.code() == Some(44) {
+            return Ok(None);
+        }
+        check_output(output, "read secret from Keychain").map(Some)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let output = Command::new("secret-tool")
+            .args(["lookup", "service", APP_NAME, "account", account])
+            .output()
+            .map_err(|e| format!("Failed to run secret-tool: {}", e))?;
+        // secret-tool exits non-zero with no output when nothing matches
+        if !output.status.success() && output.stderr.is_empty() {
+            return Ok(None);
+        }
+        check_output(output, "read secret from Secret Service").map(Some)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::Foundation::ERROR_NOT_FOUND;
+        use windows_sys::Win32::Security::Credentials::{
+            CredFree, CredReadW, CREDENTIALW, CRED_TYPE_GENERIC,
+        };
+
+        let target = credential_target(account);
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        if unsafe { CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) } == 0 {
+            let e = std::io::Error::last_os_error();
+            if e.raw_os_error() == Some(ERROR_NOT_FOUND as i32) {
+                return Ok(None);
+            }
+            return Err(format!(
+                "Failed to read secret from Credential Manager: {}",
+                e
+            ));
+        }
+        let blob = unsafe {
+            std::slice::from_raw_parts(
+                (*credential).CredentialBlob,
+                (*credential).CredentialBlobSize as usize,
+            )
+        };
+        let utf16: Vec<u16> = blob
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        let secret = String::from_utf16(&utf16);
+        unsafe { CredFree(credential as *const _) };
+        secret
+            .map(Some)
+            .map_err(|_| "Credential Manager entry is not valid UTF-16".to_string())
+    }
+}
+
+/// Replace keychain placeholders in `config` with the real secrets. A placeholder that
+/// can't be resolved is left in place so writing the config back doesn't lose it.
+pub fn resolve_secrets(config: &mut GlobalConfig) {
+    let Some(provider) = config.api.llm_providers.anthropic.as_mut() else {
+        return;
+    };
+    let Some(account) = provider.api_key.as_deref().and_then(keychain_account) else {
+        return;
+    };
+    match load_secret(account) {
+        Ok(Some(secret)) => provider.api_key = Some(secret),
+        Ok(None) => warn!("No keychain entry for {}", account),
+        Err(e) => warn!("Failed to read {} from keychain: {}", account, e),
+    }
+}
+
+/// Move a plaintext API key in `config` into the keychain, leaving a placeholder.
+/// Returns true if `config` was changed.
+pub fn protect_secrets(config: &mut GlobalConfig) -> Result<bool, String> {
+    let Some(provider) = config.api.llm_providers.anthropic.as_mut() else {
+        return Ok(false);
+    };
+    let Some(api_key) = provider.api_key.as_ref() else {
+        return Ok(false);
+    };
+    if api_key.is_empty() || keychain_account(api_key).is_some() {
+        return Ok(false);
+    }
+    // Configs read for editing come back with the key resolved, so most writes pass the
+    // stored key again; only run the keychain helper when it actually changed
+    let unchanged = SECRET_CACHE
+        .lock()
+        .unwrap()
+        .get(ANTHROPIC_API_KEY_ACCOUNT)
+        .is_some_and(|stored| stored.as_deref() == Some(api_key.as_str()));
+    if !unchanged {
+        store_secret(ANTHROPIC_API_KEY_ACCOUNT, api_key)?;
+    }
+    provider.api_key = Some(keychain_ref(ANTHROPIC_API_KEY_ACCOUNT));
+    Ok(true)
+}
+
+/// One-time migration of a plaintext Anthropic API key in config.yaml into the keychain.
+/// Pending config version migrations are applied and saved along with it, since they can
+/// move a legacy key into place. Works on the raw YAML so keys the app doesn't model are
+/// preserved.
+pub fn migrate_plaintext_secrets() -> Result<(), String> {
+    let config_path = get_global_config_dir()
+        .map_err(|e| e.to_string())?
+        .join("config.yaml");
+    let Ok(contents) = std::fs::read_to_string(&config_path) else {
+        return Ok(());
+    };
+    let mut yaml: serde_yaml::Value =
+        serde_yaml::from_str(&contents).map_err(|e| format!("Failed to parse config: {}", e))?;
+
+    let migrated = crate::config::migrate_config(&mut yaml);
+    let moved = move_api_key_to_keychain(&mut yaml);
+    if !migrated && !moved {
+        return Ok(());
+    }
+
+    let yaml =
+        serde_yaml::to_string(&yaml).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    write_global_config_yaml(&yaml).map_err(|e| format!("Failed to write config file: {}", e))?;
+    if moved {
+        info!("Moved Anthropic API key from config.yaml into the keychain");
+    }
+    Ok(())
+}
+
+/// Store a plaintext API key from raw config YAML in the keychain and replace it with a
+/// reference. Returns true if the YAML changed.
+fn move_api_key_to_keychain(yaml: &mut serde_yaml::Value) -> bool {
+    let Some(api_key) = yaml
+        .get_mut("api")
+        .and_then(|v| v.get_mut("llmProviders"))
+        .and_then(|v| v.get_mut("anthropic"))
+        .and_then(|v| v.get_mut("apiKey"))
+    else {
+        return false;
+    };
+    let Some(secret) = api_key.as_str().map(|s| s.to_string()) else {
+        return false;
+    };
+    if secret.is_empty() || keychain_account(&secret).is_some() {
+        debug!("No plaintext API key to migrate");
+        return false;
+    }
+
+    // Without a usable keychain (e.g. no secret-tool installed) the key stays where it is
+    if let Err(e) = store_secret(ANTHROPIC_API_KEY_ACCOUNT, &secret) {
+        warn!("Leaving API key in config.yaml: {}", e);
+        return false;
+    }
+    *api_key = serde_yaml::Value::String(keychain_ref(ANTHROPIC_API_KEY_ACCOUNT));
+    true
+}
@@ -19,35 +19,54 @@ pub mod api;
 pub mod bui;
 pub mod commands; // Make commands module public
 pub mod config; // Make config module public
+pub mod config_watcher;
+pub mod error;
+pub mod keychain;
 pub mod logging;
 pub mod oauth; // OAuth authentication module
 pub mod proxy;
+pub mod shutdown;
+pub mod startup_poll;
+pub mod status_monitor;
+pub mod supervisor;
 pub mod window_state;
 
 // Re-export public items
-pub use crate::api::{start_api, stop_api};
-pub use crate::bui::{start_bui, stop_bui};
+pub use crate::api::{get_api_startup_output, restart_api, start_api, stop_api};
+pub use crate::bui::{restart_bui, start_bui, stop_bui};
 pub use crate::commands::config::{
-    get_api_log_path, get_bui_log_path, get_dui_log_path, get_global_config, get_log_path,
-    get_proxy_log_path, open_log_file, set_global_config_value, test_read_config,
+    backup_global_config, get_api_log_path, get_bui_log_path, get_config_diff, get_dui_log_path,
+    get_effective_config, get_global_config, get_global_config_backups, get_log_path,
+    get_proxy_log_path, get_recent_logs, open_log_file, reload_logging_config,
+    restore_global_config, set_global_config_value, set_log_directory, set_log_level,
+    test_read_config, validate_global_config,
 };
 pub use crate::commands::proxy::{
-    get_proxy_info, set_debug_mode, set_proxy_target, start_proxy_server, stop_proxy_server,
+    benchmark_proxy, get_proxy_access_log, get_proxy_info, get_proxy_recent_errors,
+    get_proxy_stats, get_proxy_target_health, set_debug_mode, set_proxy_body_limits,
+    set_proxy_capture_bodies, set_proxy_headers, set_proxy_maintenance, set_proxy_target,
+    set_proxy_targets, start_proxy_server, stop_proxy_server,
 };
-pub use crate::commands::server_status::check_server_status;
+pub use crate::commands::server_status::{
+    check_server_status, force_stop_all, restart_unhealthy_services, verify_ports_free,
+};
+pub use crate::commands::status::{get_full_status, get_startup_status, StartupStatus};
 pub use crate::commands::upgrade::{perform_install, perform_upgrade, check_dui_update, perform_atomic_update, perform_dui_update_only};
 pub use crate::commands::version::{
-    check_version_compatibility, get_binary_version, get_version_info,
+    check_version_compatibility, check_version_compatibility_local, get_binary_version,
+    get_version_info,
 };
 pub use crate::config::{
-    get_api_config, get_bui_config, get_dui_debug_mode, read_global_config, set_dui_debug_mode,
-    ApiConfig, BuiConfig,
+    get_api_config, get_bui_config, get_dui_debug_mode, get_update_channel, read_global_config,
+    set_dui_debug_mode, set_update_channel, ApiConfig, BuiConfig,
 };
 pub use crate::window_state::{
-    apply_window_state, load_window_state, save_window_state, setup_window_state_handler,
+    apply_window_state, load_window_state, reset_window_state, save_window_state,
+    setup_window_state_handler,
 };
 pub use crate::oauth::{
-    close_oauth_window, complete_oauth_flow, get_oauth_windows, start_oauth_flow,
+    cancel_all_oauth_windows, close_oauth_window, complete_oauth_flow, get_oauth_windows,
+    start_oauth_flow,
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -96,13 +115,18 @@ fn ensure_global_config() -> Result<(), Box<dyn std::error::Error>> {
         // Create default config using the config module's default implementation
         let default_config = crate::config::GlobalConfig::default();
         let yaml = serde_yaml::to_string(&default_config)?;
-        fs::write(config_path, yaml)?;
+        crate::config::write_global_config_yaml(&yaml)?;
         info!("Created default global config");
+    } else if crate::config::fill_missing_config_defaults()? {
+        info!("Added missing fields to global config");
     }
 
     Ok(())
 }
 
+// How long startup waits for the API and BUI before showing the window anyway
+const STARTUP_SERVICES_TIMEOUT: Duration = Duration::from_secs(30);
+
 async fn start_services_if_needed() -> Result<(), String> {
     debug!("Checking API and BUI startup conditions");
 
@@ -148,12 +172,13 @@ async fn start_services_if_needed() -> Result<(), String> {
             let api_result = crate::start_api().await;
             if let Err(e) = api_result {
                 error!("Failed to start API: {}", e);
-                return Err(e);
+                return Err(e.into());
             }
             let api_result = api_result.unwrap();
             if !api_result.success {
                 let error = api_result
                     .error
+                    .map(String::from)
                     .unwrap_or_else(|| "Unknown error".to_string());
                 warn!("API start returned false: {}", error);
                 return Err("API failed to start".to_string());
@@ -166,12 +191,13 @@ async fn start_services_if_needed() -> Result<(), String> {
             let bui_result = crate::start_bui().await;
             if let Err(e) = bui_result {
                 error!("Failed to start BUI: {}", e);
-                return Err(e);
+                return Err(e.into());
             }
             let bui_result = bui_result.unwrap();
             if !bui_result.success {
                 let error = bui_result
                     .error
+                    .map(String::from)
                     .unwrap_or_else(|| "Unknown error".to_string());
                 warn!("BUI start returned false: {}", error);
                 return Err("BUI failed to start".to_string());
@@ -186,6 +212,7 @@ async fn start_services_if_needed() -> Result<(), String> {
         if !api_result.success {
             let error = api_result
                 .error
+                .map(String::from)
                 .unwrap_or_else(|| "Unknown error".to_string());
             warn!("API start returned false: {}", error);
             return Err("API failed to start".to_string());
@@ -196,6 +223,7 @@ async fn start_services_if_needed() -> Result<(), String> {
         if !bui_result.success {
             let error = bui_result
                 .error
+                .map(String::from)
                 .unwrap_or_else(|| "Unknown error".to_string());
             warn!("BUI start returned false: {}", error);
             return Err("BUI failed to start".to_string());
@@ -205,7 +233,14 @@ async fn start_services_if_needed() -> Result<(), String> {
     Ok(())
 }
 
-fn get_app_log_dir() -> Option<PathBuf> {
+pub(crate) fn get_app_log_dir() -> Option<PathBuf> {
+    // A directory chosen via set_log_directory takes precedence over the platform default
+    if let Ok(config) = config::read_global_config() {
+        if let Some(dir) = config.dui.log_directory.filter(|dir| !dir.is_empty()) {
+            return Some(PathBuf::from(dir));
+        }
+    }
+
     #[cfg(target_os = "macos")]
     {
         dirs::home_dir().map(|home| home.join("Library").join("Logs").join(config::APP_NAME))
@@ -255,6 +290,87 @@ async fn setup_windows(app: &mut tauri::App) -> Result<(), Box<dyn std::error::E
     Ok(())
 }
 
+/// Host suffixes bblink:// links may open, from `dui.linkAllowedHosts`
+fn link_allowed_hosts() -> Vec<String> {
+    match read_global_config() {
+        Ok(config) => config.dui.link_allowed_hosts,
+        Err(e) => {
+            warn!(
+                "[DOWNLOAD HANDLER] Failed to read config, using default allowlist: {}",
+                e
+            );
+            config::DuiConfig::default().link_allowed_hosts
+        }
+    }
+}
+
+/// Check that `url` is an http(s) URL whose host is, or is a subdomain of, an allowed host
+fn check_link_allowed(url: &str, allowed_hosts: &[String]) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid URL: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("scheme '{}' is not allowed", parsed.scheme()));
+    }
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| "URL has no host".to_string())?
+        .to_ascii_lowercase();
+    let allowed = allowed_hosts.iter().any(|suffix| {
+        let suffix = suffix.trim_start_matches('.').to_ascii_lowercase();
+        host == suffix || host.ends_with(&format!(".{}", suffix))
+    });
+    if allowed {
+        Ok(())
+    } else {
+        Err(format!("host '{}' is not in the allowlist", host))
+    }
+}
+
+/// Open a bblink:// URL in the system browser, or explain why it was refused
+fn open_bblink_url(actual_url: &str) -> Result<(), String> {
+    // URL-decode the string to handle any URL encoding
+    let decoded_url = match urlencoding::decode(actual_url) {
+        Ok(decoded) => decoded.to_string(),
+        Err(e) => {
+            error!("[DOWNLOAD HANDLER] Failed to decode URL: {}", e);
+            actual_url.to_string()
+        }
+    };
+
+    // Refuse other schemes before the fixups below can turn e.g. `file:///...` into
+    // something that looks like an https URL
+    if let Ok(url) = reqwest::Url::parse(&decoded_url) {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("scheme '{}' is not allowed", url.scheme()));
+        }
+    }
+
+    // Fix common protocol formatting issues and ensure proper protocol
+    let url_to_open = if decoded_url.starts_with("https://") || decoded_url.starts_with("http://") {
+        // URL already has correct protocol
+        decoded_url
+    } else if decoded_url.starts_with("https/") {
+        // Missing colon after https
+        decoded_url.replacen("https/", "https:/", 1)
+    } else if decoded_url.starts_with("http/") {
+        // Missing colon after http
+        decoded_url.replacen("http/", "http:/", 1)
+    } else {
+        // No protocol, add https://
+        format!("https://{}", decoded_url)
+    };
+
+    info!("[DOWNLOAD HANDLER] Final URL for opening: {}", url_to_open);
+    check_link_allowed(&url_to_open, &link_allowed_hosts())?;
+
+    // Open in the system browser without going through a shell, so characters like `&`
+    // in the URL can't be interpreted as commands
+    match tauri_plugin_opener::open_url(&url_to_open, None::<&str>) {
+        Ok(()) => info!("[DOWNLOAD HANDLER] Opened URL in system browser"),
+        Err(e) => error!("[DOWNLOAD HANDLER] Failed to open URL: {}", e),
+    }
+    Ok(())
+}
+
 fn handle_bblink_protocol<'a, R: tauri::Runtime>(
     _ctx: tauri::UriSchemeContext<'a, R>,
     request: tauri::http::Request<Vec<u8>>,
@@ -264,78 +380,33 @@ fn handle_bblink_protocol<'a, R: tauri::Runtime>(
     info!("[DOWNLOAD HANDLER] Received bblink request: {}", url_str);
 
     // Protocol format should be: bblink://https://example.com/file.zip
-    if let Some(actual_url) = url_str.strip_prefix("bblink://") {
-        info!(
-            "[DOWNLOAD HANDLER] Opening URL in system browser: {}",
-            actual_url
-        );
-
-        // Open in system browser using platform-specific command
-        let cmd = if cfg!(target_os = "windows") {
-            "cmd"
-        } else if cfg!(target_os = "macos") {
-            "open"
-        } else {
-            "xdg-open"
-        };
-
-        // URL-decode the string to handle any URL encoding
-        let decoded_url = match urlencoding::decode(actual_url) {
-            Ok(decoded) => decoded.to_string(),
-            Err(e) => {
-                error!("[DOWNLOAD HANDLER] Failed to decode URL: {}", e);
-                actual_url.to_string()
-            }
-        };
-
-        // Fix common protocol formatting issues and ensure proper protocol
-        let url_to_open =
-            if decoded_url.starts_with("https://") || decoded_url.starts_with("http://") {
-                // URL already has correct protocol
-                decoded_url
-            } else if decoded_url.starts_with("https/") {
-                // Missing colon after https
-                decoded_url.replacen("https/", "https:/", 1)
-            } else if decoded_url.starts_with("http/") {
-                // Missing colon after http
-                decoded_url.replacen("http/", "http:/", 1)
-            } else {
-                // No protocol, add https://
-                format!("https://{}", decoded_url)
-            };
-
-        info!("[DOWNLOAD HANDLER] Final URL for opening: {}", url_to_open);
-
-        info!(
-            "[DOWNLOAD HANDLER] Formatted URL for opening: {}",
-            url_to_open
-        );
-
-        let url_str = url_to_open.as_str();
-
-        let args = if cfg!(target_os = "windows") {
-            vec!["/c", "start", "", url_str]
-        } else {
-            vec![url_str]
-        };
-
-        match std::process::Command::new(cmd).args(args).spawn() {
-            Ok(child) => info!(
-                "[DOWNLOAD HANDLER] Successfully spawned process with ID: {:?}",
-                child.id()
-            ),
-            Err(e) => error!("[DOWNLOAD HANDLER] Failed to open URL: {}", e),
-        }
-    } else {
-        warn!("[DOWNLOAD HANDLER] Invalid bblink URL format: {}", url_str);
-    }
+    let result = match url_str.strip_prefix("bblink://") {
+        Some(actual_url) => open_bblink_url(actual_url),
+        None => Err("invalid bblink URL format".to_string()),
+    };
 
     // Return a response with a helpful message in case the page shows
     // This makes the UX better if the user somehow sees this page
+    let (title, message) = match result {
+        Ok(()) => (
+            "Link Opened in Browser",
+            "<p>The link has been opened in your default browser.</p>
+        <p>If the link is a download that doesn't start automatically, please check your browser.</p>",
+        ),
+        Err(e) => {
+            warn!("[DOWNLOAD HANDLER] Refused to open {}: {}", url_str, e);
+            (
+                "Link Not Opened",
+                "<p>This link was not opened because it does not point to a trusted website.</p>
+        <p>Trusted sites can be changed with the <code>dui.linkAllowedHosts</code> setting.</p>",
+            )
+        }
+    };
+
     let html_content = r#"<!DOCTYPE html>
 <html>
 <head>
-    <title>Link Opened in Browser</title>
+    <title>{title}</title>
     <style>
         body {
             font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, 'Open Sans', 'Helvetica Neue', sans-serif;
@@ -375,17 +446,19 @@ fn handle_bblink_protocol<'a, R: tauri::Runtime>(
     </script>
 </head>
 <body>
-    <h1>Link Opened in Browser</h1>
+    <h1>{title}</h1>
     <div class="box">
-        <p>The link has been opened in your default browser.</p>
-        <p>If the link is a download that doesn't start automatically, please check your browser.</p>
+        {message}
     </div>
     <a href="javascript:history.back()" class="button">Return to Chat Page</a>
 </body>
 </html>
 "#;
 
-    tauri::http::Response::new(html_content.as_bytes().to_vec())
+    let html_content = html_content
+        .replace("{title}", title)
+        .replace("{message}", message);
+    tauri::http::Response::new(html_content.into_bytes())
 }
 
 pub fn run() {
@@ -473,12 +546,29 @@ pub fn run() {
         warn!("Failed to ensure global config: {}", e);
     }
 
-    // Try to start services if needed
-    tauri::async_runtime::block_on(async {
+    // Older configs keep the Anthropic API key in plaintext
+    if let Err(e) = keychain::migrate_plaintext_secrets() {
+        warn!("Failed to migrate API key to keychain: {}", e);
+    }
+
+    // Try to start services if needed. Startup keeps going in the background if it takes
+    // too long, so a slow or hanging start_api doesn't hold up the window.
+    let services_startup = tauri::async_runtime::spawn(async {
         if let Err(e) = start_services_if_needed().await {
             warn!("Failed to start services: {}", e);
         }
     });
+    let services_startup_timed_out = tauri::async_runtime::block_on(async {
+        tokio::time::timeout(STARTUP_SERVICES_TIMEOUT, services_startup)
+            .await
+            .is_err()
+    });
+    if services_startup_timed_out {
+        warn!(
+            "Services did not start within {}s; continuing startup while they keep trying",
+            STARTUP_SERVICES_TIMEOUT.as_secs()
+        );
+    }
 
     // Start proxy server if needed
     debug!("Initializing proxy state");
@@ -501,46 +591,94 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             start_api,
             stop_api,
+            restart_api,
+            get_api_startup_output,
             start_bui,
             stop_bui,
+            restart_bui,
             commands::upgrade::open_external_url,
             commands::server_status::check_server_status,
+            get_full_status,
+            get_startup_status,
+            verify_ports_free,
+            restart_unhealthy_services,
+            force_stop_all,
             get_api_config,
             get_bui_config,
             get_global_config,
+            get_config_diff,
+            get_effective_config,
+            validate_global_config,
             get_binary_version,
             get_version_info,
             check_version_compatibility,
+            check_version_compatibility_local,
             perform_install,
             perform_upgrade,
             commands::upgrade::check_dui_update,
             commands::upgrade::perform_atomic_update,
             commands::upgrade::perform_dui_update_only,
+            commands::upgrade::get_install_info,
+            commands::upgrade::repair_installation,
+            commands::upgrade::cancel_install,
+            commands::upgrade::get_server_update_info,
+            commands::path_integration::check_path_integration,
+            commands::path_integration::add_to_path,
+            commands::smoke_test::run_post_install_smoke_test,
+            commands::autostart::get_launch_at_login,
+            commands::autostart::set_launch_at_login,
             set_global_config_value,
+            backup_global_config,
+            get_global_config_backups,
+            restore_global_config,
             test_read_config,
             get_log_path,
             get_api_log_path,
             get_bui_log_path,
             get_dui_log_path,
             get_proxy_log_path,
+            get_recent_logs,
+            set_log_directory,
+            set_log_level,
+            reload_logging_config,
             open_log_file,
             get_proxy_info,
+            get_proxy_stats,
+            get_proxy_access_log,
+            get_proxy_recent_errors,
+            get_proxy_target_health,
             set_proxy_target,
+            set_proxy_targets,
+            set_proxy_maintenance,
+            set_proxy_body_limits,
+            set_proxy_capture_bodies,
+            set_proxy_headers,
             set_debug_mode,
             start_proxy_server,
             stop_proxy_server,
+            benchmark_proxy,
             get_dui_debug_mode,
             set_dui_debug_mode,
+            get_update_channel,
+            set_update_channel,
             load_window_state,
             save_window_state,
+            reset_window_state,
             setup_window_state_handler,
             apply_window_state,
             start_oauth_flow,
             complete_oauth_flow,
             get_oauth_windows,
-            close_oauth_window
+            close_oauth_window,
+            cancel_all_oauth_windows,
+            status_monitor::start_status_monitoring,
+            status_monitor::stop_status_monitoring
         ])
         .manage(proxy_state)
+        .manage(StartupStatus {
+            services_timeout_secs: services_startup_timed_out
+                .then(|| STARTUP_SERVICES_TIMEOUT.as_secs()),
+        })
         //.plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
@@ -548,7 +686,89 @@ pub fn run() {
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
-        .setup(|app| tauri::async_runtime::block_on(async { setup_windows(app).await }))
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .setup(move |app| {
+            supervisor::start_service_supervisor(app.handle().clone());
+            config_watcher::start_config_watcher(app.handle().clone());
+            status_monitor::start_default_monitor(app.handle().clone());
+            tauri::async_runtime::block_on(async { setup_windows(app).await })?;
+            Ok(())
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                window_state::save_all_window_states(app_handle);
+                shutdown::shutdown_services(app_handle);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowlist() -> Vec<String> {
+        vec!["beyondbetter.app".to_string(), "github.com".to_string()]
+    }
+
+    #[test]
+    fn shell_metacharacters_in_host_are_rejected() {
+        for url in [
+            "https://beyondbetter.app&calc.exe",
+            "https://evil.example&start%20beyondbetter.app",
+            "https://beyondbetter.app%22%20&%20calc",
+            "https://beyondbetter.app$(calc)",
+            "https://beyondbetter.app;calc",
+            "https://evil.example|beyondbetter.app",
+            "https://beyondbetter.app%0acalc",
+        ] {
+            assert!(
+                check_link_allowed(url, &allowlist()).is_err(),
+                "{} was allowed",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn lookalike_hosts_are_rejected() {
+        for url in [
+            "https://beyondbetter.app.evil.example/download",
+            "https://evilbeyondbetter.app/download",
+            "https://evil.example/?next=https://beyondbetter.app",
+            "https://beyondbetter.app@evil.example/download",
+        ] {
+            assert!(
+                check_link_allowed(url, &allowlist()).is_err(),
+                "{} was allowed",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn other_schemes_are_rejected() {
+        for url in [
+            "file:///C:/Windows/System32/calc.exe",
+            "javascript:alert(1)",
+            "cmd:/c calc",
+        ] {
+            assert!(
+                check_link_allowed(url, &allowlist()).is_err(),
+                "{} was allowed",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn metacharacters_in_path_and_query_stay_part_of_the_url() {
+        // Allowed, since the opener passes the whole URL as one argument without a shell
+        let url = "https://github.com/Beyond-Better/bb/releases?a=1&b=\"2\"|x";
+
+        assert!(check_link_allowed(url, &allowlist()).is_ok());
+        assert!(
+            check_link_allowed("https://downloads.beyondbetter.app/bb.zip", &allowlist()).is_ok()
+        );
+    }
 }
@@ -1,11 +1,17 @@
 use chrono::{DateTime, Utc};
-use log::debug;
+use log::{debug, error};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::sync::{oneshot, RwLock};
 
-#[derive(Debug, Serialize, Deserialize)]
+pub const ACCESS_LOG_FILE_NAME: &str = "proxy-access.jsonl";
+const ROTATED_ACCESS_LOG_FILE_NAME: &str = "proxy-access.1.jsonl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccessLogEntry {
     pub timestamp: DateTime<Utc>,
     pub method: String,
@@ -13,27 +19,99 @@ pub struct AccessLogEntry {
     pub status: u16,
     pub duration_ms: u64,
     pub target: String,
+    #[serde(default)]
+    pub route: Option<String>,
     pub error: Option<String>,
+    /// Request body size, when declared or buffered; None for streamed uploads of unknown length
+    #[serde(default)]
+    pub request_bytes: Option<u64>,
+    /// Response body size from the upstream Content-Length, if it sent one
+    #[serde(default)]
+    pub response_bytes: Option<u64>,
+}
+
+// Entries waiting for the writer thread; when full, new entries are dropped rather than
+// holding up the request that produced them
+const ACCESS_LOG_QUEUE_SIZE: usize = 1024;
+
+enum WriterCommand {
+    Entry(AccessLogEntry),
+    SetLogDir(PathBuf, oneshot::Sender<std::io::Result<()>>),
+    Close(oneshot::Sender<std::io::Result<()>>),
 }
 
+/// Queues entries for the proxy access log. The file itself is owned by a writer thread, so
+/// logging a request never waits on disk I/O.
 #[derive(Debug)]
 pub struct AccessLogger {
     debug_mode: Arc<RwLock<bool>>,
+    log_dir: Mutex<PathBuf>,
+    sender: mpsc::Sender<WriterCommand>,
 }
 
 impl AccessLogger {
-    pub fn new(_log_dir: PathBuf, debug_mode: Arc<RwLock<bool>>) -> std::io::Result<Self> {
-        Ok(Self { debug_mode })
+    pub fn new(
+        log_dir: PathBuf,
+        debug_mode: Arc<RwLock<bool>>,
+        max_size: u64,
+    ) -> std::io::Result<Self> {
+        let file = AccessLogFile::open(log_dir.clone(), max_size)?;
+        let (sender, receiver) = mpsc::channel(ACCESS_LOG_QUEUE_SIZE);
+        std::thread::Builder::new()
+            .name("proxy-access-log".to_string())
+            .spawn(move || file.run(receiver))?;
+
+        Ok(Self {
+            debug_mode,
+            log_dir: Mutex::new(log_dir),
+            sender,
+        })
+    }
+
+    /// Path of the active (not yet rotated) access log file
+    pub fn log_path(&self) -> PathBuf {
+        self.log_dir.lock().unwrap().join(ACCESS_LOG_FILE_NAME)
+    }
+
+    /// Flush and close the file, e.g. so it can be moved. Entries logged until the next
+    /// `set_log_dir` are held and written once it reopens the log.
+    pub async fn close(&self) -> std::io::Result<()> {
+        let (reply, result) = oneshot::channel();
+        self.sender
+            .send(WriterCommand::Close(reply))
+            .await
+            .map_err(|_| writer_gone())?;
+        result.await.map_err(|_| writer_gone())?
+    }
+
+    /// Flush the current file and continue appending to the access log in `log_dir`
+    pub async fn set_log_dir(&self, log_dir: PathBuf) -> std::io::Result<()> {
+        let (reply, result) = oneshot::channel();
+        self.sender
+            .send(WriterCommand::SetLogDir(log_dir.clone(), reply))
+            .await
+            .map_err(|_| writer_gone())?;
+        result.await.map_err(|_| writer_gone())??;
+        *self.log_dir.lock().unwrap() = log_dir;
+        Ok(())
     }
 
     pub async fn log_request(&self, entry: &AccessLogEntry) -> std::io::Result<()> {
+        let bytes = |size: Option<u64>| size.map_or("?".to_string(), |size| format!("{}B", size));
         let message = format!(
-            "{} {} {} {}ms -> {}{}",
+            "{} {} {} {}ms in={} out={} -> {}{}{}",
             entry.method,
             entry.path,
             entry.status,
             entry.duration_ms,
+            bytes(entry.request_bytes),
+            bytes(entry.response_bytes),
             entry.target,
+            entry
+                .route
+                .as_ref()
+                .map(|r| format!(" [route {}]", r))
+                .unwrap_or_default(),
             entry
                 .error
                 .as_ref()
@@ -44,9 +122,135 @@ impl AccessLogger {
         // In non-debug mode, only log errors or non-200 responses
         if *self.debug_mode.read().await || entry.status >= 400 || entry.error.is_some() {
             debug!("Proxy access: {}", message);
-            log::info!(target: "proxy", "{}", message);
         }
 
+        // Every entry goes to the JSON lines file regardless of debug mode
+        match self.sender.try_send(WriterCommand::Entry(entry.clone())) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(std::io::Error::new(
+                std::io::ErrorKind::WouldBlock,
+                "access log queue is full, entry dropped",
+            )),
+            Err(TrySendError::Closed(_)) => Err(writer_gone()),
+        }
+    }
+}
+
+fn writer_gone() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::BrokenPipe,
+        "access log writer has stopped",
+    )
+}
+
+/// The access log file, owned by the writer thread
+struct AccessLogFile {
+    log_dir: PathBuf,
+    // None while closed for a move
+    writer: Option<BufWriter<File>>,
+    // Entries logged while closed
+    pending: Vec<AccessLogEntry>,
+    bytes_written: u64,
+    max_size: u64,
+}
+
+impl AccessLogFile {
+    fn open(log_dir: PathBuf, max_size: u64) -> std::io::Result<Self> {
+        fs::create_dir_all(&log_dir)?;
+        let (writer, bytes_written) = Self::open_log_file(&log_dir.join(ACCESS_LOG_FILE_NAME))?;
+
+        Ok(Self {
+            log_dir,
+            writer: Some(writer),
+            pending: Vec::new(),
+            bytes_written,
+            max_size,
+        })
+    }
+
+    fn open_log_file(path: &Path) -> std::io::Result<(BufWriter<File>, u64)> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok((BufWriter::new(file), size))
+    }
+
+    /// Write queued commands until every AccessLogger handle is dropped
+    fn run(mut self, mut receiver: mpsc::Receiver<WriterCommand>) {
+        while let Some(command) = receiver.blocking_recv() {
+            match command {
+                WriterCommand::Entry(entry) => {
+                    if let Err(e) = self.write_entry(&entry) {
+                        error!("Failed to write access log: {}", e);
+                    }
+                }
+                WriterCommand::SetLogDir(log_dir, reply) => {
+                    let _ = reply.send(self.set_log_dir(log_dir));
+                }
+                WriterCommand::Close(reply) => {
+                    let _ = reply.send(self.close());
+                }
+            }
+        }
+        if let Some(writer) = self.writer.as_mut() {
+            let _ = writer.flush();
+        }
+    }
+
+    fn close(&mut self) -> std::io::Result<()> {
+        match self.writer.take() {
+            Some(mut writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Move the current file aside (replacing any previous rotation) and start a fresh one
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush()?;
+        }
+        let current = self.log_dir.join(ACCESS_LOG_FILE_NAME);
+        let rotated = self.log_dir.join(ROTATED_ACCESS_LOG_FILE_NAME);
+        debug!("Rotating proxy access log to {:?}", rotated);
+        fs::rename(&current, &rotated)?;
+
+        let (writer, bytes_written) = Self::open_log_file(&current)?;
+        self.writer = Some(writer);
+        self.bytes_written = bytes_written;
+        Ok(())
+    }
+
+    fn set_log_dir(&mut self, log_dir: PathBuf) -> std::io::Result<()> {
+        self.close()?;
+        fs::create_dir_all(&log_dir)?;
+        let (writer, bytes_written) = Self::open_log_file(&log_dir.join(ACCESS_LOG_FILE_NAME))?;
+        self.writer = Some(writer);
+        self.bytes_written = bytes_written;
+        self.log_dir = log_dir;
+        for entry in std::mem::take(&mut self.pending) {
+            self.write_entry(&entry)?;
+        }
+        Ok(())
+    }
+
+    fn write_entry(&mut self, entry: &AccessLogEntry) -> std::io::Result<()> {
+        if self.writer.is_none() {
+            self.pending.push(entry.clone());
+            return Ok(());
+        }
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        if self.max_size > 0 && self.bytes_written + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        // Only None while closed, which was handled above
+        let writer = self.writer.as_mut().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "access log is not open")
+        })?;
+        writer.write_all(&line)?;
+        writer.flush()?;
+        self.bytes_written += line.len() as u64;
         Ok(())
     }
 }
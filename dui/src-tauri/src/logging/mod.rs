@@ -1,5 +1,14 @@
 mod access;
+mod rotation;
+mod service_output;
 mod setup;
 
-pub use access::{AccessLogEntry, AccessLogger};
-pub use setup::setup_app_logging;
+pub use access::{AccessLogEntry, AccessLogger, ACCESS_LOG_FILE_NAME};
+pub use rotation::rotate_log_file;
+pub use service_output::{
+    capture_service_output, clear_service_output, get_service_output, service_output_path,
+};
+pub use setup::{
+    reload_app_logging, reload_log_config_file, set_app_log_level, setup_app_logging,
+    suspend_app_logging,
+};
@@ -0,0 +1,63 @@
+use log::{info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `<stem>.<index>.<ext>` next to `log_path`, e.g. api.log -> api.1.log
+fn rotated_path(log_path: &Path, index: usize) -> PathBuf {
+    let stem = log_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match log_path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, index),
+    };
+    log_path.with_file_name(name)
+}
+
+/// Roll `log_path` over before a service opens it, in the same fixed-window style as the
+/// app's own logs: once it reaches `max_bytes` it becomes `<stem>.1.<ext>`, older files
+/// shift up by one and at most `max_files` rotated files are kept.
+/// Failures are logged and otherwise ignored so a stuck log never blocks a service start.
+pub fn rotate_log_file(log_path: &Path, max_bytes: u64, max_files: usize) {
+    let size = match fs::metadata(log_path) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return,
+    };
+    if max_bytes == 0 || size < max_bytes {
+        return;
+    }
+
+    if max_files == 0 {
+        if let Err(e) = fs::remove_file(log_path) {
+            warn!("Failed to remove oversized log {:?}: {}", log_path, e);
+        }
+        return;
+    }
+
+    let oldest = rotated_path(log_path, max_files);
+    if oldest.exists() {
+        if let Err(e) = fs::remove_file(&oldest) {
+            warn!("Failed to remove old log {:?}: {}", oldest, e);
+        }
+    }
+    for index in (1..max_files).rev() {
+        let from = rotated_path(log_path, index);
+        if from.exists() {
+            if let Err(e) = fs::rename(&from, rotated_path(log_path, index + 1)) {
+                warn!("Failed to rotate log {:?}: {}", from, e);
+            }
+        }
+    }
+
+    let first = rotated_path(log_path, 1);
+    match fs::rename(log_path, &first) {
+        Ok(()) => info!(
+            "Rotated {:?} ({} bytes) to {:?}",
+            log_path,
+            size,
+            first.file_name().unwrap_or_default()
+        ),
+        Err(e) => warn!("Failed to rotate log {:?}: {}", log_path, e),
+    }
+}
@@ -0,0 +1,112 @@
+use log::debug;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Lines of child process output returned per service
+const MAX_OUTPUT_LINES: usize = 200;
+// Only the end of the output file is read, however much the service has written
+const MAX_TAIL_BYTES: u64 = 64 * 1024;
+
+// Output file of the last instance of each service started by the DUI
+static SERVICE_OUTPUT: Lazy<Mutex<HashMap<String, PathBuf>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Output file for `service` next to its log file, e.g. `api-output.log`
+pub fn service_output_path(log_path: &Path, service: &str) -> PathBuf {
+    log_path.with_file_name(format!("{}-output.log", service))
+}
+
+/// Start a fresh output file for `service` and return handles for the child's stdout and
+/// stderr. The child writes to the file directly, so its output doesn't depend on the DUI
+/// still running or reading it.
+pub fn capture_service_output(service: &str, path: &Path) -> std::io::Result<(File, File)> {
+    let stdout = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+    let stderr = stdout.try_clone()?;
+    SERVICE_OUTPUT
+        .lock()
+        .unwrap()
+        .insert(service.to_string(), path.to_path_buf());
+    Ok((stdout, stderr))
+}
+
+/// Forget previously captured output, e.g. before starting a fresh instance
+pub fn clear_service_output(service: &str) {
+    SERVICE_OUTPUT.lock().unwrap().remove(service);
+}
+
+/// Most recent output of `service`, oldest first
+pub fn get_service_output(service: &str) -> Vec<String> {
+    let Some(path) = SERVICE_OUTPUT.lock().unwrap().get(service).cloned() else {
+        return Vec::new();
+    };
+    match tail_lines(&path, MAX_OUTPUT_LINES) {
+        Ok(lines) => lines,
+        Err(e) => {
+            debug!("Failed to read {} output {:?}: {}", service, path, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Last `max_lines` lines of `path`. Invalid UTF-8 is replaced rather than ending the read.
+fn tail_lines(path: &Path, max_lines: usize) -> std::io::Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(MAX_TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut reader = BufReader::new(file);
+
+    let mut lines = VecDeque::new();
+    let mut line = Vec::new();
+    // Starting mid-file means the first line is probably partial
+    let mut skip_partial = start > 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        if std::mem::take(&mut skip_partial) {
+            continue;
+        }
+        if lines.len() >= max_lines {
+            lines.pop_front();
+        }
+        let text = String::from_utf8_lossy(&line);
+        lines.push_back(text.trim_end_matches(['\r', '\n']).to_string());
+    }
+    Ok(lines.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_utf8_does_not_stop_reading() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("api-output.log");
+        std::fs::write(&path, b"first\nbad \xff\xfe bytes\r\nlast\n").unwrap();
+
+        let lines = tail_lines(&path, 10).unwrap();
+
+        assert_eq!(lines, vec!["first", "bad \u{fffd}\u{fffd} bytes", "last"]);
+    }
+
+    #[test]
+    fn only_the_last_lines_are_kept() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("api-output.log");
+        let contents: String = (1..=5).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, contents).unwrap();
+
+        assert_eq!(tail_lines(&path, 2).unwrap(), vec!["line 4", "line 5"]);
+    }
+}
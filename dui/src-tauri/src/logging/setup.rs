@@ -1,63 +1,294 @@
+use crate::config::{read_global_config, LogFormat};
+use log::LevelFilter;
 use log4rs::Handle;
-use std::path::PathBuf;
+use once_cell::sync::{Lazy, OnceCell};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
 
-pub fn setup_app_logging(log_dir: PathBuf) -> std::io::Result<Handle> {
-    // Copy config file to log directory if it doesn't exist
-    let config_path = log_dir.join("log4rs.yaml");
-    if !config_path.exists() {
-        let mut config_content = include_str!("../../config/log4rs.yaml").to_string();
-
-        // Replace the path placeholders with actual paths
-        let app_log_path = log_dir
-            .join("Beyond Better.log")
-            .to_string_lossy()
-            .to_string()
-            .replace("\\", "\\\\"); // Escape backslashes for YAML
-        let proxy_log_path = log_dir
-            .join("proxy-access.log")
-            .to_string_lossy()
-            .to_string()
-            .replace("\\", "\\\\"); // Escape backslashes for YAML
-
-        config_content = config_content.replace(
-            "path: \"Beyond Better.log\"",
-            &format!("path: \"{}\"", app_log_path),
-        );
-        config_content = config_content.replace(
-            "path: \"proxy-access.log\"",
-            &format!("path: \"{}\"", proxy_log_path),
-        );
+const LOG_CONFIG_FILE_NAME: &str = "log4rs.yaml";
+// Encoder line of the app appender in the bundled template
+const APP_TEXT_ENCODER: &str =
+    "      pattern: \"[{d(%Y-%m-%d %H:%M:%S%.3f)}] {h({l})} {t} - {m}{n}\"";
+// log4rs JSON encoder: one object per line with time, level, target, message and more
+const APP_JSON_ENCODER: &str = "      kind: json";
 
-        // Update the roller patterns with full paths
-        config_content = config_content.replace(
-            "pattern: \"Beyond Better.{}.log\"",
-            &format!(
-                "pattern: \"{}.{{}}.log\"",
-                log_dir
-                    .join("Beyond Better")
-                    .to_string_lossy()
-                    .to_string()
-                    .replace("\\", "\\\\")
-            ),
-        );
-        config_content = config_content.replace(
-            "pattern: \"proxy-access.{}.log\"",
-            &format!(
-                "pattern: \"{}.{{}}.log\"",
-                log_dir
-                    .join("proxy-access")
-                    .to_string_lossy()
-                    .to_string()
-                    .replace("\\", "\\\\")
-            ),
-        );
+// Kept so the logger can be reconfigured at runtime, e.g. after relocating logs
+static LOGGING_HANDLE: OnceCell<Handle> = OnceCell::new();
+// log4rs.yaml the running logger was built from, so the level can be changed later
+static LOG_CONFIG_PATH: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+// Root level set through set_app_log_level, kept across reloads until the app restarts
+static ROOT_LEVEL_OVERRIDE: Lazy<Mutex<Option<LevelFilter>>> = Lazy::new(|| Mutex::new(None));
+
+fn configured_log_format() -> LogFormat {
+    read_global_config()
+        .map(|config| config.dui.log_format)
+        .unwrap_or_default()
+}
 
-        std::fs::write(&config_path, config_content)?;
+/// Format an existing log4rs.yaml was written for
+fn log_config_format(config_path: &Path) -> Option<LogFormat> {
+    let content = std::fs::read_to_string(config_path).ok()?;
+    Some(if content.contains(APP_JSON_ENCODER) {
+        LogFormat::Json
+    } else {
+        LogFormat::Text
+    })
+}
+
+/// Whether an existing log4rs.yaml still has the text proxy log appender, which the proxy
+/// no longer writes to now that requests go to the JSON lines access log
+fn log_config_has_proxy_appender(config_path: &Path) -> bool {
+    std::fs::read_to_string(config_path).is_ok_and(|content| content.contains("proxy-access.log"))
+}
+
+/// Render the bundled log4rs.yaml with paths pointing into `log_dir` and write it there
+fn write_log_config(log_dir: &Path) -> std::io::Result<PathBuf> {
+    let config_path = log_dir.join(LOG_CONFIG_FILE_NAME);
+    let mut config_content = include_str!("../../config/log4rs.yaml").to_string();
+
+    if configured_log_format() == LogFormat::Json {
+        config_content = config_content.replace(APP_TEXT_ENCODER, APP_JSON_ENCODER);
+    }
+
+    // Replace the path placeholders with actual paths
+    let app_log_path = log_dir
+        .join("Beyond Better.log")
+        .to_string_lossy()
+        .to_string()
+        .replace("\\", "\\\\"); // Escape backslashes for YAML
+
+    config_content = config_content.replace(
+        "path: \"Beyond Better.log\"",
+        &format!("path: \"{}\"", app_log_path),
+    );
+
+    // Update the roller patterns with full paths
+    config_content = config_content.replace(
+        "pattern: \"Beyond Better.{}.log\"",
+        &format!(
+            "pattern: \"{}.{{}}.log\"",
+            log_dir
+                .join("Beyond Better")
+                .to_string_lossy()
+                .to_string()
+                .replace("\\", "\\\\")
+        ),
+    );
+
+    std::fs::write(&config_path, config_content)?;
+    Ok(config_path)
+}
+
+fn load_log_config(config_path: &Path) -> std::io::Result<log4rs::Config> {
+    let mut config = log4rs::config::load_config_file(config_path, Default::default())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    if let Some(level) = *ROOT_LEVEL_OVERRIDE.lock().unwrap() {
+        config.root_mut().set_level(level);
+    }
+    *LOG_CONFIG_PATH.lock().unwrap() = Some(config_path.to_path_buf());
+    Ok(config)
+}
+
+pub fn setup_app_logging(log_dir: PathBuf) -> std::io::Result<Handle> {
+    // Copy config file to log directory if it doesn't exist, or was written for the
+    // other log format or with the old proxy appender
+    let config_path = log_dir.join(LOG_CONFIG_FILE_NAME);
+    if log_config_format(&config_path) != Some(configured_log_format())
+        || log_config_has_proxy_appender(&config_path)
+    {
+        write_log_config(&log_dir)?;
     }
 
     // Parse and initialize logging with the YAML config
-    let config = log4rs::config::load_config_file(&config_path, Default::default())
+    let config = load_log_config(&config_path)?;
+
+    let handle = log4rs::init_config(config)
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    let _ = LOGGING_HANDLE.set(handle.clone());
+    Ok(handle)
+}
 
-    log4rs::init_config(config).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+fn logging_handle() -> std::io::Result<&'static Handle> {
+    LOGGING_HANDLE
+        .get()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "Logging not initialized"))
+}
+
+/// Close the log files by switching the running logger to one without appenders, e.g. so
+/// they can be moved. Messages are dropped until `reload_app_logging`.
+pub fn suspend_app_logging() -> std::io::Result<()> {
+    let config = log4rs::Config::builder()
+        .build(log4rs::config::Root::builder().build(LevelFilter::Off))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    logging_handle()?.set_config(config);
+    Ok(())
+}
+
+/// Point appender paths (and roller patterns) that lived in `old_dir` at `new_dir`
+fn relocate_appender_paths(yaml: &mut serde_yaml::Value, old_dir: &Path, new_dir: &Path) {
+    let Some(appenders) = yaml.get_mut("appenders").and_then(|a| a.as_mapping_mut()) else {
+        return;
+    };
+    let relocate = |value: Option<&mut serde_yaml::Value>| {
+        let Some(value) = value else {
+            return;
+        };
+        let relocated = value
+            .as_str()
+            .map(Path::new)
+            .filter(|path| path.parent() == Some(old_dir))
+            .and_then(|path| path.file_name())
+            .map(|name| new_dir.join(name).to_string_lossy().to_string());
+        if let Some(relocated) = relocated {
+            *value = serde_yaml::Value::String(relocated);
+        }
+    };
+    for (_, appender) in appenders.iter_mut() {
+        relocate(appender.get_mut("path"));
+        relocate(
+            appender
+                .get_mut("policy")
+                .and_then(|policy| policy.get_mut("roller"))
+                .and_then(|roller| roller.get_mut("pattern")),
+        );
+    }
+}
+
+/// Move the running logger's log4rs.yaml to `log_dir`, changing only the paths of appenders
+/// that wrote to the old directory so any other edits are kept, and apply it
+pub fn reload_app_logging(log_dir: &Path) -> std::io::Result<()> {
+    let handle = logging_handle()?;
+    let old_config_path = LOG_CONFIG_PATH.lock().unwrap().clone();
+    let config_path = log_dir.join(LOG_CONFIG_FILE_NAME);
+
+    let existing = old_config_path
+        .as_deref()
+        .and_then(|path| Some((path.parent()?, std::fs::read_to_string(path).ok()?)));
+    match existing {
+        Some((old_dir, _)) if old_dir == log_dir => {}
+        Some((old_dir, content)) => {
+            let mut yaml: serde_yaml::Value = serde_yaml::from_str(&content)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            relocate_appender_paths(&mut yaml, old_dir, log_dir);
+            let content = serde_yaml::to_string(&yaml)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            std::fs::write(&config_path, content)?;
+        }
+        None => {
+            write_log_config(log_dir)?;
+        }
+    }
+
+    let config = load_log_config(&config_path)?;
+    handle.set_config(config);
+    Ok(())
+}
+
+/// Change the root log level of the running logger without touching log4rs.yaml.
+/// Accepts off, error, warn, info, debug or trace.
+pub fn set_app_log_level(level: &str) -> Result<LevelFilter, String> {
+    let level = LevelFilter::from_str(level.trim()).map_err(|_| {
+        format!(
+            "Invalid log level: {}. Expected one of off, error, warn, info, debug, trace",
+            level
+        )
+    })?;
+    let handle = LOGGING_HANDLE
+        .get()
+        .ok_or_else(|| "Logging not initialized".to_string())?;
+    let config_path = LOG_CONFIG_PATH
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "Logging not initialized".to_string())?;
+
+    *ROOT_LEVEL_OVERRIDE.lock().unwrap() = Some(level);
+    let config =
+        load_log_config(&config_path).map_err(|e| format!("Failed to load log config: {}", e))?;
+    handle.set_config(config);
+    Ok(level)
+}
+
+/// Parse log4rs.yaml, failing on any appender or logger error instead of dropping the
+/// broken parts as log4rs's own loader does
+fn parse_log_config_strict(config_path: &Path) -> Result<log4rs::Config, String> {
+    let source = std::fs::read_to_string(config_path)
+        .map_err(|e| format!("Failed to read {:?}: {}", config_path, e))?;
+    let raw: log4rs::config::RawConfig = serde_yaml::from_str(&source)
+        .map_err(|e| format!("Invalid {}: {}", LOG_CONFIG_FILE_NAME, e))?;
+
+    let (appenders, errors) = raw.appenders_lossy(&Default::default());
+    if !errors.is_empty() {
+        return Err(format!("Invalid {}: {}", LOG_CONFIG_FILE_NAME, errors));
+    }
+    let mut config = log4rs::Config::builder()
+        .appenders(appenders)
+        .loggers(raw.loggers())
+        .build(raw.root())
+        .map_err(|e| format!("Invalid {}: {}", LOG_CONFIG_FILE_NAME, e))?;
+    if let Some(level) = *ROOT_LEVEL_OVERRIDE.lock().unwrap() {
+        config.root_mut().set_level(level);
+    }
+    Ok(config)
+}
+
+/// Re-read the on-disk log4rs.yaml, e.g. after editing appenders by hand, and apply it to
+/// the running logger. An invalid file is reported and the current configuration is kept.
+/// A level set through set_app_log_level still overrides the file's root level.
+pub fn reload_log_config_file() -> Result<PathBuf, String> {
+    let handle = LOGGING_HANDLE
+        .get()
+        .ok_or_else(|| "Logging not initialized".to_string())?;
+    let config_path = LOG_CONFIG_PATH
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "Logging not initialized".to_string())?;
+
+    let config = parse_log_config_strict(&config_path)?;
+    handle.set_config(config);
+    Ok(config_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_appenders_in_the_old_directory_are_relocated() {
+        let old_dir = Path::new("/logs/old");
+        let new_dir = Path::new("/logs/new");
+        let mut yaml: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+appenders:
+  app:
+    kind: rolling_file
+    path: "/logs/old/Beyond Better.log"
+    policy:
+      roller:
+        pattern: "/logs/old/Beyond Better.{}.log"
+  audit:
+    kind: file
+    path: "/var/log/audit.log"
+root:
+  level: debug
+"#,
+        )
+        .unwrap();
+
+        relocate_appender_paths(&mut yaml, old_dir, new_dir);
+
+        let app = &yaml["appenders"]["app"];
+        assert_eq!(app["path"].as_str(), Some("/logs/new/Beyond Better.log"));
+        assert_eq!(
+            app["policy"]["roller"]["pattern"].as_str(),
+            Some("/logs/new/Beyond Better.{}.log")
+        );
+        assert_eq!(
+            yaml["appenders"]["audit"]["path"].as_str(),
+            Some("/var/log/audit.log")
+        );
+        assert_eq!(yaml["root"]["level"].as_str(), Some("debug"));
+    }
 }
@@ -1,9 +1,29 @@
 use crate::config::get_dui_debug_mode;
-use log::{error, info};
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tauri::{Emitter, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
 
+/// How long an OAuth window may stay open before it is closed as abandoned
+const DEFAULT_OAUTH_TIMEOUT_SECS: u64 = 300;
+
+/// Bookkeeping for an OAuth window that hasn't completed yet
+#[derive(Debug, Clone)]
+struct OAuthFlow {
+    provider: String,
+    server_id: Option<String>,
+    state: String,  // Expected `state` in the callback result, guards against CSRF
+    started_at: Instant,
+    timeout: Duration,
+}
+
+/// Pending OAuth flows keyed by window label
+static OAUTH_FLOWS: Lazy<Mutex<HashMap<String, OAuthFlow>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 /// OAuth result data structure
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OAuthResult {
@@ -14,6 +34,8 @@ pub struct OAuthResult {
     pub code: Option<String>,
     pub state: Option<String>,
     pub error: Option<String>,
+    #[serde(rename = "windowLabel", default)]
+    pub window_label: Option<String>,  // OAuth window the result came from, set by the DUI
 }
 
 /// OAuth flow parameters
@@ -24,6 +46,18 @@ pub struct OAuthFlowParams {
     pub window_title: Option<String>,
     pub window_width: Option<f64>,
     pub window_height: Option<f64>,
+    pub server_id: Option<String>,  // Reported back if the flow times out
+    pub timeout_secs: Option<u64>,  // Defaults to DEFAULT_OAUTH_TIMEOUT_SECS
+}
+
+/// Active OAuth window information
+#[derive(Debug, Serialize)]
+pub struct OAuthWindowInfo {
+    pub provider: String,
+    #[serde(rename = "serverId")]
+    pub server_id: Option<String>,
+    #[serde(rename = "remainingSecs")]
+    pub remaining_secs: Option<u64>,  // None for windows not started by start_oauth_flow
 }
 
 /// Start OAuth flow by creating a new OAuth window
@@ -67,9 +101,24 @@ pub async fn start_oauth_flow(
     let window_height = params.window_height.unwrap_or(650.0);
 
     // Parse OAuth URL
-    let oauth_url = params.oauth_url.parse::<url::Url>()
+    let mut oauth_url = params.oauth_url.parse::<url::Url>()
         .map_err(|e| format!("Invalid OAuth URL: {}", e))?;
 
+    // Remember the state the callback must echo back. URLs built by the API already carry
+    // one (tied to its PKCE verifier); otherwise generate it here.
+    let existing_state = oauth_url.query_pairs()
+        .find(|(key, _)| key == "state")
+        .map(|(_, value)| value.into_owned())
+        .filter(|value| !value.is_empty());
+    let state = match existing_state {
+        Some(state) => state,
+        None => {
+            let state = hex::encode(rand::random::<[u8; 32]>());
+            oauth_url.query_pairs_mut().append_pair("state", &state);
+            state
+        }
+    };
+
     if debug_enabled {
         info!("[DEBUG] Window configuration:");
         info!("[DEBUG] - Title: {}", window_title);
@@ -106,9 +155,67 @@ pub async fn start_oauth_flow(
         error!("Failed to emit oauth-window-ready event: {}", e);
     }
 
+    let timeout = Duration::from_secs(params.timeout_secs.unwrap_or(DEFAULT_OAUTH_TIMEOUT_SECS));
+    OAUTH_FLOWS.lock().unwrap().insert(
+        window_label.clone(),
+        OAuthFlow {
+            provider: params.provider.clone(),
+            server_id: params.server_id.clone(),
+            state,
+            started_at: Instant::now(),
+            timeout,
+        },
+    );
+
+    // Close the window if the user abandons the flow so it doesn't linger until app exit
+    let timeout_label = window_label.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(timeout).await;
+        expire_oauth_flow(&app_handle, &timeout_label);
+    });
+
     Ok(window_label)
 }
 
+/// Close a flow's window once its timeout passes and tell bb_chat it failed.
+/// Does nothing if the flow already completed or was closed.
+fn expire_oauth_flow(app_handle: &tauri::AppHandle, window_label: &str) {
+    let Some(flow) = OAUTH_FLOWS.lock().unwrap().remove(window_label) else {
+        return;
+    };
+
+    warn!(
+        "OAuth flow for {} timed out after {}s, closing window {}",
+        flow.provider,
+        flow.timeout.as_secs(),
+        window_label
+    );
+
+    if let Some(window) = app_handle.get_webview_window(window_label) {
+        if let Err(e) = window.close() {
+            error!("Failed to close timed out OAuth window {}: {}", window_label, e);
+        }
+    }
+
+    let result = OAuthResult {
+        success: false,
+        provider: flow.provider,
+        server_id: flow.server_id,
+        code: None,
+        state: None,
+        error: Some("timeout".to_string()),
+        window_label: Some(window_label.to_string()),
+    };
+    match app_handle.get_webview_window("bb_chat") {
+        Some(bb_chat_window) => {
+            if let Err(e) = bb_chat_window.emit("oauth-result", &result) {
+                error!("Failed to emit oauth-result event: {}", e);
+            }
+        }
+        None => warn!("BB Chat window not found, dropping OAuth timeout result"),
+    }
+}
+
 /// Complete OAuth flow and send results to bb_chat window
 /// 
 /// This is called from the OAuth callback page to send results back to the bb_chat window
@@ -127,6 +234,7 @@ pub async fn complete_oauth_flow(
 ) -> Result<(), String> {
     let debug_enabled = get_dui_debug_mode();
     let window_label = window.label().to_string();
+    let flow = OAUTH_FLOWS.lock().unwrap().remove(&window_label);
     
     if debug_enabled {
         info!("[DEBUG] Completing OAuth flow for window: {}", window_label);
@@ -141,6 +249,38 @@ pub async fn complete_oauth_flow(
         }
     }
 
+    // Only forward results for flows we started, and only successful ones carrying the
+    // state we sent; anything else could be a forged callback (OAuth CSRF)
+    let rejection = match &flow {
+        None => Some("unknown OAuth flow"),
+        Some(flow) if result.success && result.state.as_deref() != Some(flow.state.as_str()) => {
+            Some("state mismatch")
+        }
+        Some(_) => None,
+    };
+    // Tag the result with its window and the server that started the flow so concurrent
+    // flows can tell their results apart
+    let server_id = flow.and_then(|flow| flow.server_id).or(result.server_id);
+    let result = match rejection {
+        Some(reason) => {
+            warn!("Rejecting OAuth result from window {}: {}", window_label, reason);
+            OAuthResult {
+                success: false,
+                provider: result.provider,
+                server_id,
+                code: None,
+                state: None,
+                error: Some(reason.to_string()),
+                window_label: Some(window_label.clone()),
+            }
+        }
+        None => OAuthResult {
+            server_id,
+            window_label: Some(window_label.clone()),
+            ..result
+        },
+    };
+
     // Send result to bb_chat window via event
     let bb_chat_window = window.app_handle()
         .get_webview_window("bb_chat")
@@ -182,11 +322,12 @@ pub async fn complete_oauth_flow(
 /// * `app_handle` - Tauri app handle
 /// 
 /// # Returns
-/// * `Result<HashMap<String, String>, String>` - Map of window labels to providers
+/// * `Result<HashMap<String, OAuthWindowInfo>, String>` - Map of window labels to provider
+///   and time remaining before the window is closed
 #[tauri::command]
 pub async fn get_oauth_windows(
     app_handle: tauri::AppHandle,
-) -> Result<HashMap<String, String>, String> {
+) -> Result<HashMap<String, OAuthWindowInfo>, String> {
     let debug_enabled = get_dui_debug_mode();
     
     if debug_enabled {
@@ -194,15 +335,27 @@ pub async fn get_oauth_windows(
     }
 
     let mut oauth_windows = HashMap::new();
+    let flows = OAUTH_FLOWS.lock().unwrap().clone();
     
     // Get all windows and filter OAuth windows
     let windows = app_handle.webview_windows();
     for (label, _window) in windows.iter() {
-        if label.starts_with("oauth_window_") {
+        if let Some(flow) = flows.get(label) {
+            let remaining = flow.timeout.saturating_sub(flow.started_at.elapsed());
+            oauth_windows.insert(label.clone(), OAuthWindowInfo {
+                provider: flow.provider.clone(),
+                server_id: flow.server_id.clone(),
+                remaining_secs: Some(remaining.as_secs()),
+            });
+        } else if label.starts_with("oauth_window_") {
             // Extract provider from window label
             if let Some(provider_part) = label.strip_prefix("oauth_window_") {
                 if let Some(provider) = provider_part.split('_').next() {
-                    oauth_windows.insert(label.clone(), provider.to_string());
+                    oauth_windows.insert(label.clone(), OAuthWindowInfo {
+                        provider: provider.to_string(),
+                        server_id: None,
+                        remaining_secs: None,
+                    });
                 }
             }
         }
@@ -210,8 +363,8 @@ pub async fn get_oauth_windows(
 
     if debug_enabled {
         info!("[DEBUG] Found {} OAuth windows", oauth_windows.len());
-        for (label, provider) in &oauth_windows {
-            info!("[DEBUG] - {}: {}", label, provider);
+        for (label, info) in &oauth_windows {
+            info!("[DEBUG] - {}: {} ({:?}s remaining)", label, info.provider, info.remaining_secs);
         }
     }
 
@@ -238,6 +391,7 @@ pub async fn close_oauth_window(
     if debug_enabled {
         info!("[DEBUG] Closing OAuth window: {}", window_label);
     }
+    OAUTH_FLOWS.lock().unwrap().remove(&window_label);
 
     let window = app_handle
         .get_webview_window(&window_label)
@@ -253,4 +407,75 @@ pub async fn close_oauth_window(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Cancel every OAuth flow
+/// 
+/// Closes all OAuth windows and emits a cancelled `oauth-result` to bb_chat for each, so
+/// nothing waiting on a flow hangs (e.g. after logout or a session reset). Flows whose
+/// window was already closed by the user are cancelled as well.
+/// 
+/// # Arguments
+/// * `app_handle` - Tauri app handle
+/// 
+/// # Returns
+/// * `Result<usize, String>` - Number of flows cancelled
+#[tauri::command]
+pub async fn cancel_all_oauth_windows(
+    app_handle: tauri::AppHandle,
+) -> Result<usize, String> {
+    let debug_enabled = get_dui_debug_mode();
+    let mut flows: HashMap<String, OAuthFlow> = OAUTH_FLOWS.lock().unwrap().drain().collect();
+
+    let mut labels: Vec<String> = app_handle
+        .webview_windows()
+        .into_keys()
+        .filter(|label| label.starts_with("oauth_window_"))
+        .collect();
+    for label in flows.keys() {
+        if !labels.contains(label) {
+            labels.push(label.clone());
+        }
+    }
+
+    let bb_chat_window = app_handle.get_webview_window("bb_chat");
+    if bb_chat_window.is_none() {
+        warn!("BB Chat window not found, OAuth cancellations won't be delivered");
+    }
+
+    for label in &labels {
+        if debug_enabled {
+            info!("[DEBUG] Cancelling OAuth window: {}", label);
+        }
+        let flow = flows.remove(label);
+        let provider = flow.as_ref().map(|flow| flow.provider.clone()).unwrap_or_else(|| {
+            label.strip_prefix("oauth_window_")
+                .and_then(|provider_part| provider_part.split('_').next())
+                .unwrap_or_default()
+                .to_string()
+        });
+        let result = OAuthResult {
+            success: false,
+            provider,
+            server_id: flow.and_then(|flow| flow.server_id),
+            code: None,
+            state: None,
+            error: Some("cancelled".to_string()),
+            window_label: Some(label.clone()),
+        };
+        if let Some(bb_chat_window) = &bb_chat_window {
+            if let Err(e) = bb_chat_window.emit("oauth-result", &result) {
+                error!("Failed to emit oauth-result event: {}", e);
+            }
+        }
+
+        if let Some(window) = app_handle.get_webview_window(label) {
+            if let Err(e) = window.close() {
+                error!("Failed to close OAuth window {}: {}", label, e);
+            }
+        }
+    }
+
+    info!("Cancelled {} OAuth flows", labels.len());
+    Ok(labels.len())
+}
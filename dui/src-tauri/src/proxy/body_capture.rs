@@ -0,0 +1,229 @@
+use chrono::Utc;
+use futures_util::StreamExt;
+use http::HeaderMap;
+use hyper::Body;
+use log::warn;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+pub const DEBUG_LOG_FILE_NAME: &str = "proxy-debug.log";
+const REDACTED: &str = "[REDACTED]";
+// Header values are never written when the lowercase name contains any of these
+const SENSITIVE_HEADER_PARTS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "token",
+    "api-key",
+    "apikey",
+    "secret",
+];
+
+// Credentials that can show up in request and response bodies, with the replacement
+// that keeps the surrounding text readable
+static SECRET_PATTERNS: Lazy<Vec<(Regex, &'static str)>> = Lazy::new(|| {
+    [
+        (r"sk-ant-[A-Za-z0-9_\-]+", "sk-ant-[REDACTED]"),
+        (
+            r#"(?i)("[a-z_]*(?:api_?key|token|secret|password)"\s*:\s*)"[^"]*""#,
+            r#"$1"[REDACTED]""#,
+        ),
+        (r"(?i)(bearer\s+)[A-Za-z0-9._~+/=\-]+", "${1}[REDACTED]"),
+        (
+            r"(?i)((?:[a-z_]*token|client_secret|password|code|state|api_?key)=)[^&\s#]+",
+            "${1}[REDACTED]",
+        ),
+    ]
+    .into_iter()
+    .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), replacement))
+    .collect()
+});
+
+// Requests are handled concurrently; keep each captured message in one piece
+static WRITE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Where and how much of each body to capture while body capture is enabled
+#[derive(Debug, Clone)]
+pub(super) struct BodyCapture {
+    pub max_bytes: usize,
+    pub path: PathBuf,
+}
+
+/// Body bytes seen so far for one captured message
+struct PendingCapture {
+    capture: BodyCapture,
+    heading: String,
+    headers: HeaderMap,
+    captured: Vec<u8>,
+    total: u64,
+}
+
+/// Secret values from the config that must never reach the log, whatever their format
+fn configured_secrets() -> Vec<String> {
+    let Ok(config) = crate::config::read_global_config() else {
+        return Vec::new();
+    };
+    config
+        .api
+        .llm_providers
+        .anthropic
+        .and_then(|provider| provider.api_key)
+        .filter(|key| !key.is_empty() && crate::keychain::keychain_account(key).is_none())
+        .into_iter()
+        .collect()
+}
+
+fn redact_header(name: &str, value: &str, secrets: &[String]) -> String {
+    let name = name.to_ascii_lowercase();
+    if SENSITIVE_HEADER_PARTS
+        .iter()
+        .any(|part| name.contains(part))
+    {
+        REDACTED.to_string()
+    } else {
+        redact_text(value, secrets)
+    }
+}
+
+fn redact_text(text: &str, secrets: &[String]) -> String {
+    let mut text = text.to_string();
+    for secret in secrets {
+        text = text.replace(secret, REDACTED);
+    }
+    for (pattern, replacement) in SECRET_PATTERNS.iter() {
+        text = pattern.replace_all(&text, *replacement).into_owned();
+    }
+    text
+}
+
+impl BodyCapture {
+    /// Pass `body` through unchanged, keeping up to `max_bytes` of it and writing the
+    /// capture to the debug log once the body has been fully read
+    pub(super) fn capture_body(&self, body: Body, heading: String, headers: HeaderMap) -> Body {
+        let pending = PendingCapture {
+            capture: self.clone(),
+            heading,
+            headers,
+            captured: Vec::new(),
+            total: 0,
+        };
+        let stream = futures_util::stream::unfold(Some((body, pending)), |state| async move {
+            let (mut body, mut pending) = state?;
+            match body.next().await {
+                Some(Ok(chunk)) => {
+                    let room = pending.capture.max_bytes - pending.captured.len();
+                    pending
+                        .captured
+                        .extend_from_slice(&chunk[..chunk.len().min(room)]);
+                    pending.total += chunk.len() as u64;
+                    Some((Ok(chunk), Some((body, pending))))
+                }
+                Some(Err(e)) => {
+                    pending.heading.push_str(&format!(" [body error: {}]", e));
+                    pending.write();
+                    Some((Err(e), None))
+                }
+                None => {
+                    pending.write();
+                    None
+                }
+            }
+        });
+        Body::wrap_stream(stream)
+    }
+}
+
+impl PendingCapture {
+    fn write(&self) {
+        self.write_redacted(&configured_secrets());
+    }
+
+    /// Append the capture to the debug log with `secrets` and known credential patterns
+    /// removed
+    fn write_redacted(&self, secrets: &[String]) {
+        // The heading carries the query string, e.g. OAuth callback codes
+        let mut entry = format!(
+            "[{}] {}\n",
+            Utc::now().to_rfc3339(),
+            redact_text(&self.heading, secrets)
+        );
+        for (name, value) in self.headers.iter() {
+            let value = value.to_str().unwrap_or("<binary>");
+            entry.push_str(&format!(
+                "{}: {}\n",
+                name,
+                redact_header(name.as_str(), value, secrets)
+            ));
+        }
+
+        let encoding = self
+            .headers
+            .get(hyper::header::CONTENT_ENCODING)
+            .and_then(|h| h.to_str().ok())
+            .filter(|e| !e.eq_ignore_ascii_case("identity"));
+        if let Some(encoding) = encoding {
+            entry.push_str(&format!(
+                "\n[{} byte {}-encoded body not shown]\n\n",
+                self.total, encoding
+            ));
+        } else {
+            entry.push_str(&format!(
+                "\n{}\n",
+                redact_text(&String::from_utf8_lossy(&self.captured), secrets)
+            ));
+            if self.total > self.captured.len() as u64 {
+                entry.push_str(&format!(
+                    "[truncated: {} of {} bytes shown]\n",
+                    self.captured.len(),
+                    self.total
+                ));
+            }
+            entry.push('\n');
+        }
+
+        let _guard = WRITE_LOCK.lock().unwrap();
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.capture.path)
+            .and_then(|mut file| file.write_all(entry.as_bytes()));
+        if let Err(e) = result {
+            warn!(
+                "Failed to write proxy debug log {:?}: {}",
+                self.capture.path, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_secrets_in_heading_are_redacted() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let pending = PendingCapture {
+            capture: BodyCapture {
+                max_bytes: 1024,
+                path: dir.path().join(DEBUG_LOG_FILE_NAME),
+            },
+            heading: ">>> GET /oauth/callback?code=abc123&state=xyz789&access_token=tok456&api_key=key000&page=2 -> https://example.com".to_string(),
+            headers: HeaderMap::new(),
+            captured: Vec::new(),
+            total: 0,
+        };
+
+        pending.write_redacted(&[]);
+
+        let log = std::fs::read_to_string(&pending.capture.path).unwrap();
+        for secret in ["abc123", "xyz789", "tok456", "key000"] {
+            assert!(!log.contains(secret), "{} was logged: {}", secret, log);
+        }
+        assert!(log.contains("/oauth/callback?code=[REDACTED]"));
+        assert!(log.contains("page=2 -> https://example.com"));
+    }
+}
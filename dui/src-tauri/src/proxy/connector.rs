@@ -0,0 +1,126 @@
+use hyper::client::connect::{Connected, Connection};
+use hyper::client::HttpConnector;
+use hyper::service::Service;
+use hyper::Uri;
+use hyper_tls::{HttpsConnector, MaybeHttpsStream};
+use std::future::Future;
+use std::io::IoSlice;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// HTTPS connector that tells hyper when the target agreed to HTTP/2 via ALPN, which
+/// hyper-tls doesn't report itself, and counts the upstream connections it has open.
+/// Without `h2` in the connector's ALPN list every connection stays on HTTP/1.1.
+#[derive(Debug, Clone)]
+pub struct AlpnConnector {
+    inner: HttpsConnector<HttpConnector>,
+    open_connections: Arc<AtomicUsize>,
+}
+
+impl AlpnConnector {
+    pub(super) fn new(
+        inner: HttpsConnector<HttpConnector>,
+        open_connections: Arc<AtomicUsize>,
+    ) -> Self {
+        Self {
+            inner,
+            open_connections,
+        }
+    }
+}
+
+/// Upstream connection that reports the protocol negotiated during the TLS handshake
+pub struct AlpnStream {
+    inner: MaybeHttpsStream<TcpStream>,
+    // Decremented when hyper drops the connection, whether it was in use or idle in the pool
+    open_connections: Arc<AtomicUsize>,
+}
+
+impl Drop for AlpnStream {
+    fn drop(&mut self) {
+        self.open_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Service<Uri> for AlpnConnector {
+    type Response = AlpnStream;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<AlpnStream, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connecting = self.inner.call(uri);
+        let open_connections = self.open_connections.clone();
+        Box::pin(async move {
+            let inner = connecting.await?;
+            open_connections.fetch_add(1, Ordering::Relaxed);
+            Ok(AlpnStream {
+                inner,
+                open_connections,
+            })
+        })
+    }
+}
+
+impl Connection for AlpnStream {
+    fn connected(&self) -> Connected {
+        let connected = self.inner.connected();
+        match &self.inner {
+            MaybeHttpsStream::Https(tls)
+                if tls.get_ref().negotiated_alpn().ok().flatten().as_deref() == Some(b"h2") =>
+            {
+                connected.negotiated_h2()
+            }
+            _ => connected,
+        }
+    }
+}
+
+impl AsyncRead for AlpnStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for AlpnStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,47 @@
+use http::header::{
+    ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ORIGIN,
+};
+use http::{HeaderMap, HeaderValue, Response};
+use hyper::Body;
+
+const ALLOW_METHODS: &str = "GET, POST, PUT, PATCH, DELETE, HEAD, OPTIONS";
+// Preflight results may be cached by the webview for this many seconds
+const PREFLIGHT_MAX_AGE: &str = "600";
+
+/// Echo the request's Origin so credentialed requests work; `*` when there is none
+fn allow_origin(request_headers: &HeaderMap) -> HeaderValue {
+    request_headers
+        .get(ORIGIN)
+        .cloned()
+        .unwrap_or_else(|| HeaderValue::from_static("*"))
+}
+
+/// Add permissive CORS headers to a proxied response, keeping any the target already sent
+pub(super) fn add_cors_headers(response_headers: &mut HeaderMap, request_headers: &HeaderMap) {
+    response_headers
+        .entry(ACCESS_CONTROL_ALLOW_ORIGIN)
+        .or_insert_with(|| allow_origin(request_headers));
+    response_headers
+        .entry(ACCESS_CONTROL_ALLOW_METHODS)
+        .or_insert_with(|| HeaderValue::from_static(ALLOW_METHODS));
+    response_headers
+        .entry(ACCESS_CONTROL_ALLOW_HEADERS)
+        .or_insert_with(|| {
+            request_headers
+                .get(ACCESS_CONTROL_REQUEST_HEADERS)
+                .cloned()
+                .unwrap_or_else(|| HeaderValue::from_static("*"))
+        });
+}
+
+/// Answer a CORS preflight without contacting the target
+pub(super) fn preflight_response(request_headers: &HeaderMap) -> Response<Body> {
+    let mut response = Response::builder()
+        .status(204)
+        .header(ACCESS_CONTROL_MAX_AGE, PREFLIGHT_MAX_AGE)
+        .body(Body::empty())
+        .unwrap();
+    add_cors_headers(response.headers_mut(), request_headers);
+    response
+}
@@ -0,0 +1,106 @@
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use http::HeaderMap;
+use std::io::Read;
+
+/// Content codings from the Content-Encoding header(s), in the order they were applied
+pub(super) fn content_encodings(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .get_all(hyper::header::CONTENT_ENCODING)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .map(|e| e.trim().to_ascii_lowercase())
+        .filter(|e| !e.is_empty() && e != "identity")
+        .collect()
+}
+
+/// Why `decode` could not produce a body
+#[derive(Debug)]
+pub(super) enum DecodeError {
+    /// The decoded body would be larger than the limit
+    TooLarge,
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::TooLarge => write!(f, "decoded body exceeds the size limit"),
+            DecodeError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Read `reader` to the end, stopping as soon as it yields more than `limit` bytes so a
+/// small compressed body can't expand without bound
+fn read_all(reader: impl Read, limit: u64) -> Result<Vec<u8>, DecodeError> {
+    let mut decoded = Vec::new();
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut decoded)
+        .map_err(DecodeError::Io)?;
+    if decoded.len() as u64 > limit {
+        return Err(DecodeError::TooLarge);
+    }
+    Ok(decoded)
+}
+
+/// Undo each content coding in reverse order of application. Every stage is capped at
+/// `limit` decoded bytes.
+pub(super) fn decode(
+    encodings: &[String],
+    body: &[u8],
+    limit: u64,
+) -> Result<Vec<u8>, DecodeError> {
+    let mut data = body.to_vec();
+    for encoding in encodings.iter().rev() {
+        data = match encoding.as_str() {
+            "gzip" | "x-gzip" => read_all(GzDecoder::new(&data[..]), limit)?,
+            // Should be zlib-wrapped, but some servers send raw deflate
+            "deflate" => match read_all(ZlibDecoder::new(&data[..]), limit) {
+                Err(DecodeError::Io(_)) => read_all(DeflateDecoder::new(&data[..]), limit)?,
+                result => result?,
+            },
+            other => {
+                return Err(DecodeError::Io(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    format!("Unsupported content encoding: {}", other),
+                )))
+            }
+        };
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn gzip_bomb_stops_at_limit() {
+        let bomb = gzip(&vec![0u8; 10 * 1024 * 1024]);
+        assert!(bomb.len() < 64 * 1024);
+
+        let result = decode(&["gzip".to_string()], &bomb, 1024 * 1024);
+
+        assert!(matches!(result, Err(DecodeError::TooLarge)));
+    }
+
+    #[test]
+    fn body_within_limit_is_decoded() {
+        let body = gzip(b"hello world");
+
+        let decoded = decode(&["gzip".to_string()], &body, 11).unwrap();
+
+        assert_eq!(decoded, b"hello world");
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Consecutive failures after which a target is moved behind the others
+const DEPRIORITIZE_AFTER_FAILURES: u32 = 3;
+// How long a failing target stays at the back before it gets another chance
+const DEPRIORITIZE_DURATION: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default)]
+struct FailoverTargetState {
+    consecutive_failures: u32,
+    deprioritized_until: Option<Instant>,
+}
+
+/// Tracks failing upstream targets so requests try the healthy ones first
+#[derive(Debug, Default)]
+pub struct FailoverTracker {
+    targets: Mutex<HashMap<String, FailoverTargetState>>,
+}
+
+impl FailoverTracker {
+    /// `targets` in preference order: configured order, except that deprioritized
+    /// targets go last
+    pub fn order(&self, targets: &[String]) -> Vec<String> {
+        let now = Instant::now();
+        let states = self.targets.lock().unwrap();
+        let mut ordered = targets.to_vec();
+        // Stable sort keeps the configured order within each group
+        ordered.sort_by_key(|target| {
+            states
+                .get(target)
+                .and_then(|state| state.deprioritized_until)
+                .is_some_and(|until| until > now)
+        });
+        ordered
+    }
+
+    pub fn record_success(&self, target: &str) {
+        self.targets.lock().unwrap().remove(target);
+    }
+
+    /// Returns true if this failure got the target deprioritized
+    pub fn record_failure(&self, target: &str) -> bool {
+        let mut states = self.targets.lock().unwrap();
+        let state = states.entry(target.to_string()).or_default();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures < DEPRIORITIZE_AFTER_FAILURES {
+            return false;
+        }
+        state.consecutive_failures = 0;
+        state.deprioritized_until = Some(Instant::now() + DEPRIORITIZE_DURATION);
+        true
+    }
+
+    pub fn reset(&self) {
+        self.targets.lock().unwrap().clear();
+    }
+}
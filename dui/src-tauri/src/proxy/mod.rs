@@ -1,36 +1,104 @@
+use crate::config::{DuiConfig, ProxyConfig, ProxyRoute};
 use crate::logging::{AccessLogEntry, AccessLogger};
 use chrono::Utc;
-use futures_util::{SinkExt, StreamExt};
-use http::{Request, Response};
+use futures_util::StreamExt;
+use http::{HeaderMap, HeaderName, HeaderValue, Method, Request, Response};
+use hyper::server::conn::AddrStream;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Client, Server};
 use hyper_tls::HttpsConnector;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
+use std::collections::{BTreeMap, VecDeque};
 use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tower::ServiceBuilder;
 use tower_http::trace::TraceLayer;
 
-const FALLBACK_PORTS: &[u16] = &[
+mod body_capture;
+mod connector;
+mod cors;
+mod encoding;
+mod failover;
+mod rate_limit;
+mod stats;
+mod websocket;
+
+use body_capture::BodyCapture;
+pub use body_capture::DEBUG_LOG_FILE_NAME;
+use connector::AlpnConnector;
+use failover::FailoverTracker;
+use stats::{ProxyCounters, TargetHealthTracker};
+pub use stats::{ProxyStats, ProxyTargetHealth};
+
+pub(crate) const FALLBACK_PORTS: &[u16] = &[
     45000, 45001, 45002, 45003, 45004, 45005, 45006, 45007, 45008, 45009,
 ];
-const DEFAULT_TARGET: &str = "https://chat.beyondbetter.app";
+pub(crate) const DEFAULT_TARGET: &str = "https://chat.beyondbetter.app";
+// The proxy listener itself only speaks plain HTTP on localhost
+const PROXY_SCHEME: &str = "http";
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+// Headers the proxy manages itself, which dui.proxyExtraHeaders may not set
+const RESERVED_EXTRA_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "transfer-encoding",
+    "connection",
+    "upgrade",
+];
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+// Only covers connecting and receiving response headers; bodies stream without a total limit
+const RESPONSE_HEADERS_TIMEOUT: Duration = Duration::from_secs(10);
+// A streaming body is abandoned if the upstream sends nothing for this long
+const BODY_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+// /_health?deep=1 has to answer quickly enough to be usable as a readiness probe
+const DEEP_HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+// Upper bounds for benchmark_proxy so a typo can't flood the target
+const MAX_BENCHMARK_REQUESTS: usize = 500;
+const MAX_BENCHMARK_CONCURRENCY: usize = 20;
+// The proxy answers /_health itself, so benchmark_proxy uses a path it forwards
+const BENCHMARK_PATH: &str = "/";
 const MAINTENANCE_HTML: &str = include_str!("maintenance.html");
+// Failed requests kept in memory for the status page
+const MAX_RECENT_ERRORS: usize = 20;
+// Appended to a streamed response that is cut off at the body size limit
+const RESPONSE_TRUNCATED_NOTICE: &str =
+    "\n\n[Beyond Better proxy: response truncated, body size limit exceeded]\n";
 
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct HttpProxy {
-    client: Client<HttpsConnector<hyper::client::HttpConnector>>,
+    client: Client<AlpnConnector>,
+    // Upstream connections opened by `client` and not yet closed
+    open_connections: Arc<AtomicUsize>,
     pub(crate) target_url: Arc<RwLock<String>>,
+    // Tried in order after target_url when it can't be reached
+    pub(crate) fallback_targets: Arc<RwLock<Vec<String>>>,
+    pub(crate) routes: Arc<RwLock<Vec<ProxyRoute>>>,
+    pub(crate) max_retries: u32,
+    pub(crate) decompress: bool,
+    // dui.proxyInjectCors: answer preflights and add CORS headers to responses
+    pub(crate) inject_cors: bool,
     pub(crate) port: u16,
-    access_logger: Arc<RwLock<AccessLogger>>,
+    access_logger: Arc<AccessLogger>,
+    recent_errors: Arc<RwLock<VecDeque<AccessLogEntry>>>,
     pub(crate) debug_mode: Arc<RwLock<bool>>,
+    pub(crate) maintenance_mode: Arc<RwLock<bool>>,
+    pub(crate) body_limits: Arc<RwLock<BodyLimits>>,
+    pub(crate) extra_headers: Arc<RwLock<HeaderMap>>,
+    // Bytes of each request/response body written to proxy-debug.log; None when disabled
+    body_capture_bytes: Arc<RwLock<Option<usize>>>,
     server_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+    counters: Arc<ProxyCounters>,
+    target_health: Arc<TargetHealthTracker>,
+    failover: Arc<FailoverTracker>,
+    started_at: Arc<RwLock<Option<Instant>>>,
 }
 
 // Implement Clone manually since JoinHandle doesn't implement Clone
@@ -38,58 +106,527 @@ impl Clone for HttpProxy {
     fn clone(&self) -> Self {
         Self {
             client: self.client.clone(),
+            open_connections: self.open_connections.clone(),
             target_url: self.target_url.clone(),
+            fallback_targets: self.fallback_targets.clone(),
+            routes: self.routes.clone(),
+            max_retries: self.max_retries,
+            decompress: self.decompress,
+            inject_cors: self.inject_cors,
             port: self.port,
             access_logger: self.access_logger.clone(),
+            recent_errors: self.recent_errors.clone(),
             debug_mode: self.debug_mode.clone(),
+            maintenance_mode: self.maintenance_mode.clone(),
+            body_limits: self.body_limits.clone(),
+            extra_headers: self.extra_headers.clone(),
+            body_capture_bytes: self.body_capture_bytes.clone(),
             server_handle: self.server_handle.clone(),
+            counters: self.counters.clone(),
+            target_health: self.target_health.clone(),
+            failover: self.failover.clone(),
+            started_at: self.started_at.clone(),
         }
     }
 }
 
+/// Largest request and response bodies the proxy will pass through
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct BodyLimits {
+    pub max_request_bytes: u64,
+    pub max_response_bytes: u64,
+}
+
+/// Progress of a size-limited body stream
+enum LimitedBody {
+    Streaming(Body, u64),
+    /// Limit hit after sending the notice chunk; fail the stream next
+    Exceeded,
+}
+
+/// Request details shared by every access log entry written for one proxied request
+struct RequestContext {
+    method: String,
+    path: String,
+    target: String,
+    route: Option<String>,
+    start_time: Instant,
+    request_bytes: Option<u64>,
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct ProxyInfo {
     pub port: u16,
     pub target: String,
+    pub fallback_targets: Vec<String>,
+    /// The target requests currently go to first, which differs from `target` while it
+    /// is failing
+    pub active_target: String,
     pub is_running: bool,
 }
 
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct BenchmarkResult {
+    pub requests: usize,
+    pub concurrency: usize,
+    /// Requests sent through the proxy listener
+    pub proxied: BenchmarkRun,
+    /// The same requests sent straight to the target, for comparison
+    pub direct: BenchmarkRun,
+    /// Median latency the proxy adds over a direct connection
+    pub overhead_p50_ms: f64,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct BenchmarkRun {
+    pub url: String,
+    pub successes: usize,
+    pub errors: usize,
+    pub error_rate: f64,
+    pub total_duration_ms: u64,
+    pub throughput_rps: f64,
+    pub latency_min_ms: f64,
+    pub latency_p50_ms: f64,
+    pub latency_p90_ms: f64,
+    pub latency_p99_ms: f64,
+    pub latency_max_ms: f64,
+}
+
 impl HttpProxy {
+    /// The target saved by set_proxy_target (`dui.proxyTarget`), else `proxy.target`, else
+    /// the default. A saved target that isn't an HTTPS URL is skipped.
+    pub(crate) fn configured_target(proxy_config: &ProxyConfig, dui_config: &DuiConfig) -> String {
+        [&dui_config.proxy_target, &proxy_config.target]
+            .into_iter()
+            .flatten()
+            .find(|target| match Self::validate_target(target) {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Ignoring invalid proxy target: {}", e);
+                    false
+                }
+            })
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_TARGET.to_string())
+    }
+
+    /// Proxy debug logging as saved by set_debug_mode, defaulting to the build's setting
+    pub(crate) fn configured_debug_mode(dui_config: &DuiConfig) -> bool {
+        dui_config
+            .proxy_debug_mode
+            .unwrap_or(cfg!(debug_assertions))
+    }
+
     pub async fn new(log_dir: std::path::PathBuf) -> std::io::Result<Self> {
-        let debug_mode = Arc::new(RwLock::new(cfg!(debug_assertions))); // Default to compile-time setting
+        let (proxy_config, dui_config) = match crate::config::read_global_config() {
+            Ok(config) => (config.proxy, config.dui),
+            Err(e) => {
+                warn!("Failed to read config for proxy settings: {}", e);
+                (ProxyConfig::default(), DuiConfig::default())
+            }
+        };
+        let debug_mode = Arc::new(RwLock::new(Self::configured_debug_mode(&dui_config)));
+        let routes = Self::load_routes(&proxy_config);
+        let extra_headers = Self::parse_extra_headers(&dui_config.proxy_extra_headers)
+            .unwrap_or_else(|e| {
+                warn!("Ignoring dui.proxyExtraHeaders: {}", e);
+                HeaderMap::new()
+            });
+        let port = Self::select_port(dui_config.proxy_port)?;
+        info!("Starting proxy server on port {}", port);
+        let open_connections = Arc::new(AtomicUsize::new(0));
+
+        Ok(Self {
+            client: Self::build_client(&dui_config, open_connections.clone())?,
+            open_connections,
+            target_url: Arc::new(RwLock::new(Self::configured_target(
+                &proxy_config,
+                &dui_config,
+            ))),
+            fallback_targets: Arc::new(RwLock::new(Self::load_fallback_targets(&proxy_config))),
+            routes: Arc::new(RwLock::new(routes)),
+            max_retries: proxy_config.max_retries,
+            decompress: dui_config.proxy_decompress,
+            inject_cors: dui_config.proxy_inject_cors,
+            port,
+            access_logger: Arc::new(AccessLogger::new(
+                log_dir,
+                debug_mode.clone(),
+                proxy_config.access_log_max_bytes,
+            )?),
+            recent_errors: Arc::new(RwLock::new(VecDeque::with_capacity(MAX_RECENT_ERRORS))),
+            debug_mode,
+            maintenance_mode: Arc::new(RwLock::new(false)),
+            body_limits: Arc::new(RwLock::new(BodyLimits {
+                max_request_bytes: proxy_config.max_request_body_bytes,
+                max_response_bytes: proxy_config.max_response_body_bytes,
+            })),
+            extra_headers: Arc::new(RwLock::new(extra_headers)),
+            body_capture_bytes: Arc::new(RwLock::new(None)),
+            server_handle: Arc::new(RwLock::new(None)),
+            counters: Arc::new(ProxyCounters::default()),
+            target_health: Arc::new(TargetHealthTracker::default()),
+            failover: Arc::new(FailoverTracker::default()),
+            started_at: Arc::new(RwLock::new(None)),
+        })
+    }
 
-        // Try ports until one works
-        for &port in FALLBACK_PORTS {
+    /// WebSocket upgrades don't go through this client: websocket::connect opens its own
+    /// HTTP/1.1 connection, so they keep working when HTTP/2 is enabled
+    fn build_client(
+        dui_config: &DuiConfig,
+        open_connections: Arc<AtomicUsize>,
+    ) -> std::io::Result<Client<AlpnConnector>> {
+        debug!("Creating HTTP connector with HTTPS support");
+        let mut http = hyper::client::HttpConnector::new();
+        http.enforce_http(false);
+        debug!("Creating HTTPS connector with TLS support");
+        let mut tls = native_tls::TlsConnector::builder();
+        if dui_config.proxy_accept_invalid_certs {
+            warn!(
+                "INSECURE: dui.proxyAcceptInvalidCerts is enabled, proxy will not verify TLS certificates of the target"
+            );
+            tls.danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+        if dui_config.proxy_http2 {
+            info!("dui.proxyHttp2 is enabled, offering HTTP/2 to HTTPS targets");
+            tls.request_alpns(&["h2", "http/1.1"]);
+        }
+        let tls = tls.build().map_err(std::io::Error::other)?;
+        let https = HttpsConnector::from((http, tokio_native_tls::TlsConnector::from(tls)));
+        let idle_timeout = match dui_config.proxy_pool_idle_timeout_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        debug!(
+            "Building client with HTTPS/TLS support (max {} idle connections per host, idle timeout {:?})",
+            dui_config.proxy_pool_max_idle, idle_timeout
+        );
+        Ok(Client::builder()
+            .pool_max_idle_per_host(dui_config.proxy_pool_max_idle)
+            .pool_idle_timeout(idle_timeout)
+            .build::<_, hyper::Body>(AlpnConnector::new(https, open_connections)))
+    }
+
+    /// Pick the listen port: the configured `dui.proxyPort` first, then the fallback range,
+    /// then an OS-assigned ephemeral port
+    fn select_port(preferred_port: Option<u16>) -> std::io::Result<u16> {
+        if let Some(port) = preferred_port.filter(|&port| port != 0) {
             if Self::is_port_available(port) {
-                info!("Starting proxy server on port {}", port);
-
-                return Ok(Self {
-                    client: {
-                        debug!("Creating HTTP connector with HTTPS support");
-                        let mut http = hyper::client::HttpConnector::new();
-                        http.enforce_http(false);
-                        debug!("Creating HTTPS connector with TLS support");
-                        let https = HttpsConnector::new_with_connector(http);
-                        debug!("Building client with HTTPS/TLS support");
-                        Client::builder().build::<_, hyper::Body>(https)
-                    },
-                    target_url: Arc::new(RwLock::new(DEFAULT_TARGET.to_string())),
-                    port,
-                    access_logger: Arc::new(RwLock::new(AccessLogger::new(
-                        log_dir,
-                        debug_mode.clone(),
-                    )?)),
-                    debug_mode,
-                    server_handle: Arc::new(RwLock::new(None)),
-                });
+                return Ok(port);
+            }
+            warn!("Configured proxy port {} is not available", port);
+        }
+
+        if let Some(&port) = FALLBACK_PORTS
+            .iter()
+            .find(|&&port| Self::is_port_available(port))
+        {
+            return Ok(port);
+        }
+
+        warn!(
+            "No available ports in range {:?}, requesting an ephemeral port",
+            FALLBACK_PORTS
+        );
+        match std::net::TcpListener::bind("127.0.0.1:0").and_then(|l| l.local_addr()) {
+            Ok(addr) => Ok(addr.port()),
+            Err(e) => {
+                error!("No available ports found for proxy: {}", e);
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::AddrInUse,
+                    format!(
+                        "No available ports in range {:?} and no ephemeral port: {}",
+                        FALLBACK_PORTS, e
+                    ),
+                ))
+            }
+        }
+    }
+
+    /// Load path-prefix routes from the proxy config, dropping any that fail validation
+    fn load_routes(proxy_config: &ProxyConfig) -> Vec<ProxyRoute> {
+        let mut routes = Vec::new();
+        for route in &proxy_config.routes {
+            match Self::validate_route(route) {
+                Ok(route) => {
+                    info!(
+                        "Proxy route configured: {} -> {}",
+                        route.path_prefix, route.target
+                    );
+                    routes.push(route);
+                }
+                Err(e) => error!("Ignoring invalid proxy route {:?}: {}", route, e),
+            }
+        }
+        routes
+    }
+
+    pub(crate) fn validate_route(route: &ProxyRoute) -> Result<ProxyRoute, String> {
+        if !route.path_prefix.starts_with('/') {
+            return Err(format!(
+                "Path prefix must start with '/': {}",
+                route.path_prefix
+            ));
+        }
+
+        Self::validate_target(&route.target)?;
+
+        Ok(ProxyRoute {
+            path_prefix: route.path_prefix.clone(),
+            // Request paths always start with '/', so drop any trailing slash from the target
+            target: route.target.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Targets must be HTTPS URLs, whether primary, fallback or per route
+    pub(crate) fn validate_target(target: &str) -> Result<(), String> {
+        let parsed_url = reqwest::Url::parse(target)
+            .map_err(|e| format!("Invalid target URL {}: {}", target, e))?;
+        if parsed_url.scheme() != "https" {
+            return Err(format!(
+                "Invalid URL scheme for {}: {}. Only HTTPS URLs are allowed.",
+                target,
+                parsed_url.scheme()
+            ));
+        }
+        Ok(())
+    }
+
+    fn load_fallback_targets(proxy_config: &ProxyConfig) -> Vec<String> {
+        proxy_config
+            .fallback_targets
+            .iter()
+            .filter(|target| match Self::validate_target(target) {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Ignoring invalid fallback target: {}", e);
+                    false
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Validate configured extra headers, refusing names the proxy sets itself
+    pub(crate) fn parse_extra_headers(
+        headers: &BTreeMap<String, String>,
+    ) -> Result<HeaderMap, String> {
+        let mut parsed = HeaderMap::new();
+        for (name, value) in headers {
+            let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+                .map_err(|_| format!("Invalid header name: {:?}", name))?;
+            if RESERVED_EXTRA_HEADERS.contains(&header_name.as_str()) {
+                return Err(format!("Header {} is managed by the proxy", header_name));
             }
+            let header_value = HeaderValue::from_str(value)
+                .map_err(|_| format!("Invalid value for header {}", header_name))?;
+            parsed.insert(header_name, header_value);
+        }
+        Ok(parsed)
+    }
+
+    fn path_matches_prefix(path: &str, prefix: &str) -> bool {
+        let prefix = prefix.trim_end_matches('/');
+        if prefix.is_empty() {
+            return true;
+        }
+        // Match on path segment boundaries so "/api" doesn't capture "/apix"
+        path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
+
+    /// Select the target for a request path by longest matching route prefix,
+    /// falling back to the default target. Returns the target and matched prefix.
+    async fn resolve_target(&self, path: &str) -> (String, Option<String>) {
+        let routes = self.routes.read().await;
+        let matched = routes
+            .iter()
+            .filter(|route| Self::path_matches_prefix(path, &route.path_prefix))
+            .max_by_key(|route| route.path_prefix.trim_end_matches('/').len());
+
+        match matched {
+            Some(route) => (route.target.clone(), Some(route.path_prefix.clone())),
+            None => (self.target_url.read().await.clone(), None),
+        }
+    }
+
+    /// Check that `{target}/_health` answers, so the proxy only reports healthy when
+    /// requests can actually get through
+    async fn deep_health_check(&self) -> Response<Body> {
+        let target = self.target_url.read().await.clone();
+        let url = format!("{}/_health", target.trim_end_matches('/'));
+        let result = match Request::builder()
+            .method(Method::GET)
+            .uri(&url)
+            .body(Body::empty())
+        {
+            Ok(req) => {
+                match tokio::time::timeout(DEEP_HEALTH_TIMEOUT, self.client.request(req)).await {
+                    Ok(Ok(resp)) if resp.status().is_success() => Ok(()),
+                    Ok(Ok(resp)) => Err(format!("Target returned {}", resp.status())),
+                    Ok(Err(e)) => Err(e.to_string()),
+                    Err(_) => Err(format!(
+                        "Target did not respond within {}s",
+                        DEEP_HEALTH_TIMEOUT.as_secs()
+                    )),
+                }
+            }
+            Err(e) => Err(format!("Invalid health check URL {}: {}", url, e)),
+        };
+
+        match result {
+            Ok(()) => Response::builder()
+                .status(200)
+                .body(Body::from("OK"))
+                .unwrap(),
+            Err(e) => {
+                warn!("Deep health check of {} failed: {}", url, e);
+                Response::builder()
+                    .status(503)
+                    .body(Body::from(format!("Upstream unavailable: {}", e)))
+                    .unwrap()
+            }
+        }
+    }
+
+    /// Fire `requests` GETs at BENCHMARK_PATH through the proxy listener, `concurrency` at a
+    /// time, then the same load straight at the target, and summarise both runs.
+    pub async fn benchmark(
+        &self,
+        requests: usize,
+        concurrency: usize,
+    ) -> Result<BenchmarkResult, String> {
+        if requests == 0 || requests > MAX_BENCHMARK_REQUESTS {
+            return Err(format!(
+                "Request count must be between 1 and {}",
+                MAX_BENCHMARK_REQUESTS
+            ));
+        }
+        if concurrency == 0 || concurrency > MAX_BENCHMARK_CONCURRENCY {
+            return Err(format!(
+                "Concurrency must be between 1 and {}",
+                MAX_BENCHMARK_CONCURRENCY
+            ));
         }
+        if !self.is_running().await {
+            return Err("Proxy is not running".to_string());
+        }
+
+        let proxied_url = format!(
+            "{}://127.0.0.1:{}{}",
+            PROXY_SCHEME, self.port, BENCHMARK_PATH
+        );
+        let (target, _) = self.resolve_target(BENCHMARK_PATH).await;
+        let direct_url = format!("{}{}", target.trim_end_matches('/'), BENCHMARK_PATH);
+
+        info!(
+            "Benchmarking proxy {} against {} with {} requests at concurrency {}",
+            proxied_url, direct_url, requests, concurrency
+        );
+
+        // A separate client so loopback connections don't count as upstream ones
+        let proxied =
+            Self::benchmark_run(&Client::new(), proxied_url, requests, concurrency).await?;
+        let direct = Self::benchmark_run(&self.client, direct_url, requests, concurrency).await?;
+
+        let result = BenchmarkResult {
+            requests,
+            concurrency,
+            overhead_p50_ms: proxied.latency_p50_ms - direct.latency_p50_ms,
+            proxied,
+            direct,
+        };
+
+        info!(
+            "Benchmark complete: proxied {}/{} succeeded, {:.1} req/s, p50 {:.1}ms; direct {}/{} succeeded, {:.1} req/s, p50 {:.1}ms",
+            result.proxied.successes,
+            result.requests,
+            result.proxied.throughput_rps,
+            result.proxied.latency_p50_ms,
+            result.direct.successes,
+            result.requests,
+            result.direct.throughput_rps,
+            result.direct.latency_p50_ms
+        );
+
+        Ok(result)
+    }
+
+    /// Send `requests` GETs to `url` with `client`, `concurrency` at a time
+    async fn benchmark_run<C>(
+        client: &Client<C>,
+        url: String,
+        requests: usize,
+        concurrency: usize,
+    ) -> Result<BenchmarkRun, String>
+    where
+        C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
+    {
+        let uri: hyper::Uri = url
+            .parse()
+            .map_err(|e| format!("Invalid benchmark URL {}: {}", url, e))?;
+
+        let started = Instant::now();
+        let outcomes: Vec<Option<Duration>> = futures_util::stream::iter(0..requests)
+            .map(|_| {
+                let client = client.clone();
+                let uri = uri.clone();
+                async move {
+                    let req = Request::builder()
+                        .method(Method::GET)
+                        .uri(uri)
+                        .body(Body::empty())
+                        .ok()?;
+                    let request_start = Instant::now();
+                    match tokio::time::timeout(Duration::from_secs(10), client.request(req)).await {
+                        Ok(Ok(resp)) if resp.status().is_success() => {
+                            // Drain the body so the connection can be reused
+                            let _ = hyper::body::to_bytes(resp.into_body()).await;
+                            Some(request_start.elapsed())
+                        }
+                        _ => None,
+                    }
+                }
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+        let total_duration = started.elapsed();
+
+        let mut latencies: Vec<f64> = outcomes
+            .iter()
+            .flatten()
+            .map(|d| d.as_secs_f64() * 1000.0)
+            .collect();
+        latencies.sort_by(|a, b| a.total_cmp(b));
+
+        let successes = latencies.len();
+        let errors = requests - successes;
+        let percentile = |p: f64| -> f64 {
+            if latencies.is_empty() {
+                return 0.0;
+            }
+            let idx = ((p / 100.0) * (latencies.len() - 1) as f64).round() as usize;
+            latencies[idx]
+        };
 
-        error!("No available ports found in range {:?}", FALLBACK_PORTS);
-        Err(std::io::Error::new(
-            std::io::ErrorKind::AddrInUse,
-            format!("No available ports in range {:?}", FALLBACK_PORTS),
-        ))
+        Ok(BenchmarkRun {
+            url,
+            successes,
+            errors,
+            error_rate: errors as f64 / requests as f64,
+            total_duration_ms: total_duration.as_millis() as u64,
+            throughput_rps: successes as f64 / total_duration.as_secs_f64().max(f64::EPSILON),
+            latency_min_ms: percentile(0.0),
+            latency_p50_ms: percentile(50.0),
+            latency_p90_ms: percentile(90.0),
+            latency_p99_ms: percentile(99.0),
+            latency_max_ms: percentile(100.0),
+        })
     }
 
     fn is_port_available(port: u16) -> bool {
@@ -105,11 +642,322 @@ impl HttpProxy {
         if let Some(h) = handle.take() {
             debug!("Stopping proxy server");
             h.abort();
+            *self.started_at.write().await = None;
             info!("Proxy server stopped");
         }
         Ok(())
     }
 
+    /// Location of the JSON lines access log currently being written
+    pub async fn access_log_path(&self) -> std::path::PathBuf {
+        self.access_logger.log_path()
+    }
+
+    /// Close the access log file until `set_log_dir`, e.g. while logs are being moved
+    pub async fn close_access_log(&self) -> std::io::Result<()> {
+        self.access_logger.close().await
+    }
+
+    /// Point the access log at a new directory, e.g. after logs have been relocated
+    pub async fn set_log_dir(&self, log_dir: std::path::PathBuf) -> std::io::Result<()> {
+        self.access_logger.set_log_dir(log_dir).await
+    }
+
+    /// Start or stop writing request and response bodies (up to `max_bytes` each) to
+    /// proxy-debug.log next to the access log
+    pub async fn set_body_capture(&self, max_bytes: Option<usize>) {
+        *self.body_capture_bytes.write().await = max_bytes;
+    }
+
+    /// Capture settings for a new request; capture stops as soon as debug mode is turned off
+    async fn body_capture(&self) -> Option<BodyCapture> {
+        let max_bytes = (*self.body_capture_bytes.read().await)?;
+        if !*self.debug_mode.read().await {
+            return None;
+        }
+        Some(BodyCapture {
+            max_bytes,
+            path: self
+                .access_log_path()
+                .await
+                .with_file_name(DEBUG_LOG_FILE_NAME),
+        })
+    }
+
+    /// Snapshot of request counters since the proxy was last started
+    pub async fn stats(&self) -> ProxyStats {
+        let uptime_secs = self
+            .started_at
+            .read()
+            .await
+            .map(|started| started.elapsed().as_secs())
+            .unwrap_or(0);
+        self.counters.snapshot(
+            uptime_secs,
+            self.is_running().await,
+            self.open_connections.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Append `client_ip` to any existing X-Forwarded-For chain (which may span several header lines)
+    fn forwarded_for(headers: &http::HeaderMap, client_ip: &str) -> String {
+        let mut chain: Vec<&str> = headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|h| h.to_str().ok())
+            .flat_map(|h| h.split(','))
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+            .collect();
+        chain.push(client_ip);
+        chain.join(", ")
+    }
+
+    /// The most recent failed requests, newest first
+    pub async fn recent_errors(&self) -> Vec<AccessLogEntry> {
+        self.recent_errors
+            .read()
+            .await
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    }
+
+    /// Live connection health of the default target, based on recent request outcomes
+    pub async fn target_health(&self) -> ProxyTargetHealth {
+        let target = self.target_url.read().await.clone();
+        let mut health = self.target_health.snapshot(target);
+        health.serving_maintenance |= *self.maintenance_mode.read().await;
+        health
+    }
+
+    /// Forget recorded outcomes, e.g. after switching to a different target
+    pub fn reset_target_health(&self) {
+        self.target_health.reset();
+        self.failover.reset();
+    }
+
+    /// The default target followed by the fallbacks, failing targets moved to the back
+    async fn failover_targets(&self) -> Vec<String> {
+        let mut targets = vec![self.target_url.read().await.clone()];
+        targets.extend(self.fallback_targets.read().await.iter().cloned());
+        self.failover.order(&targets)
+    }
+
+    /// Where requests for the default target are currently sent first
+    pub async fn active_target(&self) -> String {
+        self.failover_targets()
+            .await
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+    }
+
+    /// Host header value for `url`, including a non-default port
+    fn host_header(url: &str) -> Option<String> {
+        let parsed_url = reqwest::Url::parse(url).ok()?;
+        let host = parsed_url.host_str()?;
+        Some(match parsed_url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        })
+    }
+
+    /// Hand the client a plaintext body when the upstream still applied a content coding.
+    /// Encoded bodies are buffered to decode them, up to `limit` decoded bytes; plain ones
+    /// stream through as usual.
+    async fn decompress_response(resp: Response<Body>, limit: u64) -> Response<Body> {
+        let (mut parts, body) = resp.into_parts();
+        let encodings = encoding::content_encodings(&parts.headers);
+        if encodings.is_empty() {
+            return Response::from_parts(parts, body);
+        }
+
+        let bytes = match hyper::body::to_bytes(body).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                error!("Failed to read response body for decompression: {}", e);
+                return Response::builder()
+                    .status(502)
+                    .body(Body::from(format!(
+                        "Failed to read upstream response: {}",
+                        e
+                    )))
+                    .unwrap();
+            }
+        };
+
+        match encoding::decode(&encodings, &bytes, limit) {
+            Ok(decoded) => {
+                debug!(
+                    "Decompressed {:?} response: {} -> {} bytes",
+                    encodings,
+                    bytes.len(),
+                    decoded.len()
+                );
+                parts.headers.remove(hyper::header::CONTENT_ENCODING);
+                parts
+                    .headers
+                    .insert(hyper::header::CONTENT_LENGTH, decoded.len().into());
+                Response::from_parts(parts, Body::from(decoded))
+            }
+            Err(encoding::DecodeError::TooLarge) => {
+                warn!(
+                    "Decompressed {:?} response exceeds the proxy limit of {} bytes",
+                    encodings, limit
+                );
+                Self::response_too_large(limit)
+            }
+            Err(e) => {
+                // Leave the response exactly as received rather than corrupting it
+                warn!("Could not decompress {:?} response: {}", encodings, e);
+                Response::from_parts(parts, Body::from(bytes))
+            }
+        }
+    }
+
+    /// Wait up to `headers_timeout` for the upstream response headers, then stream its body
+    /// with only the `idle_timeout` stall limit so large downloads aren't cut off
+    async fn await_response<F, E>(
+        response: F,
+        headers_timeout: Duration,
+        idle_timeout: Duration,
+    ) -> Result<Result<Response<Body>, E>, tokio::time::error::Elapsed>
+    where
+        F: std::future::Future<Output = Result<Response<Body>, E>>,
+    {
+        let result = tokio::time::timeout(headers_timeout, response).await?;
+        Ok(result.map(|resp| {
+            let (parts, body) = resp.into_parts();
+            Response::from_parts(parts, Self::with_idle_timeout(body, idle_timeout))
+        }))
+    }
+
+    /// Pass the upstream body through chunk by chunk, failing only if it stalls for `idle_timeout`
+    fn with_idle_timeout(body: Body, idle_timeout: Duration) -> Body {
+        let stream = futures_util::stream::unfold(Some(body), move |body| async move {
+            let mut body = body?;
+            match tokio::time::timeout(idle_timeout, body.next()).await {
+                Ok(Some(chunk)) => Some((chunk.map_err(std::io::Error::other), Some(body))),
+                Ok(None) => None,
+                Err(_) => {
+                    warn!(
+                        "Proxy response body stalled for {:?}, aborting",
+                        idle_timeout
+                    );
+                    Some((
+                        Err(std::io::Error::new(
+                            std::io::ErrorKind::TimedOut,
+                            "Response body idle timeout",
+                        )),
+                        None,
+                    ))
+                }
+            }
+        });
+        Body::wrap_stream(stream)
+    }
+
+    /// Pass `body` through until more than `limit` bytes have gone by, then fail the stream
+    /// and set `exceeded`. A `notice` is sent first so a truncated response says why.
+    fn with_size_limit(
+        body: Body,
+        limit: u64,
+        exceeded: Arc<AtomicBool>,
+        notice: Option<&'static str>,
+    ) -> Body {
+        let stream =
+            futures_util::stream::unfold(Some(LimitedBody::Streaming(body, 0)), move |state| {
+                let exceeded = exceeded.clone();
+                async move {
+                    let (mut body, seen) = match state? {
+                        LimitedBody::Streaming(body, seen) => (body, seen),
+                        LimitedBody::Exceeded => {
+                            return Some((
+                                Err(std::io::Error::new(
+                                    std::io::ErrorKind::InvalidData,
+                                    "Body size limit exceeded",
+                                )),
+                                None,
+                            ))
+                        }
+                    };
+                    match body.next().await {
+                        Some(Ok(chunk)) => {
+                            let seen = seen + chunk.len() as u64;
+                            if seen <= limit {
+                                return Some((Ok(chunk), Some(LimitedBody::Streaming(body, seen))));
+                            }
+                            warn!("Proxy body exceeded {} byte limit, aborting", limit);
+                            exceeded.store(true, Ordering::Relaxed);
+                            match notice {
+                                Some(notice) => Some((
+                                    Ok(hyper::body::Bytes::from_static(notice.as_bytes())),
+                                    Some(LimitedBody::Exceeded),
+                                )),
+                                None => Some((
+                                    Err(std::io::Error::new(
+                                        std::io::ErrorKind::InvalidData,
+                                        "Body size limit exceeded",
+                                    )),
+                                    None,
+                                )),
+                            }
+                        }
+                        Some(Err(e)) => Some((Err(std::io::Error::other(e)), None)),
+                        None => None,
+                    }
+                }
+            });
+        Body::wrap_stream(stream)
+    }
+
+    fn content_length(headers: &http::HeaderMap) -> Option<u64> {
+        headers
+            .get(hyper::header::CONTENT_LENGTH)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.parse().ok())
+    }
+
+    fn response_too_large_message(limit: u64) -> String {
+        format!("Response body exceeds the proxy limit of {} bytes", limit)
+    }
+
+    /// Error page for an upstream response larger than `limit`
+    fn response_too_large(limit: u64) -> Response<Body> {
+        Response::builder()
+            .status(502)
+            .body(Body::from(MAINTENANCE_HTML.replace(
+                "<!--ERROR_MESSAGE-->",
+                &format!(
+                    "<p class='text-red-600 dark:text-red-400'>Error: {}</p>",
+                    Self::response_too_large_message(limit)
+                ),
+            )))
+            .unwrap()
+    }
+
+    fn payload_too_large(limit: u64) -> Response<Body> {
+        Response::builder()
+            .status(413)
+            .body(Body::from(format!(
+                "Request body exceeds the proxy limit of {} bytes",
+                limit
+            )))
+            .unwrap()
+    }
+
+    /// Only methods without side effects are safe to replay after a failed attempt
+    fn is_idempotent_method(method: &Method) -> bool {
+        matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+    }
+
+    /// Exponential backoff for retry attempts: 100ms, 300ms, 900ms, ...
+    fn retry_delay(attempt: u32) -> Duration {
+        RETRY_BASE_DELAY * 3u32.saturating_pow(attempt.saturating_sub(1))
+    }
+
     fn is_websocket_request(req: &Request<Body>) -> bool {
         req.headers()
             .get(hyper::header::UPGRADE)
@@ -122,35 +970,27 @@ impl HttpProxy {
         &self,
         req: Request<Body>,
     ) -> Result<Response<Body>, std::io::Error> {
-        let target = self.target_url.read().await.clone();
         let path = req.uri().path().to_string();
-        let query = req
-            .uri()
-            .query()
-            .map(|q| format!("?{}", q))
-            .unwrap_or_default();
-
-        // Convert http(s):// to ws(s):// for the target URL
-        let ws_target = if target.starts_with("https://") {
-            format!(
-                "wss://{}{}{}",
-                target.strip_prefix("https://").unwrap(),
-                path,
-                query
-            )
-        } else {
-            format!(
-                "ws://{}{}{}",
-                target.strip_prefix("http://").unwrap_or(&target),
-                path,
-                query
-            )
+        let (target, route) = self.resolve_target(&path).await;
+        let ws_target = match websocket::target_url(&target, &path, req.uri().query()) {
+            Ok(url) => url,
+            Err(e) => {
+                error!("Websocket: {}", e);
+                return Ok(Response::builder()
+                    .status(502)
+                    .body(Body::from(format!("WebSocket connection failed: {}", e)))
+                    .unwrap());
+            }
         };
 
-        debug!("Websocket: Upgrade request to: {}", ws_target);
+        debug!(
+            "Websocket: Upgrade request to: {} (route: {:?})",
+            ws_target, route
+        );
 
         // Create the WebSocket client connection
-        match connect_async(&ws_target).await {
+        let extra_headers = self.extra_headers.read().await.clone();
+        match websocket::connect(&ws_target, &extra_headers).await {
             Ok((ws_stream, _)) => {
                 debug!("Websocket: Connection established to target");
 
@@ -189,66 +1029,10 @@ impl HttpProxy {
                     )
                     .await;
 
-                    let (mut client_write, mut client_read) = client_ws.split();
-                    let (mut server_write, mut server_read) = ws_stream_clone.split();
-
                     // Wait a moment for the connection to stabilize
                     tokio::time::sleep(Duration::from_millis(100)).await;
 
-                    let server_to_client = tokio::spawn(async move {
-                        debug!("Websocket: Starting server to client forwarding");
-                        while let Some(Ok(msg)) = server_read.next().await {
-                            match msg {
-                                Message::Ping(data) => {
-                                    if let Err(e) = client_write.send(Message::Pong(data)).await {
-                                        error!("Websocket: Error sending pong to client: {}", e);
-                                        break;
-                                    }
-                                }
-                                Message::Pong(_) => {}
-                                Message::Close(_) => {
-                                    debug!("Websocket: Received server close");
-                                    break;
-                                }
-                                msg => {
-                                    if let Err(e) = client_write.send(msg).await {
-                                        error!("Websocket: Error forwarding to client: {}", e);
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                    });
-
-                    let client_to_server = tokio::spawn(async move {
-                        debug!("Websocket: Starting client to server forwarding");
-                        while let Some(Ok(msg)) = client_read.next().await {
-                            match msg {
-                                Message::Ping(data) => {
-                                    if let Err(e) = server_write.send(Message::Pong(data)).await {
-                                        error!("Websocket: Error sending pong to server: {}", e);
-                                        break;
-                                    }
-                                }
-                                Message::Pong(_) => {}
-                                Message::Close(_) => {
-                                    debug!("Websocket: Received client close");
-                                    break;
-                                }
-                                msg => {
-                                    if let Err(e) = server_write.send(msg).await {
-                                        error!("Websocket: Error forwarding to server: {}", e);
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                    });
-
-                    match tokio::try_join!(server_to_client, client_to_server) {
-                        Ok(_) => debug!("Websocket: Connection closed normally"),
-                        Err(e) => error!("Websocket: Connection error: {}", e),
-                    };
+                    websocket::relay(client_ws, ws_stream_clone, ws_target, extra_headers).await;
                 });
 
                 // Return upgrade response with proper WebSocket headers
@@ -293,11 +1077,14 @@ impl HttpProxy {
         // Create a new proxy instance for the service
         let proxy = self.clone();
 
-        let make_svc = make_service_fn(move |_conn| {
+        let make_svc = make_service_fn(move |conn: &AddrStream| {
             let proxy = proxy.clone();
+            let remote_addr = conn.remote_addr();
             async move {
-                let svc = service_fn(move |req| {
+                let svc = service_fn(move |mut req: Request<Body>| {
                     let proxy = proxy.clone();
+                    // Make the peer address available for X-Forwarded-For
+                    req.extensions_mut().insert(remote_addr);
                     async move { proxy.handle_request(req).await }
                 });
                 let svc = ServiceBuilder::new()
@@ -310,6 +1097,10 @@ impl HttpProxy {
         let server = Server::bind(&addr).serve(make_svc);
         info!("Proxy server listening on http://{}", addr);
 
+        // Counters cover a single run of the server
+        self.counters.reset();
+        *self.started_at.write().await = Some(Instant::now());
+
         // Store server handle for shutdown
         let handle = tokio::spawn(async move {
             if let Err(e) = server.await {
@@ -328,12 +1119,44 @@ impl HttpProxy {
         // Handle health check endpoint
         if req.uri().path() == "/_health" {
             debug!("Health check request received");
+            let deep = req
+                .uri()
+                .query()
+                .is_some_and(|q| q.split('&').any(|p| p == "deep=1" || p == "deep=true"));
+            if deep {
+                return Ok(self.deep_health_check().await);
+            }
             return Ok(Response::builder()
                 .status(200)
                 .body(Body::from("OK"))
                 .unwrap());
         }
 
+        self.counters.record_request();
+
+        // During planned maintenance every request (including WebSocket upgrades) gets the maintenance page
+        if *self.maintenance_mode.read().await {
+            debug!(
+                "Maintenance mode: refusing {} {}",
+                req.method(),
+                req.uri().path()
+            );
+            return Ok(Response::builder()
+                .status(503)
+                .header(hyper::header::CONTENT_TYPE, "text/html; charset=utf-8")
+                .body(Body::from(MAINTENANCE_HTML))
+                .unwrap());
+        }
+
+        // Dev targets without CORS support tend to reject preflights, so answer them here
+        if self.inject_cors
+            && req.method() == Method::OPTIONS
+            && headers.contains_key(hyper::header::ACCESS_CONTROL_REQUEST_METHOD)
+        {
+            debug!("Answering CORS preflight for {}", req.uri().path());
+            return Ok(cors::preflight_response(&headers));
+        }
+
         // Check for WebSocket upgrade request
         if Self::is_websocket_request(&req) {
             return self.handle_websocket_request(req).await;
@@ -342,20 +1165,34 @@ impl HttpProxy {
         let start_time = Instant::now();
         let method = req.method().to_string();
         let path = req.uri().path().to_string();
-        let target = self.target_url.read().await.clone();
+        let (target, route) = self.resolve_target(&path).await;
+        let retryable = Self::is_idempotent_method(req.method());
+
+        // Idempotent requests for the default target go to the first target in failover
+        // order and move on to the next one if it fails. Anything else always goes to the
+        // configured target, so a POST is never sent twice or to a fallback.
+        let candidates = if route.is_none() && retryable {
+            self.failover_targets().await
+        } else {
+            vec![target]
+        };
+        let target = candidates[0].clone();
 
         // Build target URL
-        let url = format!(
-            "{}{}{}",
-            target,
+        let path_and_query = format!(
+            "{}{}",
             path,
             req.uri()
                 .query()
                 .map(|q| format!("?{}", q))
                 .unwrap_or_default()
         );
+        let url = format!("{}{}", target, path_and_query);
 
-        debug!("Proxying request: {} {} -> {}", method, path, url);
+        debug!(
+            "Proxying request: {} {} -> {} (route: {:?})",
+            method, path, url, route
+        );
         debug!("Ensuring target uses HTTPS scheme");
         if !url.starts_with("https://") {
             error!("Invalid target URL scheme - must be HTTPS");
@@ -386,130 +1223,502 @@ impl HttpProxy {
         // Create proxied request builder with extracted headers
         let mut proxy_req_builder = Request::builder().method(req.method()).uri(&url);
 
-        // Copy headers except Host (which we'll set to the target)
+        // Copy headers except Host (which we'll set to the target) and the forwarding
+        // headers, which are rebuilt below
         for (key, value) in headers.iter() {
-            if key != hyper::header::HOST {
+            if key != hyper::header::HOST
+                && !(self.decompress && key == hyper::header::ACCEPT_ENCODING)
+                && key != X_FORWARDED_FOR
+                && key != X_FORWARDED_PROTO
+                && key != X_FORWARDED_HOST
+            {
                 proxy_req_builder = proxy_req_builder.header(key, value);
             }
         }
 
         // Set Host header to match the target domain
-        if let Ok(parsed_url) = reqwest::Url::parse(&url) {
-            if let Some(host) = parsed_url.host_str() {
-                let host_value = if let Some(port) = parsed_url.port() {
-                    format!("{}:{}", host, port)
-                } else {
-                    host.to_string()
-                };
-                proxy_req_builder = proxy_req_builder.header(hyper::header::HOST, host_value);
-            }
+        if let Some(host_value) = Self::host_header(&url) {
+            proxy_req_builder = proxy_req_builder.header(hyper::header::HOST, host_value);
         }
 
-        // Add forwarding headers
+        // Add forwarding headers, preserving any chain set by an earlier hop
+        let client_ip = req
+            .extensions()
+            .get::<SocketAddr>()
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_else(|| "127.0.0.1".to_string());
+        let forwarded_proto = headers
+            .get(X_FORWARDED_PROTO)
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h.to_string())
+            .unwrap_or_else(|| req.uri().scheme_str().unwrap_or(PROXY_SCHEME).to_string());
+        let forwarded_host = headers
+            .get(X_FORWARDED_HOST)
+            .or_else(|| headers.get(hyper::header::HOST))
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h.to_string())
+            .unwrap_or_else(|| format!("localhost:{}", self.port));
         proxy_req_builder = proxy_req_builder
-            .header("X-Forwarded-For", "127.0.0.1")
-            .header("X-Forwarded-Proto", "http")
-            .header("X-Forwarded-Host", format!("localhost:{}", self.port));
-
-        // Build the request with the original body
-        let proxy_req = proxy_req_builder
-            .body(req.into_body())
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-
-        // Send request with timeout
-        let response =
-            match tokio::time::timeout(Duration::from_secs(10), self.client.request(proxy_req))
-                .await
-            {
-                Ok(Ok(resp)) => {
-                    let status = resp.status().as_u16();
-                    let duration = start_time.elapsed().as_millis() as u64;
+            .header(X_FORWARDED_FOR, Self::forwarded_for(&headers, &client_ip))
+            .header(X_FORWARDED_PROTO, forwarded_proto)
+            .header(X_FORWARDED_HOST, forwarded_host);
 
-                    debug!(
-                        "Proxy request successful: {} {} -> {} ({}ms)",
-                        method, path, target, duration
-                    );
-                    debug!("Response status: {}, headers: {:?}", status, resp.headers());
+        // Build the request head once; each attempt gets a fresh copy with its own body
+        let mut proxy_req_template = proxy_req_builder.body(()).map_err(std::io::Error::other)?;
+
+        // Configured headers replace any the client sent with the same name
+        for (name, value) in self.extra_headers.read().await.iter() {
+            proxy_req_template
+                .headers_mut()
+                .insert(name.clone(), value.clone());
+        }
+
+        let mut ctx = RequestContext {
+            method: method.clone(),
+            path: path.clone(),
+            target: target.clone(),
+            route,
+            start_time,
+            request_bytes: Self::content_length(&headers),
+        };
+
+        // Refuse oversized uploads up front when the client declares their size, and cut off
+        // any that turn out larger while streaming
+        let limits = *self.body_limits.read().await;
+        if Self::content_length(&headers).is_some_and(|len| len > limits.max_request_bytes) {
+            warn!(
+                "Rejecting {} {}: declared body exceeds {} bytes",
+                method, path, limits.max_request_bytes
+            );
+            self.log_access(&ctx, 413, Some("Request body too large"))
+                .await;
+            return Ok(Self::payload_too_large(limits.max_request_bytes));
+        }
+        let request_too_large = Arc::new(AtomicBool::new(false));
+        let request_body = Self::with_size_limit(
+            req.into_body(),
+            limits.max_request_bytes,
+            request_too_large.clone(),
+            None,
+        );
+        let capture = self.body_capture().await;
+        let request_body = match &capture {
+            Some(capture) => capture.capture_body(
+                request_body,
+                format!(">>> {} {} -> {}", method, path_and_query, target),
+                proxy_req_template.headers().clone(),
+            ),
+            None => request_body,
+        };
 
-                    // Log successful request
-                    self.log_access(&method, &path, status, duration, &target, None)
+        // Idempotent requests are buffered so they can be replayed; everything else streams once
+        let max_attempts = if retryable { self.max_retries + 1 } else { 1 };
+        let (mut streaming_body, buffered_body) = if retryable {
+            let bytes = match hyper::body::to_bytes(request_body).await {
+                Ok(bytes) => bytes,
+                Err(_) if request_too_large.load(Ordering::Relaxed) => {
+                    self.log_access(&ctx, 413, Some("Request body too large"))
                         .await;
+                    return Ok(Self::payload_too_large(limits.max_request_bytes));
+                }
+                Err(e) => return Err(std::io::Error::other(e)),
+            };
+            ctx.request_bytes = Some(bytes.len() as u64);
+            (None, Some(bytes))
+        } else {
+            (Some(request_body), None)
+        };
+
+        // Send request with a timeout on the response headers, retrying idempotent requests on
+        // connection failures and then failing over to the next target
+        let mut attempt = 1;
+        let mut candidate = 0;
+        let mut proxy_uri = proxy_req_template.uri().clone();
+        let mut proxy_headers = proxy_req_template.headers().clone();
+        let result = loop {
+            let body = match &buffered_body {
+                Some(bytes) => Body::from(bytes.clone()),
+                None => streaming_body.take().unwrap_or_else(Body::empty),
+            };
+            let mut proxy_req = Request::new(body);
+            *proxy_req.method_mut() = proxy_req_template.method().clone();
+            *proxy_req.uri_mut() = proxy_uri.clone();
+            *proxy_req.headers_mut() = proxy_headers.clone();
+
+            let result = Self::await_response(
+                self.client.request(proxy_req),
+                RESPONSE_HEADERS_TIMEOUT,
+                BODY_IDLE_TIMEOUT,
+            )
+            .await;
 
-                    Ok(resp)
+            match result {
+                Ok(Ok(_)) => {
+                    self.failover.record_success(&candidates[candidate]);
+                    break result;
                 }
-                Ok(Err(e)) => {
-                    let error_msg = e.to_string();
-                    error!("Proxy request failed: {}", error_msg);
+                Ok(Err(ref e)) if attempt < max_attempts => {
+                    let delay = Self::retry_delay(attempt);
+                    warn!(
+                        "Proxy request failed (attempt {}/{}), retrying in {}ms: {}",
+                        attempt,
+                        max_attempts,
+                        delay.as_millis(),
+                        e
+                    );
 
                     self.log_access(
-                        &method,
-                        &path,
+                        &ctx,
                         500,
-                        start_time.elapsed().as_millis() as u64,
-                        &target,
-                        Some(&error_msg),
+                        Some(&format!(
+                            "Retry attempt {}/{}: {}",
+                            attempt,
+                            max_attempts - 1,
+                            e
+                        )),
                     )
                     .await;
 
-                    Ok(Response::builder()
-                        .status(500)
-                        .body(Body::from(MAINTENANCE_HTML.replace(
-                            "<!--ERROR_MESSAGE-->",
-                            &format!(
-                                "<p class='text-red-600 dark:text-red-400'>Error: {}</p>",
-                                error_msg
-                            ),
-                        )))
-                        .unwrap())
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
                 }
-                Err(_) => {
-                    let error_msg = "Request timed out".to_string();
-                    error!("Proxy request timed out");
-
+                Ok(Err(_)) | Err(_) if candidate + 1 < candidates.len() => {
+                    let error_msg = match &result {
+                        Ok(Err(e)) => e.to_string(),
+                        _ => "Request timed out".to_string(),
+                    };
+                    self.record_target_failure(&candidates[candidate]);
+                    candidate += 1;
+                    let next_url = format!("{}{}", candidates[candidate], path_and_query);
+                    warn!(
+                        "Proxy target {} failed ({}), failing over to {}",
+                        ctx.target, error_msg, candidates[candidate]
+                    );
                     self.log_access(
-                        &method,
-                        &path,
-                        504,
-                        start_time.elapsed().as_millis() as u64,
-                        &target,
-                        Some(&error_msg),
+                        &ctx,
+                        502,
+                        Some(&format!(
+                            "Failing over to {}: {}",
+                            candidates[candidate], error_msg
+                        )),
                     )
                     .await;
 
-                    Ok(Response::builder()
+                    proxy_uri = next_url.parse().map_err(std::io::Error::other)?;
+                    match Self::host_header(&next_url).and_then(|h| h.parse().ok()) {
+                        Some(host) => {
+                            proxy_headers.insert(hyper::header::HOST, host);
+                        }
+                        None => {
+                            proxy_headers.remove(hyper::header::HOST);
+                        }
+                    }
+                    ctx.target = candidates[candidate].clone();
+                    attempt = 1;
+                }
+                result => {
+                    self.record_target_failure(&candidates[candidate]);
+                    break result;
+                }
+            }
+        };
+
+        if request_too_large.load(Ordering::Relaxed) {
+            self.log_access(&ctx, 413, Some("Request body too large"))
+                .await;
+            return Ok(Self::payload_too_large(limits.max_request_bytes));
+        }
+
+        let response = match result {
+            Ok(Ok(mut resp)) => {
+                let status = resp.status().as_u16();
+                let duration = start_time.elapsed().as_millis() as u64;
+
+                debug!(
+                    "Proxy request successful: {} {} -> {} ({}ms)",
+                    method, path, target, duration
+                );
+                debug!("Response status: {}, headers: {:?}", status, resp.headers());
+
+                if status >= 500 {
+                    self.target_health
+                        .record_failure(&format!("Target returned {}", status), false);
+                } else {
+                    self.target_health.record_success();
+                }
+
+                if Self::content_length(resp.headers())
+                    .is_some_and(|len| len > limits.max_response_bytes)
+                {
+                    let error_msg = Self::response_too_large_message(limits.max_response_bytes);
+                    warn!("{} {}: {}", method, path, error_msg);
+                    self.log_access(&ctx, 502, Some(&error_msg)).await;
+                    return Ok(Self::response_too_large(limits.max_response_bytes));
+                }
+
+                // Give the webview the retry timing of a rate limit without it parsing
+                // provider-specific headers; the body is passed through untouched
+                let rate_limit_error = if status == 429 {
+                    let summary = rate_limit::rate_limit_summary(resp.headers());
+                    warn!("{} {}: rate limited by target ({})", method, path, summary);
+                    if let Some(secs) = rate_limit::retry_after_secs(resp.headers(), Utc::now()) {
+                        resp.headers_mut()
+                            .insert(rate_limit::X_BB_RETRY_AFTER, secs.into());
+                    }
+                    Some(format!("Rate limited: {}", summary))
+                } else {
+                    None
+                };
+                if self.inject_cors {
+                    cors::add_cors_headers(resp.headers_mut(), &headers);
+                }
+
+                // Log successful request
+                self.log_access_entry(
+                    &ctx,
+                    status,
+                    rate_limit_error.as_deref(),
+                    Self::content_length(resp.headers()),
+                )
+                .await;
+
+                // Bodies without a Content-Length are cut off if they grow past the limit
+                let (parts, body) = resp.into_parts();
+                let body = match &capture {
+                    Some(capture) => capture.capture_body(
+                        body,
+                        format!(
+                            "<<< {} {} {} <- {}",
+                            status, method, path_and_query, ctx.target
+                        ),
+                        parts.headers.clone(),
+                    ),
+                    None => body,
+                };
+                let body = Self::with_size_limit(
+                    body,
+                    limits.max_response_bytes,
+                    Arc::new(AtomicBool::new(false)),
+                    Some(RESPONSE_TRUNCATED_NOTICE),
+                );
+                let resp = Response::from_parts(parts, body);
+
+                if self.decompress {
+                    return Ok(Self::decompress_response(resp, limits.max_response_bytes).await);
+                }
+
+                Ok(resp)
+            }
+            Ok(Err(e)) => {
+                let error_msg = e.to_string();
+                error!("Proxy request failed: {}", error_msg);
+                self.target_health.record_failure(&error_msg, true);
+
+                self.log_access(&ctx, 500, Some(&error_msg)).await;
+
+                Ok(Response::builder()
+                    .status(500)
+                    .body(Body::from(MAINTENANCE_HTML.replace(
+                        "<!--ERROR_MESSAGE-->",
+                        &format!(
+                            "<p class='text-red-600 dark:text-red-400'>Error: {}</p>",
+                            error_msg
+                        ),
+                    )))
+                    .unwrap())
+            }
+            Err(_) => {
+                let error_msg = "Request timed out".to_string();
+                error!("Proxy request timed out");
+                self.counters.record_timeout();
+                self.target_health.record_failure(&error_msg, true);
+
+                self.log_access(&ctx, 504, Some(&error_msg)).await;
+
+                Ok(Response::builder()
                     .status(504)
                     .body(Body::from(MAINTENANCE_HTML.replace(
                         "<!--ERROR_MESSAGE-->",
-                        "<p class='text-red-600 dark:text-red-400'>Error: Request timed out</p>"
+                        "<p class='text-red-600 dark:text-red-400'>Error: Request timed out</p>",
                     )))
                     .unwrap())
-                }
-            };
+            }
+        };
 
         response
     }
 
-    async fn log_access(
+    fn record_target_failure(&self, target: &str) {
+        if self.failover.record_failure(target) {
+            warn!(
+                "Proxy target {} keeps failing, trying other targets first for a while",
+                target
+            );
+        }
+    }
+
+    async fn log_access(&self, ctx: &RequestContext, status: u16, error: Option<&str>) {
+        self.log_access_entry(ctx, status, error, None).await;
+    }
+
+    async fn log_access_entry(
         &self,
-        method: &str,
-        path: &str,
+        ctx: &RequestContext,
         status: u16,
-        duration_ms: u64,
-        target: &str,
         error: Option<&str>,
+        response_bytes: Option<u64>,
     ) {
+        // Failed retry attempts count as errors too, so errors can exceed requests
+        if status >= 500 || error.is_some() {
+            self.counters.record_error();
+        }
+
         let entry = AccessLogEntry {
             timestamp: Utc::now(),
-            method: method.to_string(),
-            path: path.to_string(),
+            method: ctx.method.clone(),
+            path: ctx.path.clone(),
             status,
-            duration_ms,
-            target: target.to_string(),
+            duration_ms: ctx.start_time.elapsed().as_millis() as u64,
+            target: ctx.target.clone(),
+            route: ctx.route.clone(),
             error: error.map(String::from),
+            request_bytes: ctx.request_bytes,
+            response_bytes,
         };
+        self.counters.record_duration(entry.duration_ms);
 
-        if let Err(e) = self.access_logger.write().await.log_request(&entry).await {
+        if let Err(e) = self.access_logger.log_request(&entry).await {
             error!("Failed to write access log: {}", e);
         }
+
+        if status >= 400 || entry.error.is_some() {
+            let mut recent = self.recent_errors.write().await;
+            if recent.len() == MAX_RECENT_ERRORS {
+                recent.pop_front();
+            }
+            recent.push_back(entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADERS_TIMEOUT: Duration = Duration::from_millis(200);
+    const IDLE_TIMEOUT: Duration = Duration::from_millis(150);
+    // Together longer than HEADERS_TIMEOUT, so a timeout over the whole transfer would cut it off
+    const TRICKLE_CHUNKS: usize = 10;
+    const TRICKLE_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// A response whose body arrives a byte per `interval`
+    fn trickle_response(chunks: usize, interval: Duration) -> Response<Body> {
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            for _ in 0..chunks {
+                tokio::time::sleep(interval).await;
+                if sender.send_data("x".into()).await.is_err() {
+                    return;
+                }
+            }
+        });
+        Response::new(body)
+    }
+
+    #[tokio::test]
+    async fn slow_download_outlasting_headers_timeout_completes() {
+        let started = Instant::now();
+        let response = HttpProxy::await_response(
+            async { Ok::<_, hyper::Error>(trickle_response(TRICKLE_CHUNKS, TRICKLE_INTERVAL)) },
+            HEADERS_TIMEOUT,
+            IDLE_TIMEOUT,
+        )
+        .await
+        .expect("headers arrived in time")
+        .unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+        assert_eq!(body.len(), TRICKLE_CHUNKS);
+        assert!(
+            started.elapsed() > HEADERS_TIMEOUT,
+            "download finished in {:?}, before the trickle could outlast the headers timeout",
+            started.elapsed()
+        );
+    }
+
+    #[test]
+    fn forwarded_for_appends_to_existing_chain() {
+        let mut headers = HeaderMap::new();
+        headers.append(X_FORWARDED_FOR, HeaderValue::from_static("10.0.0.1"));
+        headers.append(
+            X_FORWARDED_FOR,
+            HeaderValue::from_static("192.168.1.5, 172.16.0.2"),
+        );
+
+        assert_eq!(
+            HttpProxy::forwarded_for(&headers, "127.0.0.1"),
+            "10.0.0.1, 192.168.1.5, 172.16.0.2, 127.0.0.1"
+        );
+    }
+
+    #[test]
+    fn forwarded_for_starts_chain_without_header() {
+        assert_eq!(
+            HttpProxy::forwarded_for(&HeaderMap::new(), "127.0.0.1"),
+            "127.0.0.1"
+        );
+    }
+
+    #[test]
+    fn saved_proxy_target_takes_precedence() {
+        let proxy_config = ProxyConfig {
+            target: Some("https://configured.example.com".to_string()),
+            ..ProxyConfig::default()
+        };
+        let mut dui_config = DuiConfig {
+            proxy_target: Some("https://saved.example.com".to_string()),
+            ..DuiConfig::default()
+        };
+        assert_eq!(
+            HttpProxy::configured_target(&proxy_config, &dui_config),
+            "https://saved.example.com"
+        );
+
+        dui_config.proxy_target = Some("http://saved.example.com".to_string());
+        assert_eq!(
+            HttpProxy::configured_target(&proxy_config, &dui_config),
+            "https://configured.example.com"
+        );
+    }
+
+    #[test]
+    fn fallback_targets_without_https_are_dropped() {
+        let config = ProxyConfig {
+            fallback_targets: vec![
+                "https://backup.example.com".to_string(),
+                "http://plain.example.com".to_string(),
+                "not a url".to_string(),
+            ],
+            ..ProxyConfig::default()
+        };
+
+        assert_eq!(
+            HttpProxy::load_fallback_targets(&config),
+            vec!["https://backup.example.com"]
+        );
+    }
+
+    #[tokio::test]
+    async fn stalled_download_fails_after_idle_timeout() {
+        let response = HttpProxy::await_response(
+            async { Ok::<_, hyper::Error>(trickle_response(1, IDLE_TIMEOUT * 3)) },
+            HEADERS_TIMEOUT,
+            IDLE_TIMEOUT,
+        )
+        .await
+        .expect("headers arrived in time")
+        .unwrap();
+
+        assert!(hyper::body::to_bytes(response.into_body()).await.is_err());
     }
 }
@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use http::HeaderMap;
+
+const RATE_LIMIT_PREFIX: &str = "anthropic-ratelimit-";
+/// Seconds until a rate-limited request may be retried, added to upstream 429 responses
+pub(super) const X_BB_RETRY_AFTER: &str = "x-bb-retry-after";
+
+/// `retry-after` and `anthropic-ratelimit-*` headers as `name=value` pairs for the access log
+pub(super) fn rate_limit_summary(headers: &HeaderMap) -> String {
+    let mut pairs: Vec<String> = headers
+        .iter()
+        .filter(|(name, _)| {
+            *name == hyper::header::RETRY_AFTER || name.as_str().starts_with(RATE_LIMIT_PREFIX)
+        })
+        .map(|(name, value)| format!("{}={}", name, value.to_str().unwrap_or("<binary>")))
+        .collect();
+    pairs.sort();
+    if pairs.is_empty() {
+        "no rate limit headers".to_string()
+    } else {
+        pairs.join(", ")
+    }
+}
+
+/// Seconds from `now` until a retry is allowed: `retry-after` (delay-seconds or HTTP date),
+/// otherwise the latest `anthropic-ratelimit-*-reset` time
+pub(super) fn retry_after_secs(headers: &HeaderMap, now: DateTime<Utc>) -> Option<u64> {
+    let secs_until = |at: DateTime<Utc>| {
+        let millis = (at - now).num_milliseconds().max(0) as u64;
+        millis.div_ceil(1000)
+    };
+
+    if let Some(value) = headers
+        .get(hyper::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+    {
+        if let Ok(secs) = value.parse::<u64>() {
+            return Some(secs);
+        }
+        if let Ok(at) = DateTime::parse_from_rfc2822(value) {
+            return Some(secs_until(at.with_timezone(&Utc)));
+        }
+    }
+
+    headers
+        .iter()
+        .filter(|(name, _)| {
+            name.as_str().starts_with(RATE_LIMIT_PREFIX) && name.as_str().ends_with("-reset")
+        })
+        .filter_map(|(_, value)| DateTime::parse_from_rfc3339(value.to_str().ok()?.trim()).ok())
+        .map(|at| secs_until(at.with_timezone(&Utc)))
+        .max()
+}
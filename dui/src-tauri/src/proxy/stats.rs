@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+// Weight given to the newest sample in the rolling average duration
+const DURATION_EMA_ALPHA: f64 = 0.1;
+// Consecutive failures after which the target is reported as down rather than degraded
+const TARGET_DOWN_THRESHOLD: u32 = 3;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProxyStats {
+    pub total_requests: u64,
+    pub total_errors: u64,
+    pub total_timeouts: u64,
+    pub average_duration_ms: f64,
+    pub uptime_secs: u64,
+    pub is_running: bool,
+    /// Upstream connections currently open, both in use and idle in the pool
+    pub open_connections: usize,
+}
+
+/// Lock-free request counters shared by every clone of the proxy
+#[derive(Debug, Default)]
+pub struct ProxyCounters {
+    total_requests: AtomicU64,
+    total_errors: AtomicU64,
+    total_timeouts: AtomicU64,
+    // f64 bits of the exponential moving average, so it fits in an atomic
+    average_duration_bits: AtomicU64,
+    samples: AtomicU64,
+}
+
+impl ProxyCounters {
+    pub fn record_request(&self) {
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self) {
+        self.total_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_timeout(&self) {
+        self.total_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_duration(&self, duration_ms: u64) {
+        let first_sample = self.samples.fetch_add(1, Ordering::Relaxed) == 0;
+        let sample = duration_ms as f64;
+        let _ =
+            self.average_duration_bits
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                    let average = if first_sample {
+                        sample
+                    } else {
+                        let current = f64::from_bits(bits);
+                        current + DURATION_EMA_ALPHA * (sample - current)
+                    };
+                    Some(average.to_bits())
+                });
+    }
+
+    pub fn reset(&self) {
+        self.total_requests.store(0, Ordering::Relaxed);
+        self.total_errors.store(0, Ordering::Relaxed);
+        self.total_timeouts.store(0, Ordering::Relaxed);
+        self.average_duration_bits.store(0, Ordering::Relaxed);
+        self.samples.store(0, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(
+        &self,
+        uptime_secs: u64,
+        is_running: bool,
+        open_connections: usize,
+    ) -> ProxyStats {
+        ProxyStats {
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+            total_errors: self.total_errors.load(Ordering::Relaxed),
+            total_timeouts: self.total_timeouts.load(Ordering::Relaxed),
+            average_duration_ms: f64::from_bits(self.average_duration_bits.load(Ordering::Relaxed)),
+            uptime_secs,
+            is_running,
+            open_connections,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetState {
+    /// No request has reached the target yet
+    Unknown,
+    Healthy,
+    Degraded,
+    Down,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProxyTargetHealth {
+    pub target: String,
+    pub state: TargetState,
+    pub last_request_succeeded: Option<bool>,
+    pub secs_since_last_success: Option<u64>,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// Whether the last response was the maintenance page rather than the target's
+    pub serving_maintenance: bool,
+}
+
+#[derive(Debug, Default)]
+struct TargetHealthState {
+    last_request_succeeded: Option<bool>,
+    last_success: Option<Instant>,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    serving_maintenance: bool,
+}
+
+/// Outcome of the most recent requests to the proxy target
+#[derive(Debug, Default)]
+pub struct TargetHealthTracker {
+    state: Mutex<TargetHealthState>,
+}
+
+impl TargetHealthTracker {
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.last_request_succeeded = Some(true);
+        state.last_success = Some(Instant::now());
+        state.consecutive_failures = 0;
+        state.serving_maintenance = false;
+    }
+
+    pub fn record_failure(&self, error: &str, serving_maintenance: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.last_request_succeeded = Some(false);
+        state.consecutive_failures += 1;
+        state.last_error = Some(error.to_string());
+        state.serving_maintenance = serving_maintenance;
+    }
+
+    pub fn reset(&self) {
+        *self.state.lock().unwrap() = TargetHealthState::default();
+    }
+
+    pub fn snapshot(&self, target: String) -> ProxyTargetHealth {
+        let state = self.state.lock().unwrap();
+        let target_state = match state.last_request_succeeded {
+            None => TargetState::Unknown,
+            Some(_) if state.consecutive_failures == 0 => TargetState::Healthy,
+            Some(_) if state.consecutive_failures < TARGET_DOWN_THRESHOLD => TargetState::Degraded,
+            Some(_) => TargetState::Down,
+        };
+
+        ProxyTargetHealth {
+            target,
+            state: target_state,
+            last_request_succeeded: state.last_request_succeeded,
+            secs_since_last_success: state.last_success.map(|t| t.elapsed().as_secs()),
+            consecutive_failures: state.consecutive_failures,
+            last_error: state.last_error.clone(),
+            serving_maintenance: state.serving_maintenance,
+        }
+    }
+}
@@ -0,0 +1,397 @@
+use futures_util::stream::SplitStream;
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use http::HeaderMap;
+use hyper::upgrade::Upgraded;
+use log::{debug, error, warn};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Response;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+const RECONNECT_ATTEMPTS: u32 = 3;
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+// Client messages held while the upstream is reconnecting; oldest are dropped beyond this
+const RECONNECT_BUFFER_SIZE: usize = 100;
+// How long a forwarded close may go unacknowledged before the connections are dropped
+const CLOSE_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(super) type ClientStream = WebSocketStream<Upgraded>;
+pub(super) type UpstreamStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+enum Reconnect<U> {
+    Connected(Box<WebSocketStream<U>>),
+    ClientClosed,
+    Failed,
+}
+
+/// WebSocket URL for a request `path` and raw `query` on an http(s) `target`, keeping any
+/// path prefix the target has. `path` and `query` are already percent-encoded by the client.
+pub(super) fn target_url(target: &str, path: &str, query: Option<&str>) -> Result<String, String> {
+    let mut url =
+        url::Url::parse(target).map_err(|e| format!("Invalid target URL {}: {}", target, e))?;
+    let scheme = match url.scheme() {
+        "https" | "wss" => "wss",
+        "http" | "ws" => "ws",
+        other => return Err(format!("Unsupported target URL scheme: {}", other)),
+    };
+    url.set_scheme(scheme)
+        .map_err(|_| format!("Cannot convert {} to a WebSocket URL", target))?;
+
+    let joined = format!("{}{}", url.path().trim_end_matches('/'), path);
+    url.set_path(&joined);
+    url.set_query(query);
+    url.set_fragment(None);
+    Ok(url.to_string())
+}
+
+/// Open the upstream connection, sending `extra_headers` with the handshake
+pub(super) async fn connect(
+    ws_target: &str,
+    extra_headers: &HeaderMap,
+) -> Result<(UpstreamStream, Response), tungstenite::Error> {
+    let mut request = ws_target.into_client_request()?;
+    for (name, value) in extra_headers.iter() {
+        request.headers_mut().insert(name.clone(), value.clone());
+    }
+    connect_async(request).await
+}
+
+/// Relay messages between the client and upstream, transparently reconnecting to
+/// `ws_target` if the upstream connection drops without a clean close
+pub(super) async fn relay(
+    client_ws: ClientStream,
+    upstream: UpstreamStream,
+    ws_target: String,
+    extra_headers: HeaderMap,
+) {
+    let (target, headers) = (ws_target.as_str(), &extra_headers);
+    relay_with(client_ws, upstream, target, move || async move {
+        connect(target, headers).await.map(|(stream, _)| stream)
+    })
+    .await;
+}
+
+/// The relay loop, with `reconnect_to` opening a replacement upstream connection to
+/// `ws_target` (named in logs) so the loop can run over any pair of WebSocket streams
+async fn relay_with<C, U, F, Fut>(
+    client_ws: WebSocketStream<C>,
+    upstream: WebSocketStream<U>,
+    ws_target: &str,
+    reconnect_to: F,
+) where
+    C: AsyncRead + AsyncWrite + Unpin,
+    U: AsyncRead + AsyncWrite + Unpin,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<WebSocketStream<U>, tungstenite::Error>>,
+{
+    let (mut client_write, mut client_read) = client_ws.split();
+    let (mut server_write, mut server_read) = upstream.split();
+    let mut pending: VecDeque<Message> = VecDeque::new();
+
+    loop {
+        let upstream_lost = tokio::select! {
+            msg = client_read.next() => match msg {
+                Some(Ok(Message::Ping(data))) => {
+                    if let Err(e) = server_write.send(Message::Pong(data)).await {
+                        warn!("Websocket: Error sending pong to server: {}", e);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                Some(Ok(Message::Pong(_))) => false,
+                Some(Ok(Message::Close(frame))) => {
+                    debug!("Websocket: Received client close: {:?}", frame);
+                    forward_close(frame, &mut server_write, &mut server_read, &mut client_write)
+                        .await;
+                    break;
+                }
+                Some(Ok(msg)) => {
+                    if let Err(e) = server_write.send(msg.clone()).await {
+                        warn!("Websocket: Error forwarding to server: {}", e);
+                        buffer_message(&mut pending, msg);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                Some(Err(e)) => {
+                    debug!("Websocket: Client connection error: {}", e);
+                    close_upstream(&mut server_write).await;
+                    break;
+                }
+                None => {
+                    debug!("Websocket: Client disconnected");
+                    close_upstream(&mut server_write).await;
+                    break;
+                }
+            },
+            msg = server_read.next() => match msg {
+                Some(Ok(Message::Ping(data))) => {
+                    if let Err(e) = client_write.send(Message::Pong(data)).await {
+                        error!("Websocket: Error sending pong to client: {}", e);
+                        break;
+                    }
+                    false
+                }
+                Some(Ok(Message::Pong(_))) => false,
+                Some(Ok(Message::Close(frame))) => {
+                    debug!("Websocket: Received server close: {:?}", frame);
+                    forward_close(frame, &mut client_write, &mut client_read, &mut server_write)
+                        .await;
+                    break;
+                }
+                Some(Ok(msg)) => {
+                    if let Err(e) = client_write.send(msg).await {
+                        error!("Websocket: Error forwarding to client: {}", e);
+                        break;
+                    }
+                    false
+                }
+                Some(Err(e)) => {
+                    warn!("Websocket: Upstream connection error: {}", e);
+                    true
+                }
+                None => {
+                    warn!("Websocket: Upstream connection dropped");
+                    true
+                }
+            },
+        };
+
+        if !upstream_lost {
+            continue;
+        }
+
+        match reconnect(ws_target, &reconnect_to, &mut client_read, &mut pending).await {
+            Reconnect::Connected(stream) => {
+                (server_write, server_read) = (*stream).split();
+                // Replay what the client sent while the upstream was away
+                while let Some(msg) = pending.pop_front() {
+                    if let Err(e) = server_write.send(msg.clone()).await {
+                        warn!("Websocket: Error replaying buffered message: {}", e);
+                        pending.push_front(msg);
+                        break;
+                    }
+                }
+            }
+            Reconnect::ClientClosed => {
+                debug!("Websocket: Client closed during reconnect");
+                break;
+            }
+            Reconnect::Failed => {
+                error!(
+                    "Websocket: Giving up on {} after {} reconnect attempts",
+                    ws_target, RECONNECT_ATTEMPTS
+                );
+                let _ = client_write
+                    .send(Message::Close(Some(CloseFrame {
+                        code: CloseCode::Away,
+                        reason: "Upstream unavailable".into(),
+                    })))
+                    .await;
+                break;
+            }
+        }
+    }
+
+    debug!("Websocket: Connection closed");
+}
+
+/// Retry the upstream connection with exponential backoff, buffering client messages meanwhile
+async fn reconnect<C, U, F, Fut>(
+    ws_target: &str,
+    reconnect_to: &F,
+    client_read: &mut SplitStream<WebSocketStream<C>>,
+    pending: &mut VecDeque<Message>,
+) -> Reconnect<U>
+where
+    C: AsyncRead + AsyncWrite + Unpin,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<WebSocketStream<U>, tungstenite::Error>>,
+{
+    for attempt in 1..=RECONNECT_ATTEMPTS {
+        let delay = RECONNECT_BASE_DELAY * 2u32.pow(attempt - 1);
+        debug!(
+            "Websocket: Reconnect attempt {}/{} in {:?}",
+            attempt, RECONNECT_ATTEMPTS, delay
+        );
+
+        let connect = async {
+            tokio::time::sleep(delay).await;
+            reconnect_to().await
+        };
+        tokio::pin!(connect);
+
+        loop {
+            tokio::select! {
+                result = &mut connect => match result {
+                    Ok(stream) => {
+                        debug!("Websocket: Reconnected to {}", ws_target);
+                        return Reconnect::Connected(Box::new(stream));
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Websocket: Reconnect attempt {}/{} failed: {}",
+                            attempt, RECONNECT_ATTEMPTS, e
+                        );
+                        break;
+                    }
+                },
+                msg = client_read.next() => match msg {
+                    Some(Ok(Message::Ping(_))) | Some(Ok(Message::Pong(_))) => {}
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                        return Reconnect::ClientClosed;
+                    }
+                    Some(Ok(msg)) => buffer_message(pending, msg),
+                },
+            }
+        }
+    }
+
+    Reconnect::Failed
+}
+
+/// Pass a close frame (code and reason intact) on to the other peer, wait for it to answer
+/// with its own close, then flush the reply tungstenite queued for the peer that started
+/// closing, so both closing handshakes complete
+async fn forward_close<W, R, A>(
+    frame: Option<CloseFrame<'static>>,
+    to_write: &mut W,
+    to_read: &mut R,
+    from_write: &mut A,
+) where
+    W: Sink<Message, Error = tungstenite::Error> + Unpin,
+    R: Stream<Item = Result<Message, tungstenite::Error>> + Unpin,
+    A: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    if let Err(e) = to_write.send(Message::Close(frame)).await {
+        debug!("Websocket: Could not forward close: {}", e);
+    } else {
+        let acknowledged = tokio::time::timeout(CLOSE_HANDSHAKE_TIMEOUT, async {
+            // Anything still in flight is dropped; the initiating peer won't accept it
+            while let Some(Ok(msg)) = to_read.next().await {
+                if msg.is_close() {
+                    break;
+                }
+            }
+        })
+        .await;
+        if acknowledged.is_err() {
+            debug!("Websocket: Close was not acknowledged in time");
+        }
+    }
+    let _ = from_write.close().await;
+}
+
+/// Tell the upstream the client went away without a close frame of its own
+async fn close_upstream<W>(server_write: &mut W)
+where
+    W: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    let _ = server_write
+        .send(Message::Close(Some(CloseFrame {
+            code: CloseCode::Away,
+            reason: "Client disconnected".into(),
+        })))
+        .await;
+    let _ = server_write.close().await;
+}
+
+fn buffer_message(pending: &mut VecDeque<Message>, msg: Message) {
+    if pending.len() >= RECONNECT_BUFFER_SIZE {
+        pending.pop_front();
+        warn!("Websocket: Reconnect buffer full, dropping oldest message");
+    }
+    pending.push_back(msg);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::DuplexStream;
+    use tokio_tungstenite::tungstenite::protocol::Role;
+
+    /// Two ends of an in-memory WebSocket connection: (client role, server role)
+    async fn ws_pair() -> (WebSocketStream<DuplexStream>, WebSocketStream<DuplexStream>) {
+        let (client_io, server_io) = tokio::io::duplex(4096);
+        let client = WebSocketStream::from_raw_socket(client_io, Role::Client, None).await;
+        let server = WebSocketStream::from_raw_socket(server_io, Role::Server, None).await;
+        (client, server)
+    }
+
+    #[test]
+    fn target_url_keeps_path_prefix_and_encoded_query() {
+        let url = target_url(
+            "https://example.com/bb/api/",
+            "/v1/ws/conversation/abc%20def",
+            Some("token=a%2Bb%3D&name=x%26y"),
+        )
+        .unwrap();
+        assert_eq!(
+            url,
+            "wss://example.com/bb/api/v1/ws/conversation/abc%20def?token=a%2Bb%3D&name=x%26y"
+        );
+    }
+
+    #[tokio::test]
+    async fn client_going_away_close_code_reaches_upstream() {
+        let (mut client, proxy_client) = ws_pair().await;
+        let (proxy_upstream, mut upstream) = ws_pair().await;
+
+        let relay_task = tokio::spawn(async move {
+            relay_with(
+                proxy_client,
+                proxy_upstream,
+                "ws://upstream.test/ws",
+                || async {
+                    Err::<WebSocketStream<DuplexStream>, _>(tungstenite::Error::ConnectionClosed)
+                },
+            )
+            .await
+        });
+        let upstream_task = tokio::spawn(async move {
+            let received = upstream.next().await;
+            // Keep reading so the close reply is flushed back to the proxy
+            while let Some(Ok(_)) = upstream.next().await {}
+            received
+        });
+
+        client
+            .send(Message::Close(Some(CloseFrame {
+                code: CloseCode::Away,
+                reason: "Navigating away".into(),
+            })))
+            .await
+            .unwrap();
+
+        // The client gets the reply to its own close
+        match tokio::time::timeout(Duration::from_secs(5), client.next()).await {
+            Ok(Some(Ok(Message::Close(_)))) => {}
+            other => panic!("expected a close reply, got {:?}", other),
+        }
+
+        tokio::time::timeout(Duration::from_secs(5), relay_task)
+            .await
+            .expect("relay did not finish")
+            .unwrap();
+        let received = tokio::time::timeout(Duration::from_secs(5), upstream_task)
+            .await
+            .expect("upstream did not finish")
+            .unwrap();
+        match received {
+            Some(Ok(Message::Close(Some(frame)))) => {
+                assert_eq!(frame.code, CloseCode::Away);
+                assert_eq!(u16::from(frame.code), 1001);
+                assert_eq!(frame.reason, "Navigating away");
+            }
+            other => panic!("expected a 1001 close upstream, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use log::{error, info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::RwLock;
+
+use crate::config::read_global_config;
+use crate::proxy::HttpProxy;
+
+// Upper bound on how long quitting the app can be held up by stopping services
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+async fn stop_proxy(app_handle: &AppHandle) {
+    let Some(state) = app_handle.try_state::<Arc<RwLock<HttpProxy>>>() else {
+        return;
+    };
+    let proxy = state.read().await;
+    if let Err(e) = proxy.stop().await {
+        error!("Shutdown: failed to stop proxy: {}", e);
+    }
+}
+
+fn log_service_stop(service: &str, stopped: Result<bool, String>) {
+    match stopped {
+        Ok(true) => info!("Shutdown: {} stopped", service.to_uppercase()),
+        Ok(false) => warn!(
+            "Shutdown: some {} processes may still be running",
+            service.to_uppercase()
+        ),
+        Err(e) => error!("Shutdown: failed to stop {}: {}", service.to_uppercase(), e),
+    }
+}
+
+/// Stop the proxy and, when `dui.stopServicesOnExit` is set, the API and BUI before the
+/// app exits. Blocks for at most SHUTDOWN_TIMEOUT.
+pub fn shutdown_services(app_handle: &AppHandle) {
+    let stop_services = match read_global_config() {
+        Ok(config) => config.dui.stop_services_on_exit,
+        Err(e) => {
+            warn!("Shutdown: failed to read config: {}", e);
+            false
+        }
+    };
+    info!(
+        "Shutting down proxy{}",
+        if stop_services { ", API and BUI" } else { "" }
+    );
+
+    tauri::async_runtime::block_on(async {
+        let shutdown = async {
+            if stop_services {
+                tokio::join!(
+                    stop_proxy(app_handle),
+                    async { log_service_stop("api", crate::api::stop_api().await) },
+                    async { log_service_stop("bui", crate::bui::stop_bui().await) },
+                );
+            } else {
+                stop_proxy(app_handle).await;
+            }
+        };
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, shutdown)
+            .await
+            .is_err()
+        {
+            error!(
+                "Shutdown: stopping services did not finish within {}s",
+                SHUTDOWN_TIMEOUT.as_secs()
+            );
+        }
+    });
+}
@@ -0,0 +1,94 @@
+use rand::Rng;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+// Longest single wait between checks, however long startup has been going
+const MAX_POLL_DELAY: Duration = Duration::from_secs(4);
+// Each delay is randomised by up to this fraction either way
+const POLL_JITTER: f64 = 0.2;
+
+/// Call `check` until it returns true or `timeout` has passed, waiting `initial_delay`
+/// before the first check and doubling the wait (with jitter) after each miss.
+/// Returns whether the check passed and how long it took.
+pub async fn wait_until_ready<F, Fut>(
+    initial_delay: Duration,
+    timeout: Duration,
+    mut check: F,
+) -> (bool, Duration)
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let started = Instant::now();
+    let mut delay = initial_delay.min(MAX_POLL_DELAY);
+    let mut attempt = 1;
+    loop {
+        let jitter = rand::thread_rng().gen_range(1.0 - POLL_JITTER..=1.0 + POLL_JITTER);
+        let remaining = timeout.saturating_sub(started.elapsed());
+        // Async sleeps keep the runtime responsive and let the poll be cancelled by
+        // dropping the future
+        tokio::time::sleep(delay.mul_f64(jitter).min(remaining)).await;
+
+        if check(attempt).await {
+            return (true, started.elapsed());
+        }
+        if started.elapsed() >= timeout {
+            return (false, started.elapsed());
+        }
+        delay = (delay * 2).min(MAX_POLL_DELAY);
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // With one runtime thread, a blocking wait between checks would stall every other task
+    // for as long as the poll runs
+    #[tokio::test(flavor = "current_thread")]
+    async fn other_tasks_progress_while_polling() {
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        });
+
+        let (ready, _) = wait_until_ready(
+            Duration::from_millis(50),
+            Duration::from_secs(5),
+            |attempt| async move { attempt >= 3 },
+        )
+        .await;
+        let ticks_during_poll = ticks.load(Ordering::SeqCst);
+        ticker.abort();
+
+        assert!(ready);
+        assert!(
+            ticks_during_poll >= 5,
+            "concurrent task only ran {} times while the poll waited",
+            ticks_during_poll
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn gives_up_after_timeout() {
+        let (ready, elapsed) = wait_until_ready(
+            Duration::from_millis(20),
+            Duration::from_millis(200),
+            |_| async { false },
+        )
+        .await;
+
+        assert!(!ready);
+        assert!(elapsed >= Duration::from_millis(200));
+        assert!(elapsed < Duration::from_secs(2));
+    }
+}
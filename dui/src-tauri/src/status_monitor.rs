@@ -0,0 +1,88 @@
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::server_status::{check_server_status, ServiceState};
+
+const DEFAULT_MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+// Status checks probe both services, so don't let the frontend ask for a tighter loop
+const MIN_MONITOR_INTERVAL: Duration = Duration::from_millis(500);
+
+static MONITOR_TASK: Lazy<Mutex<Option<JoinHandle<()>>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceStatusChangedEvent {
+    pub service: String,
+    pub previous: ServiceState,
+    pub current: ServiceState,
+}
+
+async fn monitor(app: AppHandle, interval: Duration) {
+    let mut previous: HashMap<&'static str, ServiceState> = HashMap::new();
+    loop {
+        match check_server_status().await {
+            Ok(status) => {
+                for (service, current) in [("api", status.api.state), ("bui", status.bui.state)] {
+                    // The first check only establishes the baseline
+                    let Some(prev) = previous.insert(service, current) else {
+                        continue;
+                    };
+                    if prev == current {
+                        continue;
+                    }
+                    info!(
+                        "Status monitor: {} changed from {:?} to {:?}",
+                        service.to_uppercase(),
+                        prev,
+                        current
+                    );
+                    let event = ServiceStatusChangedEvent {
+                        service: service.to_string(),
+                        previous: prev,
+                        current,
+                    };
+                    if let Err(e) = app.emit("service-status-changed", event) {
+                        warn!("Status monitor: failed to emit event: {}", e);
+                    }
+                }
+            }
+            Err(e) => warn!("Status monitor: failed to check server status: {}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// (Re)start the background task that emits `service-status-changed` whenever the API
+/// or BUI changes state
+pub fn start_monitor(app: AppHandle, interval: Duration) {
+    let interval = interval.max(MIN_MONITOR_INTERVAL);
+    let mut task = MONITOR_TASK.lock().unwrap();
+    if let Some(handle) = task.take() {
+        handle.abort();
+    }
+    debug!("Starting status monitor every {}ms", interval.as_millis());
+    *task = Some(tauri::async_runtime::spawn(monitor(app, interval)));
+}
+
+/// Start monitoring at the default interval, called once at app startup
+pub fn start_default_monitor(app: AppHandle) {
+    start_monitor(app, DEFAULT_MONITOR_INTERVAL);
+}
+
+#[tauri::command]
+pub fn start_status_monitoring(app: AppHandle, interval_ms: u64) {
+    start_monitor(app, Duration::from_millis(interval_ms));
+}
+
+#[tauri::command]
+pub fn stop_status_monitoring() {
+    if let Some(handle) = MONITOR_TASK.lock().unwrap().take() {
+        handle.abort();
+        info!("Status monitor stopped");
+    }
+}
@@ -0,0 +1,165 @@
+use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::server_status::{check_server_status, ServiceState};
+use crate::config::read_global_config;
+use crate::error::BBError;
+
+// Automatic restarts allowed per service within RESTART_WINDOW before backing off
+const MAX_RESTARTS_PER_WINDOW: usize = 3;
+const RESTART_WINDOW: Duration = Duration::from_secs(60);
+
+// Services stopped through stop_api/stop_bui, which must not be brought back automatically
+static STOPPED_DELIBERATELY: Lazy<Mutex<HashSet<&'static str>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Record that `service` was stopped on purpose so the supervisor leaves it down
+pub fn note_service_stopped(service: &'static str) {
+    STOPPED_DELIBERATELY.lock().unwrap().insert(service);
+}
+
+/// Record that `service` is being started, re-enabling automatic restarts for it
+pub fn note_service_started(service: &'static str) {
+    STOPPED_DELIBERATELY.lock().unwrap().remove(service);
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceRestartedEvent {
+    pub service: String,
+    pub success: bool,
+    pub error: Option<BBError>,
+}
+
+#[derive(Default)]
+struct SupervisorState {
+    previous: HashMap<&'static str, ServiceState>,
+    restarts: HashMap<&'static str, VecDeque<Instant>>,
+}
+
+impl SupervisorState {
+    /// Record a restart attempt unless the service already used up its budget for the window
+    fn allow_restart(&mut self, service: &'static str) -> bool {
+        let now = Instant::now();
+        let history = self.restarts.entry(service).or_default();
+        while history
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > RESTART_WINDOW)
+        {
+            history.pop_front();
+        }
+        if history.len() >= MAX_RESTARTS_PER_WINDOW {
+            return false;
+        }
+        history.push_back(now);
+        true
+    }
+}
+
+async fn restart(service: &str) -> ServiceRestartedEvent {
+    let result = match service {
+        "api" => crate::api::start_api()
+            .await
+            .map(|result| (result.success, result.error)),
+        _ => crate::bui::start_bui()
+            .await
+            .map(|result| (result.success, result.error)),
+    };
+    let (success, error) = match result {
+        Ok((success, error)) => (success, error),
+        Err(e) => (false, Some(e)),
+    };
+    ServiceRestartedEvent {
+        service: service.to_string(),
+        success,
+        error,
+    }
+}
+
+async fn check_services(app: &AppHandle, state: &mut SupervisorState) {
+    let status = match check_server_status().await {
+        Ok(status) => status,
+        Err(e) => {
+            warn!("Supervisor: failed to check server status: {}", e);
+            return;
+        }
+    };
+
+    for (service, current) in [("api", status.api.state), ("bui", status.bui.state)] {
+        let previous = state.previous.insert(service, current);
+        // Only services that died under us are restarted; zombies are left for the user
+        // to restart explicitly
+        if previous != Some(ServiceState::Running) || current != ServiceState::Stopped {
+            continue;
+        }
+        if STOPPED_DELIBERATELY.lock().unwrap().contains(service) {
+            debug!(
+                "Supervisor: {} was stopped deliberately",
+                service.to_uppercase()
+            );
+            continue;
+        }
+
+        if !state.allow_restart(service) {
+            warn!(
+                "Supervisor: {} stopped but was restarted {} times in the last {}s, not restarting",
+                service.to_uppercase(),
+                MAX_RESTARTS_PER_WINDOW,
+                RESTART_WINDOW.as_secs()
+            );
+            continue;
+        }
+
+        info!(
+            "Supervisor: {} is no longer running, restarting",
+            service.to_uppercase()
+        );
+        let event = restart(service).await;
+        if event.success {
+            info!("Supervisor: {} restarted", service.to_uppercase());
+            state.previous.insert(service, ServiceState::Running);
+        } else {
+            error!(
+                "Supervisor: failed to restart {}: {:?}",
+                service.to_uppercase(),
+                event.error
+            );
+        }
+        if let Err(e) = app.emit("service-restarted", event) {
+            warn!("Supervisor: failed to emit service-restarted event: {}", e);
+        }
+    }
+}
+
+/// Periodically check the API and BUI and restart any that stopped unexpectedly.
+/// Controlled by `dui.autoRestartServices` and `dui.serviceCheckIntervalSecs`, which are
+/// re-read on every tick so changes apply without restarting the app.
+pub fn start_service_supervisor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut state = SupervisorState::default();
+        loop {
+            let (enabled, interval_secs) = match read_global_config() {
+                Ok(config) => (
+                    config.dui.auto_restart_services,
+                    config.dui.service_check_interval_secs,
+                ),
+                Err(e) => {
+                    warn!("Supervisor: failed to read config: {}", e);
+                    (false, 30)
+                }
+            };
+            tokio::time::sleep(Duration::from_secs(interval_secs.max(1))).await;
+
+            if enabled {
+                check_services(&app, &mut state).await;
+            } else {
+                debug!("Supervisor: auto-restart disabled");
+                state.previous.clear();
+            }
+        }
+    });
+}
@@ -1,8 +1,9 @@
-use crate::config::get_dui_debug_mode;
+use crate::config::{get_dui_debug_mode, get_window_sizing_mode, WindowSizingMode};
 use log::{error, info};
-use once_cell::sync::OnceCell;
+use once_cell::sync::{Lazy, OnceCell};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
 use std::time::Duration;
 use tauri::{Manager, WebviewWindow};
 use tauri_plugin_store::StoreExt;
@@ -12,7 +13,10 @@ use tokio::time::sleep;
 const DEBOUNCE_DURATION: Duration = Duration::from_millis(500);
 static SAVE_HANDLE: OnceCell<tokio::sync::Mutex<Option<tauri::async_runtime::JoinHandle<()>>>> =
     OnceCell::new();
+// Windows with state handlers, flushed on exit so a pending debounced save isn't lost
+static TRACKED_WINDOWS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
 
+/// Window geometry as stored: physical pixels plus the scale factor they were measured at
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct WindowState {
     pub width: f64,
@@ -20,6 +24,104 @@ pub struct WindowState {
     pub x: Option<f64>,
     pub y: Option<f64>,
     pub scale_factor: f64,
+    #[serde(default)]
+    pub maximized: bool,
+    #[serde(default)]
+    pub fullscreen: bool,
+}
+
+/// Bounds of a connected monitor in physical pixels
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct MonitorBounds {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+/// Window geometry remembered for one monitor configuration
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct SavedLayout {
+    monitors: Vec<MonitorBounds>,
+    state: WindowState,
+}
+
+/// Stored value per window label: one layout per monitor fingerprint, so docking and
+/// undocking a laptop don't overwrite each other's geometry
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct SavedWindowStates {
+    layouts: HashMap<String, SavedLayout>,
+}
+
+impl SavedWindowStates {
+    /// Parse a stored value. Entries written before states were kept per monitor layout
+    /// hold a bare WindowState, which is adopted as the state for `current` monitors.
+    fn from_stored(value: &serde_json::Value, current: &[MonitorBounds]) -> Self {
+        if let Ok(saved) = serde_json::from_value::<SavedWindowStates>(value.clone()) {
+            return saved;
+        }
+        let mut saved = SavedWindowStates::default();
+        if let Ok(state) = serde_json::from_value::<WindowState>(value.clone()) {
+            saved.layouts.insert(
+                monitor_fingerprint(current),
+                SavedLayout {
+                    monitors: current.to_vec(),
+                    state,
+                },
+            );
+        }
+        saved
+    }
+
+    /// State saved for the current monitor layout, or for the saved layout sharing the
+    /// most monitors with it. None when no saved layout has any monitor in common.
+    fn closest(&self, current: &[MonitorBounds]) -> Option<&WindowState> {
+        if let Some(layout) = self.layouts.get(&monitor_fingerprint(current)) {
+            return Some(&layout.state);
+        }
+        self.layouts
+            .values()
+            .map(|layout| {
+                let shared = layout
+                    .monitors
+                    .iter()
+                    .filter(|m| current.contains(m))
+                    .count();
+                let extra = layout.monitors.len().abs_diff(current.len());
+                (shared, extra, layout)
+            })
+            .filter(|(shared, _, _)| *shared > 0)
+            .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+            .map(|(_, _, layout)| &layout.state)
+    }
+}
+
+/// Connected monitors in a stable order; empty when they can't be queried
+fn current_monitors(monitors: tauri::Result<Vec<tauri::Monitor>>) -> Vec<MonitorBounds> {
+    let mut bounds: Vec<MonitorBounds> = monitors
+        .unwrap_or_default()
+        .iter()
+        .map(|monitor| MonitorBounds {
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+        })
+        .collect();
+    bounds.sort();
+    bounds
+}
+
+/// Key identifying a monitor configuration, e.g. `1920x1080@0,0|2560x1440@1920,0`
+fn monitor_fingerprint(monitors: &[MonitorBounds]) -> String {
+    if monitors.is_empty() {
+        return "unknown".to_string();
+    }
+    monitors
+        .iter()
+        .map(|m| format!("{}x{}@{},{}", m.width, m.height, m.x, m.y))
+        .collect::<Vec<_>>()
+        .join("|")
 }
 
 impl WindowState {
@@ -85,6 +187,8 @@ impl Default for WindowState {
             x: Some(100.0),         // Logical position
             y: Some(100.0),         // Will be scaled appropriately
             scale_factor,
+            maximized: false,
+            fullscreen: false,
         }
     }
 }
@@ -124,11 +228,48 @@ impl WindowState {
             x: Some(logical_x),
             y: Some(logical_y),
             scale_factor: 1.0, // Will be updated with actual scale factor
+            maximized: false,
+            fullscreen: false,
         })
     }
 }
 
-fn validate_window_state(state: &WindowState, _window: Option<&WebviewWindow>) -> WindowState {
+/// `state` converted from physical to logical pixels using its own scale factor
+fn to_logical(state: &WindowState) -> WindowState {
+    let scale = if state.scale_factor > 0.0 {
+        state.scale_factor
+    } else {
+        1.0
+    };
+    WindowState {
+        width: state.width / scale,
+        height: state.height / scale,
+        x: state.x.map(|x| x / scale),
+        y: state.y.map(|y| y / scale),
+        ..state.clone()
+    }
+}
+
+/// Size to apply for a stored `state`. Positions stay physical in both modes since desktop
+/// coordinates span monitors with different scale factors. Logical mode sizes the window in
+/// logical pixels so it keeps its apparent size on whichever monitor it lands on.
+fn restore_size(state: &WindowState, sizing_mode: WindowSizingMode) -> tauri::Size {
+    match sizing_mode {
+        WindowSizingMode::Physical => tauri::Size::Physical(tauri::PhysicalSize {
+            width: state.width as u32,
+            height: state.height as u32,
+        }),
+        WindowSizingMode::Logical => {
+            let logical = to_logical(state);
+            tauri::Size::Logical(tauri::LogicalSize {
+                width: logical.width,
+                height: logical.height,
+            })
+        }
+    }
+}
+
+fn validate_window_state(state: &WindowState, window: Option<&WebviewWindow>) -> WindowState {
     let debug_enabled = get_dui_debug_mode();
     if debug_enabled {
         info!("[DEBUG] Validating window state:");
@@ -190,9 +331,111 @@ fn validate_window_state(state: &WindowState, _window: Option<&WebviewWindow>) -
     // Ensure valid scale factor
     validated.scale_factor = validated.scale_factor.max(1.0);
 
+    // A monitor the window was saved on may since have been disconnected
+    if let (Some(window), Some(_), Some(_)) = (window, validated.x, validated.y) {
+        if !is_on_any_monitor(&validated, window) {
+            center_on_primary_monitor(&mut validated, window);
+        }
+    }
+
     validated
 }
 
+/// Whether enough of the window is on some connected monitor to grab its title bar.
+/// Returns true when monitors can't be queried so we never move a window blindly.
+fn is_on_any_monitor(state: &WindowState, window: &WebviewWindow) -> bool {
+    let debug_enabled = get_dui_debug_mode();
+    // Logical pixels that must overlap a monitor in each direction
+    const MIN_VISIBLE: f64 = 50.0;
+
+    let (Some(x), Some(y)) = (state.x, state.y) else {
+        return true;
+    };
+    let monitors = match window.available_monitors() {
+        Ok(monitors) if !monitors.is_empty() => monitors,
+        _ => {
+            if debug_enabled {
+                info!("[DEBUG] No monitors available, skipping visibility check");
+            }
+            return true;
+        }
+    };
+
+    for (i, monitor) in monitors.iter().enumerate() {
+        let pos = monitor.position();
+        let size = monitor.size();
+        let (left, top) = (pos.x as f64, pos.y as f64);
+        let (right, bottom) = (left + size.width as f64, top + size.height as f64);
+        let overlap_x = (x + state.width).min(right) - x.max(left);
+        let overlap_y = (y + state.height).min(bottom) - y.max(top);
+        // Monitor bounds are physical pixels at the monitor's own scale factor
+        let min_visible = MIN_VISIBLE * monitor.scale_factor();
+        if debug_enabled {
+            info!(
+                "[DEBUG] Monitor {}: bounds=({},{})-({},{}), overlap={}x{}, required={}",
+                i, left, top, right, bottom, overlap_x, overlap_y, min_visible
+            );
+        }
+        if overlap_x >= min_visible && overlap_y >= min_visible {
+            if debug_enabled {
+                info!("[DEBUG] Window position ({},{}) is on monitor {}", x, y, i);
+            }
+            return true;
+        }
+    }
+
+    if debug_enabled {
+        info!(
+            "[DEBUG] Window position ({},{}) is not on any connected monitor",
+            x, y
+        );
+    }
+    false
+}
+
+/// Move `state` to the middle of the primary monitor, rescaling its size to that
+/// monitor's scale factor and shrinking it to fit if needed
+fn center_on_primary_monitor(state: &mut WindowState, window: &WebviewWindow) {
+    let debug_enabled = get_dui_debug_mode();
+    let monitor = window.primary_monitor().ok().flatten().or_else(|| {
+        window
+            .available_monitors()
+            .ok()
+            .and_then(|monitors| monitors.into_iter().next())
+    });
+    let Some(monitor) = monitor else {
+        if debug_enabled {
+            info!("[DEBUG] No primary monitor found, leaving position to the window manager");
+        }
+        state.x = None;
+        state.y = None;
+        return;
+    };
+
+    let pos = monitor.position();
+    let size = monitor.size();
+    let monitor_scale = monitor.scale_factor();
+    let rescale = monitor_scale / state.scale_factor;
+    state.width = (state.width * rescale).min(size.width as f64);
+    state.height = (state.height * rescale).min(size.height as f64);
+    state.x = Some(pos.x as f64 + (size.width as f64 - state.width) / 2.0);
+    state.y = Some(pos.y as f64 + (size.height as f64 - state.height) / 2.0);
+    state.scale_factor = monitor_scale;
+
+    if debug_enabled {
+        info!("[DEBUG] Re-centered window on primary monitor:");
+        info!(
+            "[DEBUG] - Monitor: pos=({},{}), size={}x{}, scale_factor={}",
+            pos.x, pos.y, size.width, size.height, monitor_scale
+        );
+        info!(
+            "[DEBUG] - Size (physical): {}x{}",
+            state.width, state.height
+        );
+        info!("[DEBUG] - Position (physical): {:?},{:?}", state.x, state.y);
+    }
+}
+
 #[tauri::command]
 pub async fn setup_window_state_handler(
     window_label: String,
@@ -214,6 +457,10 @@ pub fn setup_window_state_handler_internal(window: &WebviewWindow) {
             window.label()
         );
     }
+    TRACKED_WINDOWS
+        .lock()
+        .unwrap()
+        .insert(window.label().to_string());
     let window_clone = window.clone();
     window.on_window_event(move |event| {
         if debug_enabled {
@@ -266,23 +513,141 @@ pub fn setup_window_state_handler_internal(window: &WebviewWindow) {
                 }
                 save_window_state_internal(&window_clone, false);
             }
+            tauri::WindowEvent::CloseRequested { .. } => {
+                if debug_enabled {
+                    info!(
+                        "[DEBUG] Close requested, flushing state for window: {}",
+                        window_clone.label()
+                    );
+                }
+                cancel_pending_save();
+                do_save(&window_clone);
+            }
+            tauri::WindowEvent::Destroyed => {
+                TRACKED_WINDOWS.lock().unwrap().remove(window_clone.label());
+            }
             _ => {}
         }
     });
 }
 
+/// Abort a debounced save that hasn't run yet; callers save immediately instead
+fn cancel_pending_save() {
+    if let Some(save_handle) = SAVE_HANDLE.get() {
+        if let Ok(mut handle) = save_handle.try_lock() {
+            if let Some(h) = handle.take() {
+                h.abort();
+            }
+        }
+    }
+}
+
+/// Save every tracked window right away, bypassing the debounce. Called when the app is
+/// about to exit, when a pending debounced save would otherwise be dropped.
+pub fn save_all_window_states(app_handle: &tauri::AppHandle) {
+    let debug_enabled = get_dui_debug_mode();
+    cancel_pending_save();
+    let labels: Vec<String> = TRACKED_WINDOWS.lock().unwrap().iter().cloned().collect();
+    for label in labels {
+        if let Some(window) = app_handle.get_webview_window(&label) {
+            if debug_enabled {
+                info!("[DEBUG] Saving state on exit for window: {}", label);
+            }
+            do_save(&window);
+        }
+    }
+}
+
+/// Default geometry for `window_label` in physical pixels: the chat window sits next to
+/// the main window when it exists, everything else uses `WindowState::default()`
+fn default_window_state(window_label: &str, app_handle: &tauri::AppHandle) -> WindowState {
+    let debug_enabled = get_dui_debug_mode();
+    let actual_scale_factor = WindowState::get_system_scale_factor(Some(app_handle));
+    let main_window = app_handle.get_webview_window("main");
+
+    // For chat window, try to position relative to main window
+    let mut default_state = if window_label == "bb_chat" {
+        if debug_enabled {
+            info!(
+                "[DEBUG] Creating chat window state with scale factor: {}",
+                actual_scale_factor
+            );
+        }
+        if let Some(main_window) = &main_window {
+            if debug_enabled {
+                info!("[DEBUG] Calculating position relative to main window");
+            }
+            match WindowState::default_relative_to(main_window) {
+                Ok(state) => {
+                    if debug_enabled {
+                        info!("[DEBUG] Using relative position to main window");
+                    }
+                    state
+                }
+                Err(e) => {
+                    if debug_enabled {
+                        info!("[DEBUG] Failed to calculate relative position: {}", e);
+                    }
+                    WindowState::default()
+                }
+            }
+        } else {
+            if debug_enabled {
+                info!("[DEBUG] Main window not found, using default position");
+            }
+            WindowState::default()
+        }
+    } else {
+        if debug_enabled {
+            info!("[DEBUG] Using default window state");
+        }
+        WindowState::default()
+    };
+
+    // Update scale factor and adjust physical sizes
+    default_state.scale_factor = actual_scale_factor;
+    // Convert logical sizes to physical using actual scale factor
+    default_state.width *= actual_scale_factor;
+    default_state.height *= actual_scale_factor;
+    if let Some(x) = default_state.x {
+        default_state.x = Some(x * actual_scale_factor);
+    }
+    if let Some(y) = default_state.y {
+        default_state.y = Some(y * actual_scale_factor);
+    }
+    if debug_enabled {
+        info!(
+            "[DEBUG] Adjusted default state with scale factor {}:",
+            actual_scale_factor
+        );
+        info!(
+            "[DEBUG] - Physical size: {}x{}",
+            default_state.width, default_state.height
+        );
+        info!(
+            "[DEBUG] - Physical position: {:?},{:?}",
+            default_state.x, default_state.y
+        );
+    }
+
+    default_state
+}
+
 /// Load window state from storage
 ///
 /// # Arguments
 /// * `window_label` - The label of the window to load state for
 /// * `app_handle` - The Tauri app handle
-/// * `use_logical_size` - If true, returns values in logical pixels (scaled by DPI)
+/// * `use_logical_size` - If true, returns values in logical pixels (scaled by DPI); when
+///   omitted, follows `dui.windowSizingMode`
 #[tauri::command]
 pub async fn load_window_state(
     window_label: String,
     app_handle: tauri::AppHandle,
     use_logical_size: Option<bool>,
 ) -> Result<WindowState, String> {
+    let use_logical_size =
+        use_logical_size.unwrap_or_else(|| get_window_sizing_mode() == WindowSizingMode::Logical);
     let debug_enabled = get_dui_debug_mode();
     // Get the actual scale factor from the system
     let actual_scale_factor = WindowState::get_system_scale_factor(Some(&app_handle));
@@ -305,15 +670,30 @@ pub async fn load_window_state(
         format!("Failed to access store: {}", e)
     })?;
 
-    let state = store.get(&window_label);
+    let stored = store.get(&window_label);
     if debug_enabled {
         info!("[DEBUG] Looking for state with key: {}", window_label);
     }
 
     if debug_enabled {
-        info!("[DEBUG] Raw state from store: {:?}", state);
+        info!("[DEBUG] Raw state from store: {:?}", stored);
     }
 
+    let monitors = current_monitors(app_handle.available_monitors());
+    let state = stored.and_then(|stored| {
+        let saved = SavedWindowStates::from_stored(&stored, &monitors);
+        let state = saved.closest(&monitors).cloned();
+        if debug_enabled {
+            info!(
+                "[DEBUG] Monitor layout: {} ({} saved layouts, match: {})",
+                monitor_fingerprint(&monitors),
+                saved.layouts.len(),
+                state.is_some()
+            );
+        }
+        state
+    });
+
     // Try to get main window for relative positioning
     let main_window = app_handle.get_webview_window("main");
 
@@ -322,12 +702,6 @@ pub async fn load_window_state(
             if debug_enabled {
                 info!("[DEBUG] Found saved state in store: {:?}", state);
             }
-            if debug_enabled {
-                info!("[DEBUG] Deserializing JSON state:");
-                info!("[DEBUG] Raw JSON: {:?}", state);
-            }
-            let state: WindowState = serde_json::from_value(state.clone())
-                .map_err(|e| format!("Failed to parse state: {}", e))?;
             if debug_enabled {
                 info!("[DEBUG] Parsed window state:");
                 info!("[DEBUG] - Size: {}x{}", state.width, state.height);
@@ -336,15 +710,16 @@ pub async fn load_window_state(
             }
 
             // Validate state before returning
+            // Any window will do for querying monitors if this one isn't created yet
             let window = app_handle.get_webview_window(&window_label);
-            let validated = validate_window_state(&state, window.as_ref());
+            let validated = validate_window_state(&state, window.as_ref().or(main_window.as_ref()));
             if debug_enabled && validated != state {
                 info!("[DEBUG] State was adjusted for sanity:");
                 info!("[DEBUG] - Original: {:?}", state);
                 info!("[DEBUG] - Adjusted: {:?}", validated);
             }
             // Convert to logical values if requested
-            if use_logical_size.unwrap_or(false) {
+            if use_logical_size {
                 if debug_enabled {
                     info!(
                         "[DEBUG] Converting to logical values (scale_factor: {})",
@@ -379,13 +754,7 @@ pub async fn load_window_state(
                         );
                     }
                 }
-                let logical = WindowState {
-                    width: validated.width / validated.scale_factor,
-                    height: validated.height / validated.scale_factor,
-                    x: validated.x.map(|x| x / validated.scale_factor),
-                    y: validated.y.map(|y| y / validated.scale_factor),
-                    scale_factor: validated.scale_factor,
-                };
+                let logical = to_logical(&validated);
 
                 if debug_enabled {
                     info!("[DEBUG] Converted to logical values:");
@@ -408,78 +777,9 @@ pub async fn load_window_state(
                 info!("[DEBUG] No saved state found");
             }
 
-            // For chat window, try to position relative to main window
-            let mut default_state = if window_label == "bb_chat" {
-                if debug_enabled {
-                    info!(
-                        "[DEBUG] Creating chat window state with scale factor: {}",
-                        actual_scale_factor
-                    );
-                }
-                if debug_enabled {
-                    info!(
-                        "[DEBUG] Creating chat window state with scale factor: {}",
-                        actual_scale_factor
-                    );
-                }
-                if let Some(main_window) = &main_window {
-                    if debug_enabled {
-                        info!("[DEBUG] Calculating position relative to main window");
-                    }
-                    match WindowState::default_relative_to(main_window) {
-                        Ok(state) => {
-                            if debug_enabled {
-                                info!("[DEBUG] Using relative position to main window");
-                            }
-                            state
-                        }
-                        Err(e) => {
-                            if debug_enabled {
-                                info!("[DEBUG] Failed to calculate relative position: {}", e);
-                            }
-                            WindowState::default()
-                        }
-                    }
-                } else {
-                    if debug_enabled {
-                        info!("[DEBUG] Main window not found, using default position");
-                    }
-                    WindowState::default()
-                }
-            } else {
-                if debug_enabled {
-                    info!("[DEBUG] Using default window state");
-                }
-                WindowState::default()
-            };
+            let default_state = default_window_state(&window_label, &app_handle);
 
-            // Update scale factor and adjust physical sizes
-            default_state.scale_factor = actual_scale_factor;
-            // Convert logical sizes to physical using actual scale factor
-            default_state.width *= actual_scale_factor;
-            default_state.height *= actual_scale_factor;
-            if let Some(x) = default_state.x {
-                default_state.x = Some(x * actual_scale_factor);
-            }
-            if let Some(y) = default_state.y {
-                default_state.y = Some(y * actual_scale_factor);
-            }
-            if debug_enabled {
-                info!(
-                    "[DEBUG] Adjusted default state with scale factor {}:",
-                    actual_scale_factor
-                );
-                info!(
-                    "[DEBUG] - Physical size: {}x{}",
-                    default_state.width, default_state.height
-                );
-                info!(
-                    "[DEBUG] - Physical position: {:?},{:?}",
-                    default_state.x, default_state.y
-                );
-            }
-
-            let state = if use_logical_size.unwrap_or(false) {
+            let state = if use_logical_size {
                 if debug_enabled {
                     info!("[DEBUG] Converting default state to logical values:");
                     info!(
@@ -494,13 +794,7 @@ pub async fn load_window_state(
                         default_state.height / default_state.scale_factor
                     );
                 }
-                WindowState {
-                    width: default_state.width / default_state.scale_factor,
-                    height: default_state.height / default_state.scale_factor,
-                    x: default_state.x.map(|x| x / default_state.scale_factor),
-                    y: default_state.y.map(|y| y / default_state.scale_factor),
-                    scale_factor: default_state.scale_factor,
-                }
+                to_logical(&default_state)
             } else {
                 default_state
             };
@@ -569,6 +863,14 @@ fn save_window_state_internal(window: &WebviewWindow, force: bool) {
 fn do_save(window: &WebviewWindow) {
     let debug_enabled = get_dui_debug_mode();
 
+    // A minimized window reports a meaningless position (e.g. -32000,-32000 on Windows)
+    if window.is_minimized().unwrap_or(false) {
+        if debug_enabled {
+            info!("[DEBUG] Window {} is minimized, not saving", window.label());
+        }
+        return;
+    }
+
     // Get current window state in physical pixels
     let position = match window.outer_position() {
         Ok(pos) => pos,
@@ -584,9 +886,17 @@ fn do_save(window: &WebviewWindow) {
             return;
         }
     };
-    let scale_factor = window.scale_factor().unwrap_or(1.0);
     let current_monitor = window.current_monitor().ok().flatten();
     let monitor_ref = current_monitor.as_ref();
+    // Logical mode rescales by the saved factor on restore, so it has to be the one the
+    // size was measured at. Right after moving between monitors the window can still
+    // report the previous monitor's factor; the monitor it is on now is authoritative.
+    let scale_factor = match (get_window_sizing_mode(), monitor_ref) {
+        (WindowSizingMode::Logical, Some(monitor)) if monitor.scale_factor() > 0.0 => {
+            monitor.scale_factor()
+        }
+        _ => window.scale_factor().unwrap_or(1.0),
+    };
 
     if debug_enabled {
         info!("[DEBUG] ========== Monitor Detection ===========");
@@ -657,6 +967,8 @@ fn do_save(window: &WebviewWindow) {
         x: Some(position.x as f64),
         y: Some(position.y as f64),
         scale_factor,
+        maximized: window.is_maximized().unwrap_or(false),
+        fullscreen: window.is_fullscreen().unwrap_or(false),
     };
 
     // Validate state before saving
@@ -694,19 +1006,35 @@ fn do_save(window: &WebviewWindow) {
         info!("[DEBUG] - Scale factor: {}", validated_state.scale_factor);
     }
 
-    let state_json = json!({
-        "width": validated_state.width,
-        "height": validated_state.height,
-        "x": validated_state.x,
-        "y": validated_state.y,
-        "scale_factor": validated_state.scale_factor,
-    });
-
     match window.app_handle().store("bb-window-state.json") {
         Ok(store) => {
             let window_label = window.label().to_string();
+            let monitors = current_monitors(window.available_monitors());
+            let fingerprint = monitor_fingerprint(&monitors);
+            let mut saved = store
+                .get(&window_label)
+                .map(|stored| SavedWindowStates::from_stored(&stored, &monitors))
+                .unwrap_or_default();
+            let state = floating_state(
+                validated_state,
+                saved.layouts.get(&fingerprint).map(|layout| &layout.state),
+                || default_window_state(&window_label, window.app_handle()),
+            );
+            saved
+                .layouts
+                .insert(fingerprint.clone(), SavedLayout { monitors, state });
+            let state_json = match serde_json::to_value(&saved) {
+                Ok(json) => json,
+                Err(e) => {
+                    error!("Error serializing window state: {}", e);
+                    return;
+                }
+            };
             if debug_enabled {
-                info!("[DEBUG] Saving state to store with key: {}", window_label);
+                info!(
+                    "[DEBUG] Saving state to store with key: {} (monitor layout: {})",
+                    window_label, fingerprint
+                );
                 info!("[DEBUG] State being saved: {:?}", state_json);
             }
             store.set(window_label, state_json);
@@ -720,6 +1048,62 @@ fn do_save(window: &WebviewWindow) {
     }
 }
 
+/// The state to store for `current`. A maximized or fullscreen window reports the screen's
+/// geometry, so it keeps the floating geometry saved before (or the default when there is
+/// none) and un-maximizing after a restart returns to a normal window.
+fn floating_state(
+    current: WindowState,
+    previous: Option<&WindowState>,
+    default: impl FnOnce() -> WindowState,
+) -> WindowState {
+    if !current.maximized && !current.fullscreen {
+        return current;
+    }
+    let floating = previous.cloned().unwrap_or_else(default);
+    WindowState {
+        maximized: current.maximized,
+        fullscreen: current.fullscreen,
+        ..floating
+    }
+}
+
+/// Forget the stored geometry for `window_label` (every window when empty) and move the
+/// live window(s) back to their default position and size
+#[tauri::command]
+pub async fn reset_window_state(
+    window_label: String,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let debug_enabled = get_dui_debug_mode();
+    let store = app_handle
+        .store("bb-window-state.json")
+        .map_err(|e| format!("Failed to access store: {}", e))?;
+
+    let labels: Vec<String> = if window_label.is_empty() {
+        store.clear();
+        app_handle.webview_windows().into_keys().collect()
+    } else {
+        store.delete(&window_label);
+        vec![window_label]
+    };
+    store
+        .save()
+        .map_err(|e| format!("Failed to save store: {}", e))?;
+
+    for label in labels {
+        let Some(window) = app_handle.get_webview_window(&label) else {
+            continue;
+        };
+        let state = default_window_state(&label, &app_handle);
+        if debug_enabled {
+            info!("[DEBUG] Resetting window {} to {:?}", label, state);
+        }
+        apply_window_state_internal(&window, &state);
+    }
+    info!("Window state reset");
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn apply_window_state(
     window_label: String,
@@ -738,6 +1122,7 @@ pub fn apply_window_state_internal(window: &WebviewWindow, state: &WindowState)
     let current_monitor = window.current_monitor().ok().flatten();
     let current_scale = window.scale_factor().unwrap_or(1.0);
     let debug_enabled = get_dui_debug_mode();
+    let sizing_mode = get_window_sizing_mode();
 
     if debug_enabled {
         info!("[DEBUG] ========== Scale Factor Analysis ===========");
@@ -817,7 +1202,8 @@ pub fn apply_window_state_internal(window: &WebviewWindow, state: &WindowState)
         info!("[DEBUG] - Adjusted: {:?}", validated_state);
     }
 
-    // Set window position using physical pixels
+    // Restore the floating geometry first so the window maximizes on the monitor it was
+    // saved on and un-maximizing returns to its previous size
     if let (Some(x), Some(y)) = (validated_state.x, validated_state.y) {
         if debug_enabled {
             info!("[DEBUG] Setting window position (physical):");
@@ -837,19 +1223,13 @@ pub fn apply_window_state_internal(window: &WebviewWindow, state: &WindowState)
         }
     }
 
-    // Set window size using physical pixels
+    let size = restore_size(&validated_state, sizing_mode);
     if debug_enabled {
-        info!("[DEBUG] Setting window size (physical):");
-        info!(
-            "[DEBUG] - Size: {}x{}",
-            validated_state.width, validated_state.height
-        );
+        info!("[DEBUG] Setting window size ({:?}):", sizing_mode);
+        info!("[DEBUG] - Size: {:?}", size);
     }
 
-    if let Err(e) = window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-        width: validated_state.width as u32,
-        height: validated_state.height as u32,
-    })) {
+    if let Err(e) = window.set_size(size) {
         error!("Error setting window size: {}", e);
         if debug_enabled {
             info!("[DEBUG] Failed to set window size: {}", e);
@@ -857,4 +1237,116 @@ pub fn apply_window_state_internal(window: &WebviewWindow, state: &WindowState)
     } else if debug_enabled {
         info!("[DEBUG] Successfully set window size");
     }
+
+    if validated_state.fullscreen {
+        if debug_enabled {
+            info!("[DEBUG] Restoring fullscreen state");
+        }
+        if let Err(e) = window.set_fullscreen(true) {
+            error!("Error setting window fullscreen: {}", e);
+        }
+    } else if validated_state.maximized {
+        if debug_enabled {
+            info!("[DEBUG] Restoring maximized state");
+        }
+        if let Err(e) = window.maximize() {
+            error!("Error maximizing window: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved_at(scale_factor: f64) -> WindowState {
+        WindowState {
+            width: 2560.0,
+            height: 1600.0,
+            x: Some(300.0),
+            y: Some(150.0),
+            scale_factor,
+            maximized: true,
+            fullscreen: false,
+        }
+    }
+
+    fn floating(width: f64) -> WindowState {
+        WindowState {
+            width,
+            height: 800.0,
+            x: Some(100.0),
+            y: Some(100.0),
+            scale_factor: 1.0,
+            maximized: false,
+            fullscreen: false,
+        }
+    }
+
+    #[test]
+    fn maximized_window_without_saved_geometry_stores_default_size() {
+        let state = floating_state(saved_at(1.0), None, || floating(1200.0));
+
+        assert_eq!(state.width, 1200.0);
+        assert!(state.maximized);
+    }
+
+    #[test]
+    fn maximized_window_keeps_previous_floating_size() {
+        let previous = floating(1000.0);
+
+        let state = floating_state(saved_at(1.0), Some(&previous), || floating(1200.0));
+
+        assert_eq!(state.width, 1000.0);
+        assert!(state.maximized);
+    }
+
+    #[test]
+    fn floating_window_stores_its_own_size() {
+        let state = floating_state(floating(900.0), Some(&floating(1000.0)), || {
+            floating(1200.0)
+        });
+
+        assert_eq!(state, floating(900.0));
+    }
+
+    #[test]
+    fn to_logical_divides_size_and_position_by_scale_factor() {
+        let logical = to_logical(&saved_at(2.0));
+        assert_eq!(logical.width, 1280.0);
+        assert_eq!(logical.height, 800.0);
+        assert_eq!(logical.x, Some(150.0));
+        assert_eq!(logical.y, Some(75.0));
+        assert_eq!(logical.scale_factor, 2.0);
+        assert!(logical.maximized);
+    }
+
+    #[test]
+    fn to_logical_treats_missing_scale_factor_as_one() {
+        let logical = to_logical(&saved_at(0.0));
+        assert_eq!(logical.width, 2560.0);
+        assert_eq!(logical.height, 1600.0);
+        assert_eq!(logical.x, Some(300.0));
+    }
+
+    #[test]
+    fn physical_mode_restores_stored_pixels() {
+        match restore_size(&saved_at(2.0), WindowSizingMode::Physical) {
+            tauri::Size::Physical(size) => {
+                assert_eq!((size.width, size.height), (2560, 1600));
+            }
+            other => panic!("expected a physical size, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn logical_mode_restores_size_at_saved_scale_factor() {
+        match restore_size(&saved_at(1.5), WindowSizingMode::Logical) {
+            tauri::Size::Logical(size) => {
+                assert!((size.width - 2560.0 / 1.5).abs() < 1e-9);
+                assert!((size.height - 1600.0 / 1.5).abs() < 1e-9);
+            }
+            other => panic!("expected a logical size, got {:?}", other),
+        }
+    }
 }
@@ -92,6 +92,8 @@ export function ServerControl({ onStatusChange, onConnectionChange, onNavigate }
 			service_responds: false,
 			pid: null,
 			error: null,
+			state: 'stopped',
+			unresponsive_secs: null,
 		},
 		bui: {
 			pid_exists: false,
@@ -99,6 +101,8 @@ export function ServerControl({ onStatusChange, onConnectionChange, onNavigate }
 			service_responds: false,
 			pid: null,
 			error: null,
+			state: 'stopped',
+			unresponsive_secs: null,
 		},
 		all_services_ready: false,
 	});
@@ -118,6 +122,8 @@ export function ServerControl({ onStatusChange, onConnectionChange, onNavigate }
 	const [proxyInfo, setProxyInfo] = useState<ProxyInfo | null>(null);
 	const [isChatWindowOpen, setIsChatWindowOpen] = useState(false);
 	const [isVisible, setIsVisible] = useState(true);
+	// Seconds app startup waited for the services before giving up, if it did
+	const [startupTimeoutSecs, setStartupTimeoutSecs] = useState<number | null>(null);
 
 	// const [hasStartedBefore, setHasStartedBefore] = useState(() => {
 	// 	return localStorage.getItem('bb-server-started-before') === 'true';
@@ -160,7 +166,8 @@ export function ServerControl({ onStatusChange, onConnectionChange, onNavigate }
 			// Set proxy target based on debug mode
 			if (globalConfig?.api && !globalConfig.api.tls.useTls) {
 				try {
-					await setProxyTarget(direct);
+					// Built from local config, so the BUI is usually not up yet; skip the reachability check
+					await setProxyTarget(direct, true);
 					if (debugMode) console.info('[DEBUG] Set proxy target to:', direct);
 				} catch (err) {
 					console.error('Failed to set proxy target:', err);
@@ -222,6 +229,13 @@ export function ServerControl({ onStatusChange, onConnectionChange, onNavigate }
 		return () => document.removeEventListener('visibilitychange', handleVisibilityChange);
 	}, []);
 
+	// Startup may have stopped waiting for the services before this window loaded
+	useEffect(() => {
+		invoke<{ servicesTimeoutSecs: number | null }>('get_startup_status')
+			.then((startup) => setStartupTimeoutSecs(startup.servicesTimeoutSecs))
+			.catch((err) => console.error('Failed to get startup status:', err));
+	}, []);
+
 	// Reload chat window when server status changes (including TLS changes)
 	useEffect(() => {
 		const reloadChatWindow = () => {
@@ -490,7 +504,7 @@ export function ServerControl({ onStatusChange, onConnectionChange, onNavigate }
 					navigateToSettings();
 					return;
 				}
-				throw new Error(result.api.error || result.bui.error || 'Failed to start services');
+				throw new Error(result.api.error?.message || result.bui.error?.message || 'Failed to start services');
 			}
 
 			// Start proxy server if needed (when API TLS is disabled)
@@ -915,6 +929,12 @@ export function ServerControl({ onStatusChange, onConnectionChange, onNavigate }
 			</div>*/}
 
 			{/* Error Message */}
+			{startupTimeoutSecs !== null && !status.all_services_ready && !error && (
+				<div className='text-yellow-700 dark:text-yellow-400 text-center mb-4 p-2 bg-yellow-50 dark:bg-yellow-900/20 rounded'>
+					Services did not start within {startupTimeoutSecs}s and are still starting...
+				</div>
+			)}
+
 			{error && (
 				<div className='text-red-600 dark:text-red-400 text-center mb-4 p-2 bg-red-50 dark:bg-red-900/20 rounded'>
 					{error}
@@ -184,7 +184,7 @@ export function Settings(): JSX.Element {
 				} else {
 					console.error(
 						'Failed to start services:',
-						startResult.api.error || startResult.bui.error || 'Unknown error'
+						startResult.api.error?.message || startResult.bui.error?.message || 'Unknown error'
 					);
 				}
 			} else {
@@ -142,6 +142,8 @@ export function VersionUpgradePrompt(): JSX.Element {
         case 'downloading': return 'Downloading';
         case 'installing': return 'Installing';
         case 'backup': return 'Creating Backup';
+        case 'verifying': return 'Verifying Server';
+        case 'rolling-back': return 'Rolling Back';
         case 'upgrading-server': return 'Updating Server';
         case 'checking-dui': return 'Checking Application Updates';
         case 'downloading-dui': return 'Downloading Application Update';
@@ -1,9 +1,13 @@
+export type ServiceState = 'stopped' | 'running' | 'zombie';
+
 export interface ServiceStatus {
   pid_exists: boolean;
   process_responds: boolean;
   service_responds: boolean;
   pid: number | null;
   error: string | null;
+  state: ServiceState;
+  unresponsive_secs: number | null;
 }
 
 export interface ServerStatus {
@@ -41,11 +45,27 @@ export interface GlobalConfig {
   bui: BuiConfig;
 }
 
+export type BBErrorType =
+  | 'BinaryNotFound'
+  | 'PortInUse'
+  | 'PermissionDenied'
+  | 'NetworkError'
+  | 'ConfigError'
+  | 'ProcessError'
+  | 'Other';
+
+// Error from start_api, perform_upgrade, check_server_status and similar commands
+export interface BBError {
+  type: BBErrorType;
+  message: string;
+}
+
 export interface ServiceStartResult {
   success: boolean;
   pid: number | null;
-  error: string | null;
+  error: BBError | null;
   requires_settings: boolean;
+  startup_ms: number | null;
 }
 
 export interface ServerStartResult {
@@ -21,10 +21,14 @@ export interface VersionCompatibility {
   releaseNotes?: string;
   hasBreakingChanges?: boolean;
   criticalNotice?: string;
+  updateChannel: UpdateChannel;
+  isDowngrade: boolean;
 }
 
+export type UpdateChannel = 'stable' | 'beta';
+
 export interface InstallProgress {
-  stage: 'idle' | 'preparing' | 'downloading' | 'installing' | 'backup' | 'complete' | 'upgrading-server' | 'checking-dui' | 'downloading-dui' | 'installing-dui';
+  stage: 'idle' | 'preparing' | 'downloading' | 'installing' | 'backup' | 'verifying' | 'rolling-back' | 'complete' | 'upgrading-server' | 'checking-dui' | 'downloading-dui' | 'installing-dui';
   progress: number;
   message?: string;
 }
@@ -10,8 +10,9 @@ export async function startServer(): Promise<ServerStartResult> {
 			bui: {
 				success: false,
 				pid: null,
-				error: 'API failed to start, BUI not attempted',
+				error: { type: 'Other', message: 'API failed to start, BUI not attempted' },
 				requires_settings: false,
+				startup_ms: null,
 			},
 			all_services_ready: false,
 		};
@@ -24,7 +25,10 @@ export async function startServer(): Promise<ServerStartResult> {
 	// If BUI fails, stop API
 	if (!buiResult.success && apiResult.success) {
 		await stopServer();
-		buiResult.error = `BUI failed to start: ${buiResult.error}. API stopped.`;
+		buiResult.error = {
+			type: buiResult.error?.type ?? 'Other',
+			message: `BUI failed to start: ${buiResult.error?.message}. API stopped.`,
+		};
 	}
 
 	return {
@@ -3,6 +3,8 @@ import { invoke } from '@tauri-apps/api/core';
 export interface ProxyInfo {
     port: number;
     target: string;
+    fallback_targets: string[];
+    active_target: string;
     is_running: boolean;
 }
 
@@ -53,9 +55,9 @@ export async function setDebugMode(debug_mode: boolean): Promise<void> {
     return invoke('set_debug_mode', { debug_mode });
 }
 
-export async function setProxyTarget(target: string): Promise<void> {
-    console.debug('Setting proxy target to:', target);
-    return invoke('set_proxy_target', { target });
+export async function setProxyTarget(target: string, force = false): Promise<void> {
+    console.debug('Setting proxy target to:', target, force ? '(forced)' : '');
+    return invoke('set_proxy_target', { target, force });
 }
 
 // Helper function to determine if proxy should be used
@@ -9,6 +9,8 @@ export interface WindowState {
     x: number | null;
     y: number | null;
     scaleFactor: number;
+    maximized?: boolean;
+    fullscreen?: boolean;
 }
 
 // Default state matches Rust default
@@ -57,6 +59,19 @@ export async function saveWindowState(window: WebviewWindow, force: boolean = fa
     }
 }
 
+/**
+ * Forget stored window geometry and move the window(s) back to their default position and size
+ * @param windowName - The name/label of the window, or an empty string to reset all windows
+ */
+export async function resetWindowState(windowName: string = ''): Promise<void> {
+    try {
+        await invoke('reset_window_state', { windowLabel: windowName });
+    } catch (error) {
+        console.error('[ERROR] Failed to reset window state:', { window: windowName, error });
+        throw error;
+    }
+}
+
 /**
  * Set up window state handlers in Rust
  * @param window - The window to set up handlers for
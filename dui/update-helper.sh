@@ -7,8 +7,12 @@ APP_NAME="Beyond Better.app"
 TMP_UPDATE="$1"  # Path to downloaded update
 INSTALL_PATH="$2"  # Path to current app installation
 PID="$3"  # PID of current process to wait for
+READY_FILE="$4"  # Touched once running, so the app knows it is safe to exit
 
 echo "[UPDATE] Starting update helper..."
+if [ -n "$READY_FILE" ]; then
+    touch "$READY_FILE"
+fi
 echo "[UPDATE] Waiting for process $PID to exit..."
 
 # Wait for the main process to exit (max 30 seconds)
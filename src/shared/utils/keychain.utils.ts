@@ -0,0 +1,79 @@
+import { join } from '@std/path';
+import { logger } from 'shared/logger.ts';
+import { getGlobalConfigDir } from 'shared/dataDir.ts';
+
+// Must match APP_NAME and the keychain layout used by the desktop app (dui/src-tauri/src/keychain.rs)
+const KEYCHAIN_SERVICE = 'dev.beyondbetter.app';
+const KEYCHAIN_REF_PREFIX = 'keychain:';
+
+export function isKeychainRef(value: string | undefined): boolean {
+	return !!value && value.startsWith(KEYCHAIN_REF_PREFIX);
+}
+
+async function runHelper(cmd: string, args: string[], input?: string): Promise<Deno.CommandOutput> {
+	const command = new Deno.Command(cmd, {
+		args,
+		stdin: input === undefined ? 'null' : 'piped',
+		stdout: 'piped',
+		stderr: 'piped',
+	});
+	const child = command.spawn();
+	if (input !== undefined) {
+		const writer = child.stdin.getWriter();
+		await writer.write(new TextEncoder().encode(input));
+		await writer.close();
+	}
+	return await child.output();
+}
+
+async function loadSecret(account: string): Promise<string | undefined> {
+	const decode = (output: Deno.CommandOutput) =>
+		output.success ? new TextDecoder().decode(output.stdout).trim() || undefined : undefined;
+
+	switch (Deno.build.os) {
+		case 'darwin':
+			return decode(
+				await runHelper('security', ['find-generic-password', '-s', KEYCHAIN_SERVICE, '-a', account, '-w']),
+			);
+		case 'windows': {
+			// DPAPI-encrypted file written by the desktop app, readable only by this user
+			const path = join(await getGlobalConfigDir(), 'secrets', `${account}.dpapi`);
+			let encrypted: string;
+			try {
+				encrypted = await Deno.readTextFile(path);
+			} catch (_error) {
+				return undefined;
+			}
+			return decode(
+				await runHelper('powershell', [
+					'-NoProfile',
+					'-NonInteractive',
+					'-Command',
+					'$c = [Console]::In.ReadToEnd().Trim(); $ss = ConvertTo-SecureString $c; [Runtime.InteropServices.Marshal]::PtrToStringBSTR([Runtime.InteropServices.Marshal]::SecureStringToBSTR($ss))',
+				], encrypted),
+			);
+		}
+		default:
+			return decode(await runHelper('secret-tool', ['lookup', 'service', KEYCHAIN_SERVICE, 'account', account]));
+	}
+}
+
+/**
+ * Resolve an API key from config. The desktop app stores keys in the OS keychain and leaves a
+ * `keychain:<account>` reference in config.yaml; those are read from the keychain, falling back
+ * to `envVar` (which the desktop app sets when it starts bb-api). Plain keys are returned as-is.
+ */
+export async function resolveApiKey(configValue: string | undefined, envVar: string): Promise<string | undefined> {
+	if (configValue && !isKeychainRef(configValue)) return configValue;
+	if (configValue) {
+		const account = configValue.slice(KEYCHAIN_REF_PREFIX.length);
+		try {
+			const secret = await loadSecret(account);
+			if (secret) return secret;
+			logger.warn(`Keychain: no entry for ${account}`);
+		} catch (error) {
+			logger.warn(`Keychain: failed to read ${account}: ${(error as Error).message}`);
+		}
+	}
+	return Deno.env.get(envVar) || undefined;
+}